@@ -0,0 +1,125 @@
+// The boss is a giant computer-controlled snake that takes turns between
+// attack patterns
+// It only decides where it wants to go; the game steers it there safely
+
+use crate::game::Direction;
+use crate::locale::tr;
+use crate::snake::SnakeId;
+
+// How long the boss is when it appears
+pub const BOSS_LENGTH: i32 = 20;
+
+// Each food the player eats takes this many segments off the boss
+pub const BITE_DAMAGE: i32 = 2;
+
+// Surviving this many ticks beats the boss
+const FIGHT_TICKS: u32 = 600;
+
+// Each attack pattern lasts ATTACK_TICKS ticks before the boss switches
+const ATTACK_TICKS: u32 = 50;
+
+// Sweeps cross the board in rows SWEEP_SPACING apart
+const SWEEP_SPACING: i32 = 3;
+
+// Cut-offs aim this many tiles ahead of the player's head
+const CUT_OFF_LEAD: i32 = 4;
+
+// Things the boss can try
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Attack {
+    // Criss-cross the board row by row, fencing the player in
+    Sweep,
+    // Head for the tile the player is about to reach
+    CutOff,
+}
+
+impl Attack {
+    // Name shown in the HUD
+    pub fn name(self) -> String {
+        let key = match self {
+            Attack::Sweep => "attack.sweep",
+            Attack::CutOff => "attack.cut-off",
+        };
+        tr(key, &[])
+    }
+}
+
+#[derive(Clone)]
+pub struct Boss {
+    pub id: SnakeId,
+    pub attack: Attack,
+    pub fight_ticks_left: u32,
+    attack_ticks_left: u32,
+    sweep_row: i32,
+    sweep_right: bool,
+    sweep_down: bool,
+}
+
+impl Boss {
+    pub fn new(id: SnakeId, row: i32) -> Self {
+        Self {
+            id,
+            attack: Attack::Sweep,
+            fight_ticks_left: FIGHT_TICKS,
+            attack_ticks_left: ATTACK_TICKS,
+            sweep_row: row,
+            sweep_right: true,
+            sweep_down: false,
+        }
+    }
+
+    // Count down the fight and switch attacks when the current one is over
+    pub fn tick(&mut self) {
+        self.fight_ticks_left = self.fight_ticks_left.saturating_sub(1);
+        self.attack_ticks_left = self.attack_ticks_left.saturating_sub(1);
+        if self.attack_ticks_left == 0 {
+            self.attack_ticks_left = ATTACK_TICKS;
+            self.attack = match self.attack {
+                Attack::Sweep => Attack::CutOff,
+                Attack::CutOff => Attack::Sweep,
+            };
+        }
+    }
+
+    // Pick the tile the boss is heading for, given where its head is and
+    // where the player is going
+    pub fn target(
+        &mut self,
+        head: (i32, i32),
+        player: (i32, i32),
+        player_direction: Direction,
+        width: i32,
+        height: i32,
+    ) -> (i32, i32) {
+        match self.attack {
+            Attack::Sweep => {
+                let edge = if self.sweep_right { width - 1 } else { 0 };
+                if head.0 == edge {
+                    // Reached the side, so drop to the next row and come back
+                    self.sweep_right = !self.sweep_right;
+                    let step = if self.sweep_down {
+                        SWEEP_SPACING
+                    } else {
+                        -SWEEP_SPACING
+                    };
+                    let next = self.sweep_row + step;
+                    if next < 1 || next > height - 2 {
+                        self.sweep_down = !self.sweep_down;
+                        self.sweep_row -= step;
+                    } else {
+                        self.sweep_row = next;
+                    }
+                }
+                let edge = if self.sweep_right { width - 1 } else { 0 };
+                (edge, self.sweep_row.clamp(0, height - 1))
+            }
+            Attack::CutOff => {
+                let mut ahead = player;
+                for _ in 0..CUT_OFF_LEAD {
+                    ahead = player_direction.step(ahead.0, ahead.1);
+                }
+                (ahead.0.clamp(0, width - 1), ahead.1.clamp(0, height - 1))
+            }
+        }
+    }
+}
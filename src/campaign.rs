@@ -0,0 +1,161 @@
+// Campaigns are runs of levels played one after another, carrying the score
+// and lives from each level to the next
+//
+// Progress through a campaign is checkpointed after each level in a small
+// text file, one per campaign, so it can be picked up again later:
+//   level <number of the next level, from 1>
+//   lives <count>
+//   score <points>
+
+use crate::level::Level;
+use crate::locale::tr;
+use crate::nibbles;
+use crate::profile;
+use crate::win::WinCondition;
+use std::fs;
+use std::path::PathBuf;
+
+// Nibbles clears each arena after the food numbered 1 to 9
+const NIBBLES_FOOD: u32 = 9;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Campaign {
+    // The nine arenas of QBasic Nibbles, with numbered food
+    Nibbles,
+}
+
+impl Campaign {
+    pub const ALL: [Campaign; 1] = [Campaign::Nibbles];
+
+    pub fn name(self) -> String {
+        tr(&format!("campaign.{}", self.key()), &[])
+    }
+
+    pub fn key(self) -> &'static str {
+        match self {
+            Campaign::Nibbles => "nibbles",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Campaign::ALL.iter().copied().find(|c| c.key() == key)
+    }
+
+    pub fn levels(self) -> Vec<Level> {
+        match self {
+            Campaign::Nibbles => nibbles::levels(),
+        }
+    }
+
+    // What clears each level
+    pub fn win_condition(self) -> WinCondition {
+        match self {
+            Campaign::Nibbles => WinCondition::EatFood(NIBBLES_FOOD),
+        }
+    }
+
+    // Whether the campaign is played with numbered food
+    pub fn numbered_food(self) -> bool {
+        match self {
+            Campaign::Nibbles => true,
+        }
+    }
+}
+
+// Where a campaign run got to at the end of its last completed level
+pub struct Checkpoint {
+    pub level: usize, // the next level to play, counting from 0
+    pub lives: u32,
+    pub score: i32,
+}
+
+impl Checkpoint {
+    fn path(campaign: Campaign) -> Option<PathBuf> {
+        Some(profile::data_dir()?.join(format!("{}-checkpoint", campaign.key())))
+    }
+
+    // Read the campaign's checkpoint, if a run is under way
+    pub fn load(campaign: Campaign) -> Result<Option<Self>, String> {
+        let path = match Self::path(campaign) {
+            Some(path) if path.exists() => path,
+            _ => return Ok(None),
+        };
+        let text = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let checkpoint = Self::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+        if checkpoint.level >= campaign.levels().len() {
+            return Err(format!(
+                "{}: no level {}",
+                path.display(),
+                checkpoint.level + 1
+            ));
+        }
+        Ok(Some(checkpoint))
+    }
+
+    fn parse(text: &str) -> Result<Self, String> {
+        let mut level = None;
+        let mut lives = None;
+        let mut score = None;
+        for (i, line) in text.lines().enumerate() {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let bad = |setting| format!("line {}: {} must be a whole number", i + 1, setting);
+            match words.as_slice() {
+                [] => (),
+                ["level", n] => {
+                    level = Some(
+                        n.parse::<usize>()
+                            .ok()
+                            .filter(|&n| n > 0)
+                            .ok_or_else(|| bad("level"))?
+                            - 1,
+                    )
+                }
+                ["lives", n] => {
+                    lives = Some(
+                        n.parse()
+                            .ok()
+                            .filter(|&n| n > 0)
+                            .ok_or_else(|| bad("lives"))?,
+                    )
+                }
+                ["score", n] => score = Some(n.parse().map_err(|_| bad("score"))?),
+                _ => return Err(format!("line {}: unknown setting", i + 1)),
+            }
+        }
+        match (level, lives, score) {
+            (Some(level), Some(lives), Some(score)) => Ok(Self {
+                level,
+                lives,
+                score,
+            }),
+            _ => Err("checkpoint needs a level, lives and score".to_string()),
+        }
+    }
+
+    pub fn save(&self, campaign: Campaign) -> Result<(), String> {
+        let path = match Self::path(campaign) {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let text = format!(
+            "level {}\nlives {}\nscore {}\n",
+            self.level + 1,
+            self.lives,
+            self.score
+        );
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+        }
+        fs::write(&path, text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    // Forget the checkpoint once the run is over, won or lost
+    pub fn clear(campaign: Campaign) -> Result<(), String> {
+        match Self::path(campaign) {
+            Some(path) if path.exists() => {
+                fs::remove_file(&path).map_err(|e| format!("{}: {}", path.display(), e))
+            }
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,86 @@
+// Co-op mode, where two players share one snake and take turns steering it
+//
+// Control passes to the other player every few seconds, or every time the
+// snake eats. A warning flashes on the HUD before a timed handoff.
+
+use crate::win::TICKS_PER_SECOND;
+
+// How many players take turns
+const PLAYERS: usize = 2;
+
+// How long the warning flashes before a timed handoff
+const WARNING_TICKS: u32 = 2 * TICKS_PER_SECOND;
+
+// When control passes to the other player
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Handoff {
+    Seconds(u32),
+    Food,
+}
+
+impl Handoff {
+    // Parse a number of seconds, or food for every meal
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "food" => Some(Handoff::Food),
+            _ => s
+                .parse()
+                .ok()
+                .filter(|&seconds| seconds > 0)
+                .map(Handoff::Seconds),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct CoOp {
+    pub handoff: Handoff,
+    pub driver: usize,   // the player steering, counting from 0
+    pub ticks_left: u32, // before a timed handoff
+}
+
+impl CoOp {
+    pub fn new(handoff: Handoff) -> Self {
+        let mut co_op = Self {
+            handoff,
+            driver: 0,
+            ticks_left: 0,
+        };
+        co_op.restart_clock();
+        co_op
+    }
+
+    fn restart_clock(&mut self) {
+        if let Handoff::Seconds(seconds) = self.handoff {
+            self.ticks_left = seconds * TICKS_PER_SECOND;
+        }
+    }
+
+    // Count down a tick, given whether the snake ate on it, and pass control
+    // on when it's time
+    // Returns whether control changed hands
+    pub fn tick(&mut self, ate: bool) -> bool {
+        let due = match self.handoff {
+            Handoff::Seconds(_) => {
+                self.ticks_left = self.ticks_left.saturating_sub(1);
+                self.ticks_left == 0
+            }
+            Handoff::Food => ate,
+        };
+        if due {
+            self.driver = (self.driver + 1) % PLAYERS;
+            self.restart_clock();
+        }
+        due
+    }
+
+    // Whether a timed handoff is close enough to warn about
+    pub fn warning(&self) -> bool {
+        matches!(self.handoff, Handoff::Seconds(_)) && self.ticks_left <= WARNING_TICKS
+    }
+
+    // The player who steers next
+    pub fn next_driver(&self) -> usize {
+        (self.driver + 1) % PLAYERS
+    }
+}
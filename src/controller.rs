@@ -0,0 +1,60 @@
+// Controllers steer the first snake in place of a player, for games played
+// with --quiet
+//
+// They only see the game through what it lets anyone look at, the same as
+// any other bot would.
+
+use crate::game::{Direction, FoodKind, Game};
+use crate::replay::Replay;
+
+pub trait Controller {
+    // The way to point the snake before the next update, if it should turn
+    fn steer(&mut self, game: &Game) -> Option<Direction>;
+}
+
+// The controllers that can be picked by name
+pub fn from_key(key: &str) -> Option<Box<dyn Controller>> {
+    match key {
+        "greedy" => Some(Box::new(Greedy)),
+        _ => None,
+    }
+}
+
+// Heads for the nearest food that isn't poison, never stepping anywhere it
+// would die straight away if it can help it, and keeping on the way it's
+// going when nothing is better
+pub struct Greedy;
+
+impl Controller for Greedy {
+    fn steer(&mut self, game: &Game) -> Option<Direction> {
+        let (x, y) = game.segments(0).next()?;
+        let current = game.direction(0);
+        let food: Vec<(i32, i32)> = game
+            .food_positions()
+            .filter(|&(_, _, kind)| kind != FoodKind::Poison)
+            .map(|(x, y, _)| (x, y))
+            .collect();
+        let distance = |(x, y): (i32, i32)| {
+            food.iter()
+                .map(|&(fx, fy)| (fx - x).abs() + (fy - y).abs())
+                .min()
+                .unwrap_or(0)
+        };
+
+        game.directions()
+            .iter()
+            .copied()
+            .filter(|&direction| direction != current.opposite())
+            .filter_map(|direction| Some((direction, game.neighbor(x, y, direction)?)))
+            .filter(|&(_, (x, y))| game.is_cell_safe(x, y))
+            .min_by_key(|&(direction, to)| (distance(to), direction != current))
+            .map(|(direction, _)| direction)
+    }
+}
+
+// Plays a replay back turn by turn
+impl Controller for Replay {
+    fn steer(&mut self, game: &Game) -> Option<Direction> {
+        self.turn(game.ticks())
+    }
+}
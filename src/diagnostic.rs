@@ -0,0 +1,60 @@
+// Diagnostics add what a setting should have been to the messages about
+// files that couldn't be read, so they can be fixed without looking up
+// every valid value
+//
+// Files that only change how the game looks or scores, like themes and
+// scoring rules, are read as far as they can be: a line that's wrong is
+// warned about and left at its default. Levels, scripts and mods have to be
+// right to be played.
+
+// Words further than this many edits from every valid one aren't guessed at
+const MAX_EDITS: usize = 2;
+
+// A line that's wrong in a file read anyway, as it's shown
+pub fn warning(path: &str, problem: &str) -> String {
+    format!("{}: {}, so that line is left out", path, problem)
+}
+
+// Suggest the valid word closest to one that isn't, as something to put on
+// the end of a message, or nothing if none are close
+pub fn did_you_mean(word: &str, valid: &[&str]) -> String {
+    valid
+        .iter()
+        .map(|&candidate| (edits(word, candidate), candidate))
+        .filter(|&(edits, _)| edits <= MAX_EDITS.min(word.chars().count() / 2 + 1))
+        .min_by_key(|&(edits, _)| edits)
+        .map_or(String::new(), |(_, candidate)| {
+            format!(" (did you mean {}?)", candidate)
+        })
+}
+
+// Suggest the nearest whole number in a range to one outside it, or
+// nothing if it isn't a number at all
+pub fn nearest_in(value: &str, min: i64, max: i64) -> String {
+    match value.parse::<i64>() {
+        Ok(n) if n < min => format!(" (the nearest is {})", min),
+        Ok(n) if n > max => format!(" (the nearest is {})", max),
+        _ => String::new(),
+    }
+}
+
+// How many characters have to be added, removed or swapped for another to
+// turn one word into another
+fn edits(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == cb {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
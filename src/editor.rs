@@ -0,0 +1,335 @@
+// A cursor-driven editor for level files
+//
+// wasd moves the cursor and the other keys change the tile under it:
+//   #  wall            ~ !  traps
+//   p  portal end      o    snake start, again to turn it
+//   f  next food       x    clear the tile
+//   t  playtest        v    save         q  quit
+
+use crate::game::{Direction, Game, PortalId, Trap};
+use crate::layout::{self, Layout};
+use crate::level::{Level, PortalPair, Rotation, SnakeStart};
+use crate::locale::tr;
+use crate::snake::START_LENGTH;
+use crate::QUIT_KEYS;
+use crossterm::{
+    style::{self, Color},
+    terminal, QueueableCommand,
+};
+use std::io::{prelude::*, stdout};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+// Where levels without a file name are saved
+const DEFAULT_PATH: &str = "level.txt";
+
+// Size of a new level
+const NEW_WIDTH: i32 = 16;
+const NEW_HEIGHT: i32 = 16;
+
+// Playtests run at the normal game speed
+const PLAYTEST_TICK: Duration = Duration::from_millis(100);
+
+// How often to check whether the terminal has been resized
+const RESIZE_CHECK: Duration = Duration::from_millis(100);
+
+struct Editor {
+    level: Level,
+    path: String,
+    cursor: (i32, i32),
+    pending_portal: Option<(PortalId, (i32, i32))>, // a placed end waiting for its twin
+    message: String,
+}
+
+// Edit the level at `path`, or a new one if there's no file yet
+// Keys come from the game's input channel so playtests can share it
+pub fn run(path: Option<String>, keys: &Receiver<char>) -> Result<(), String> {
+    let path = path.unwrap_or_else(|| DEFAULT_PATH.to_string());
+    let level = if std::path::Path::new(&path).exists() {
+        Level::load(&path)?
+    } else {
+        Level::blank(NEW_WIDTH, NEW_HEIGHT)
+    };
+
+    let mut editor = Editor {
+        level,
+        path,
+        cursor: (0, 0),
+        pending_portal: None,
+        message: String::new(),
+    };
+
+    // Check for the terminal being resized while waiting on a key
+    let mut size = terminal::size().ok();
+    editor.draw();
+    loop {
+        let key = match keys.recv_timeout(RESIZE_CHECK) {
+            Ok(key) => key,
+            Err(RecvTimeoutError::Timeout) => {
+                if terminal::size().ok() != size {
+                    size = terminal::size().ok();
+                    editor.draw();
+                }
+                continue;
+            }
+            Err(RecvTimeoutError::Disconnected) => return Ok(()),
+        };
+        editor.message.clear();
+
+        match key {
+            'w' | 'a' | 's' | 'd' => editor.move_cursor(key),
+            '#' => {
+                editor.clear();
+                editor.level.walls.push(editor.cursor);
+            }
+            '~' | '!' => {
+                let trap = if key == '~' {
+                    Trap::Mirror
+                } else {
+                    Trap::Reverse
+                };
+                editor.clear();
+                editor.level.traps.push((editor.cursor, trap));
+            }
+            'p' => editor.place_portal(),
+            'o' => editor.place_snake(),
+            'f' => {
+                editor.clear();
+                editor.level.food.push(editor.cursor);
+            }
+            'x' => editor.clear(),
+            'v' => editor.save(),
+            't' => editor.playtest(keys),
+            'q' => return Ok(()),
+            _ => (),
+        }
+        editor.draw();
+    }
+}
+
+impl Editor {
+    fn move_cursor(&mut self, key: char) {
+        let direction = match key {
+            'w' => Direction::Up,
+            's' => Direction::Down,
+            'a' => Direction::Left,
+            _ => Direction::Right,
+        };
+        let (x, y) = direction.step(self.cursor.0, self.cursor.1);
+        if x >= 0 && x < self.level.width && y >= 0 && y < self.level.height {
+            self.cursor = (x, y);
+        }
+    }
+
+    // Remove whatever is under the cursor
+    // Clearing one end of a portal removes the whole pair
+    fn clear(&mut self) {
+        let pos = self.cursor;
+        self.level.walls.retain(|&wall| wall != pos);
+        self.level.traps.retain(|&(at, _)| at != pos);
+        self.level
+            .portals
+            .retain(|pair| pair.a != pos && pair.b != pos);
+        self.level.food.retain(|&food| food != pos);
+        if self.level.snake.is_some_and(|start| start.head == pos) {
+            self.level.snake = None;
+        }
+        if self.pending_portal.is_some_and(|(_, at)| at == pos) {
+            self.pending_portal = None;
+        }
+    }
+
+    // Place the first end of a new portal pair, or finish the pending one
+    fn place_portal(&mut self) {
+        match self.pending_portal {
+            Some((_, at)) if at == self.cursor => (),
+            Some((id, a)) => {
+                self.clear();
+                self.level.portals.push(PortalPair {
+                    id,
+                    a,
+                    b: self.cursor,
+                    rotation: Rotation::None,
+                });
+                self.pending_portal = None;
+            }
+            None => {
+                let id = (0..26).find(|&id| self.level.portals.iter().all(|pair| pair.id != id));
+                match id {
+                    Some(id) => {
+                        self.clear();
+                        self.pending_portal = Some((id, self.cursor));
+                    }
+                    None => self.message = tr("editor.portals-full", &[]),
+                }
+            }
+        }
+    }
+
+    // Start the snake here, or turn it a quarter if it already starts here
+    fn place_snake(&mut self) {
+        match &mut self.level.snake {
+            Some(start) if start.head == self.cursor => {
+                start.direction = start.direction.clockwise()
+            }
+            _ => {
+                self.clear();
+                self.level.snake = Some(SnakeStart {
+                    head: self.cursor,
+                    direction: Direction::Up,
+                    length: START_LENGTH,
+                });
+            }
+        }
+    }
+
+    fn save(&mut self) {
+        if self.pending_portal.is_some() {
+            self.message = tr("editor.portal-pending", &[]);
+            return;
+        }
+        // Don't save anything that couldn't be loaded again
+        if let Err(e) = Level::parse(&self.level.to_text()) {
+            self.message = e;
+            return;
+        }
+        self.message = match self.level.save(&self.path) {
+            Ok(()) => tr("editor.saved", &[&self.path]),
+            Err(e) => e,
+        };
+    }
+
+    // Play the level as it stands until the snake dies, or the playtest is
+    // quit
+    fn playtest(&mut self, keys: &Receiver<char>) {
+        let mut game = Game::from_level(&self.level);
+        let mut input = game.direction(0);
+        let mut quit = false;
+        crate::draw(&game);
+        while !game.finished() && !quit {
+            // Puzzles wait for each step, everything else runs in real time
+            if game.turn_based() {
+                let key = match keys.recv() {
+                    Ok(key) => key,
+                    Err(_) => break,
+                };
+                if QUIT_KEYS.contains(&key) {
+                    quit = true;
+                    break;
+                }
+                match crate::steer(key) {
+                    Some((0, direction)) => input = direction,
+                    _ => continue,
+                }
+                if game.set_direction(0, input).is_err() {
+                    continue;
+                }
+            } else {
+                while let Ok(key) = keys.try_recv() {
+                    quit |= QUIT_KEYS.contains(&key);
+                    if let Some((0, direction)) = crate::steer(key) {
+                        input = direction;
+                    }
+                }
+                if quit {
+                    break;
+                }
+                thread::sleep(PLAYTEST_TICK);
+            }
+            let _ = game.set_direction(0, game.input_transform(0).apply(input));
+            game.update();
+            crate::draw(&game);
+        }
+        if quit {
+            self.message = tr("editor.playtest-quit", &[]);
+            return;
+        }
+        thread::sleep(Duration::from_secs(1));
+
+        // Drop keys pressed while the playtest ended
+        while keys.try_recv().is_ok() {}
+        self.message = tr("editor.playtest-over", &[&game.score()]);
+    }
+
+    fn draw(&self) {
+        let border = format!("  {}", "--".repeat(self.level.width as usize));
+        let mut footer = vec![
+            format!("  {}  {},{}", self.path, self.cursor.0, self.cursor.1),
+            format!("  {}", tr("editor.keys", &[])),
+            format!("  {}", tr("editor.more-keys", &[])),
+        ];
+        if !self.message.is_empty() {
+            footer.push(format!("  {}", self.message));
+        }
+
+        let width = footer
+            .iter()
+            .map(|line| layout::text_width(line))
+            .fold(layout::text_width(&border) + 2, usize::max);
+        let height = self.level.height as usize + 2 + footer.len();
+        let mut layout = Layout::centered(width, height);
+
+        layout.print_row(&border);
+        for y in 0..self.level.height {
+            print!("| ");
+            for x in 0..self.level.width {
+                let glyph = self.glyph((x, y));
+                if (x, y) == self.cursor {
+                    stdout()
+                        .queue(style::SetBackgroundColor(Color::DarkGrey))
+                        .unwrap();
+                    print!("{}", glyph);
+                    stdout().queue(style::ResetColor).unwrap();
+                } else {
+                    print!("{}", glyph);
+                }
+            }
+            layout.print_row(" |");
+        }
+        layout.print_row(&border);
+
+        for line in &footer {
+            layout.print_row(line);
+        }
+        stdout().flush().unwrap();
+    }
+
+    fn glyph(&self, pos: (i32, i32)) -> String {
+        let level = &self.level;
+        if let Some(start) = level.snake.filter(|start| start.head == pos) {
+            return match start.direction {
+                Direction::Up => "^^",
+                Direction::Down => "vv",
+                Direction::Left => "<<",
+                Direction::Right => ">>",
+                Direction::UpLeft | Direction::DownRight => "\\\\",
+                Direction::UpRight | Direction::DownLeft => "//",
+            }
+            .to_string();
+        }
+        if let Some(pair) = level.portals.iter().find(|p| p.a == pos || p.b == pos) {
+            let letter = (b'A' + pair.id) as char;
+            return format!("{}{}", letter, letter);
+        }
+        if let Some((id, _)) = self.pending_portal.filter(|&(_, at)| at == pos) {
+            let letter = (b'A' + id) as char;
+            return format!("{}?", letter);
+        }
+        if let Some(i) = level.food.iter().position(|&food| food == pos) {
+            return format!("{:>2}", i + 1);
+        }
+        match level.traps.iter().find(|&&(at, _)| at == pos) {
+            Some((_, Trap::Mirror)) => return "~~".to_string(),
+            Some((_, Trap::Reverse)) => return "!!".to_string(),
+            None => (),
+        }
+        if level.walls.contains(&pos) {
+            "▒▒".to_string()
+        } else if level.obstacles.iter().any(|o| o.route().contains(&pos)) {
+            "::".to_string()
+        } else {
+            "  ".to_string()
+        }
+    }
+}
@@ -0,0 +1,79 @@
+// Events are what a game reports happening as it updates, so anything that
+// reacts to play can follow them instead of working out what changed itself
+
+use crate::game::{Direction, FoodKind};
+use crate::locale::tr;
+use crate::power_up::PowerUp;
+use crate::snake::SnakeId;
+use crate::win::WinCondition;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum GameEvent {
+    FoodEaten {
+        snake: SnakeId,
+        kind: FoodKind,
+        points: i32, // before any missions paid out for it
+    },
+    Moved {
+        snake: SnakeId,
+        x: i32,
+        y: i32,
+    },
+    Turned {
+        snake: SnakeId,
+        from: Direction,
+        to: Direction,
+    },
+    // Also sent when the snake has lives left and respawns
+    Died {
+        snake: SnakeId,
+        cause: DeathCause,
+    },
+    PowerUpStarted {
+        snake: SnakeId,
+        power_up: PowerUp,
+    },
+    PowerUpEnded {
+        snake: SnakeId,
+        power_up: PowerUp,
+    },
+    // The first snake met one of its win conditions
+    Won(WinCondition),
+}
+
+// What a snake died of
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum DeathCause {
+    Edge,          // left the board
+    Wall,          // walls and portals entered the wrong way
+    Body(SnakeId), // whose body it ran into, which can be its own
+    HeadOn,        // another head got to the same tile
+    Obstacle,      // ran into or was run over by one
+    Poison,        // ate poison without enough length to lose
+    Zone,          // caught by the battle royale walls
+    Beaten,        // the boss, once outlasted or outgrown
+}
+
+impl DeathCause {
+    // The name the cause goes by in results printed for scripts
+    pub fn key(self) -> &'static str {
+        match self {
+            DeathCause::Edge => "edge",
+            DeathCause::Wall => "wall",
+            DeathCause::Body(_) => "body",
+            DeathCause::HeadOn => "head-on",
+            DeathCause::Obstacle => "obstacle",
+            DeathCause::Poison => "poison",
+            DeathCause::Zone => "zone",
+            DeathCause::Beaten => "beaten",
+        }
+    }
+
+    // What happened, for the game over screen
+    pub fn describe(self, snake: SnakeId) -> String {
+        match self {
+            DeathCause::Body(owner) if owner == snake => tr("death.self", &[]),
+            cause => tr(&format!("death.{}", cause.key()), &[]),
+        }
+    }
+}
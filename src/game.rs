@@ -0,0 +1,3618 @@
+use crate::boss::{Boss, BITE_DAMAGE, BOSS_LENGTH};
+use crate::co_op::{CoOp, Handoff};
+use crate::event::{DeathCause, GameEvent};
+use crate::hooks::SharedHooks;
+use crate::json::Json;
+use crate::layout::{self, Layout};
+use crate::level::{Level, Rotation, SnakeStart};
+use crate::locale::tr;
+use crate::mission::Mission;
+use crate::obstacle::Obstacle;
+use crate::power_up::PowerUp;
+use crate::random_event::{EventScheduler, RandomEvent};
+use crate::score::{Mode, ScoreRules, COMBO_MAX};
+use crate::shop::{Upgrade, Waves};
+use crate::skin::Skin;
+use crate::snake::{Snake, SnakeId, START_LENGTH};
+use crate::theme::{Theme, CELL_WIDTH};
+use crate::topology::{self, Topology};
+use crate::win::{WinCondition, TICKS_PER_SECOND};
+use crossterm::style::{self, Color};
+use rand::{prelude::*, rngs::StdRng, thread_rng};
+use rand_chacha::ChaCha20Rng;
+use std::collections::HashMap;
+use std::io::{prelude::*, stdout};
+use std::rc::Rc;
+
+pub type SnakeVal = i32;
+pub type PortalId = u8;
+
+// How many segments poison takes off the snake
+const POISON_SHRINK: i32 = 3;
+
+// Bonus food has a 1 in BONUS_ODDS chance of showing up when food is eaten,
+// and only lasts BONUS_TTL ticks
+const BONUS_ODDS: u32 = 8;
+const BONUS_TTL: u32 = 30;
+
+// Prey tries to step away every PREY_MOVE_TICKS ticks once the head is
+// within PREY_ALERT_DISTANCE tiles
+const PREY_MOVE_TICKS: u32 = 3;
+const PREY_ALERT_DISTANCE: i32 = 6;
+
+// Power-ups have a 1 in POWER_UP_ODDS chance of showing up when food is
+// eaten, and stay on the board for POWER_UP_TTL ticks
+const POWER_UP_ODDS: u32 = 5;
+const POWER_UP_TTL: u32 = 50;
+
+// Shrink pickups take SHRINK_SEGMENTS off the tail. They only show up,
+// 1 in SHRINK_ODDS times food is eaten, once the snake is SHRINK_MIN_LENGTH
+// long, and last SHRINK_TTL ticks
+const SHRINK_SEGMENTS: i32 = 5;
+const SHRINK_ODDS: u32 = 12;
+const SHRINK_MIN_LENGTH: i32 = 12;
+const SHRINK_TTL: u32 = 40;
+
+// A respawned snake can't be killed by running into snakes or obstacles
+// for this many ticks
+const INVINCIBLE_TICKS: u32 = 15;
+
+// The far food policy keeps food at least this many tiles from the head
+const FAR_FOOD_DISTANCE: i32 = 6;
+
+// Expiring food blinks for this many ticks before it disappears
+const FOOD_BLINK_TICKS: u32 = 10;
+
+// During a meteor shower a wall lasting METEOR_TTL ticks drops every
+// METEOR_INTERVAL ticks, never within METEOR_CLEARANCE tiles of a head
+const METEOR_INTERVAL: u32 = 4;
+const METEOR_TTL: u32 = 30;
+const METEOR_CLEARANCE: i32 = 3;
+
+// A food frenzy puts this much extra food on the board
+const FRENZY_FOOD: usize = 5;
+
+// How much faster the game runs during a speed surge
+const SURGE_SPEED: f32 = 1.5;
+
+// In battle royale the walls close in one ring every ZONE_CLOSE_TICKS ticks
+// until the open middle is no more than ZONE_MIN_SIZE tiles across
+const ZONE_CLOSE_TICKS: u32 = 60;
+const ZONE_MIN_SIZE: i32 = 6;
+
+// Zooming in past this many rows a cell stops being useful
+const MAX_ZOOM_HEIGHT: usize = 4;
+
+// Popups about points scored stay up in the HUD for this many ticks
+const POPUP_TICKS: u32 = 15;
+
+// The next ring to close is marked for this many ticks beforehand
+const ZONE_WARNING_TICKS: u32 = 15;
+
+// The board containing the snakes and food
+#[derive(Clone)]
+pub struct Game {
+    width: i32,
+    height: i32,
+    tiles: Vec<Vec<Tile>>, // tiles[x][y]
+    portals: HashMap<(i32, i32), PortalExit>,
+    topology: Rc<dyn Topology>, // how the tiles join up
+    obstacles: Vec<Obstacle>,
+    snakes: Vec<Snake>,
+    poison_chance: f32,
+    prey_chance: f32,
+    food_policy: FoodPolicy,
+    food_ttl: Option<u32>,
+    food_timers: Vec<FoodTimer>,
+    nibbles: Option<u8>, // the number on the next food, in Nibbles mode
+    ticks: u32,
+    power_ups: bool,
+    fog: Option<i32>, // how far the first snake can see, if not the whole board
+    zone: Option<ZoneTimer>, // battle royale walls closing in
+    boss: Option<Boss>,
+    events: Option<EventScheduler>,
+    missions: Vec<Mission>,                 // the first snake's
+    food_sequence: Option<Vec<(i32, i32)>>, // fixed food still to come, last first
+    moves_left: Option<u32>,                // in puzzles
+    win_conditions: Vec<WinCondition>,      // the first snake's
+    won: Option<WinCondition>,              // the condition that was met
+    food_eaten: u32,                        // by the first snake
+    speed_curve: SpeedCurve,
+    waves: Option<Waves>, // survival mode's waves and shop
+    co_op: Option<CoOp>,  // two players taking turns on the first snake
+    skin: Skin,           // the first snake's
+    rules: ScoreRules,
+    popup: Option<(String, u32)>, // a message for the first player, with ticks left
+    theme: Theme,
+    zoom: Zoom,
+    controls: InputTransform, // scrambling that lasts the whole game
+    happened: Vec<GameEvent>, // on the last update
+    hooks: Vec<SharedHooks>,  // run in the order they were added
+    rng: ChaCha20Rng,         // seeded from the seed, so snapshots can pick up where it was
+    seed: u64,                // each food is drawn from this and how many came before
+    foods_served: u64,        // normal food spawned so far
+}
+
+// Ticks left before the food, power-up or meteor on a tile goes away
+#[derive(Clone)]
+struct FoodTimer {
+    x: i32,
+    y: i32,
+    ticks_left: u32,
+}
+
+// Where a snake entering a portal comes out, and how it is turned
+#[derive(Clone, Copy)]
+struct PortalExit {
+    x: i32,
+    y: i32,
+    rotation: Rotation,
+}
+
+// How far the battle royale walls have closed in, and when they next move
+#[derive(Clone)]
+struct ZoneTimer {
+    margin: i32, // rings of the board already walled off
+    ticks_left: u32,
+}
+
+impl Game {
+    // Create a world with the specified size
+    pub fn new(width: i32, height: i32) -> Self {
+        let mut new = Self::empty(width, height);
+
+        new.lay_body(0);
+        new.spawn_food();
+
+        new
+    }
+
+    // Create a world from a level's layout
+    pub fn from_level(level: &Level) -> Self {
+        let mut new = Self::empty(level.width, level.height);
+
+        for &(x, y) in &level.walls {
+            new.tiles[x as usize][y as usize] = Tile::Wall;
+        }
+
+        for &((x, y), trap) in &level.traps {
+            new.tiles[x as usize][y as usize] = Tile::Trap(trap);
+        }
+
+        for pair in &level.portals {
+            new.add_portal_pair(pair.id, pair.a, pair.b, pair.rotation);
+        }
+
+        new.obstacles = level.obstacles.clone();
+
+        match level.snake {
+            Some(start) => new.place_snake(0, start.head, start.direction, start.length),
+            None => new.lay_body(0),
+        }
+        if !level.food.is_empty() {
+            new.food_sequence = Some(level.food.iter().rev().copied().collect());
+            new.add_win_condition(WinCondition::ClearFood);
+        }
+        for &condition in &level.win {
+            new.add_win_condition(condition);
+        }
+        new.moves_left = level.moves;
+
+        new.spawn_food();
+
+        new
+    }
+
+    // A snapshot of the board, the snakes and the rules they play by
+    // Obstacles and the modes with state of their own (the boss, random
+    // events, missions, waves and co-op) aren't part of it, so games with
+    // them can't be saved from the command line. Nothing about how the game
+    // looks is part of it either.
+    pub fn to_json(&self) -> Json {
+        let field = |key: &str, value: Json| (key.to_string(), value);
+        let rows: Vec<Json> = (0..self.height)
+            .map(|y| {
+                let row: Vec<String> = (0..self.width)
+                    .map(|x| self.tiles[x as usize][y as usize].to_text())
+                    .collect();
+                row.into()
+            })
+            .collect();
+        let mut portals: Vec<(&(i32, i32), &PortalExit)> = self.portals.iter().collect();
+        portals.sort_by_key(|&(&at, _)| at);
+        let portals: Vec<Json> = portals
+            .into_iter()
+            .map(|(&(x, y), exit)| {
+                Json::Object(vec![
+                    field("at", Json::pos(x, y)),
+                    field("exit", Json::pos(exit.x, exit.y)),
+                    field("rotation", exit.rotation.key().into()),
+                ])
+            })
+            .collect();
+        let food_timers: Vec<Json> = self
+            .food_timers
+            .iter()
+            .map(|timer| {
+                Json::Object(vec![
+                    field("at", Json::pos(timer.x, timer.y)),
+                    field("ticks_left", timer.ticks_left.into()),
+                ])
+            })
+            .collect();
+        let zone = self.zone.as_ref().map(|zone| {
+            Json::Object(vec![
+                field("margin", zone.margin.into()),
+                field("ticks_left", zone.ticks_left.into()),
+            ])
+        });
+        let food_sequence = self.food_sequence.as_ref().map(|sequence| {
+            let sequence: Vec<Json> = sequence.iter().map(|&(x, y)| Json::pos(x, y)).collect();
+            Json::from(sequence)
+        });
+        let win_conditions: Vec<String> = self
+            .win_conditions
+            .iter()
+            .map(|condition| condition.to_text())
+            .collect();
+
+        Json::Object(vec![
+            field("width", self.width.into()),
+            field("height", self.height.into()),
+            field("tiles", rows.into()), // row by row from the top
+            field("portals", portals.into()),
+            field("topology", self.topology.key().into()),
+            field(
+                "snakes",
+                self.snakes
+                    .iter()
+                    .map(Snake::to_json)
+                    .collect::<Vec<_>>()
+                    .into(),
+            ),
+            field("poison_chance", self.poison_chance.into()),
+            field("prey_chance", self.prey_chance.into()),
+            field("food_policy", self.food_policy.key().into()),
+            field("food_ttl", self.food_ttl.into()),
+            field("food_timers", food_timers.into()),
+            field("nibbles", self.nibbles.map(u32::from).into()),
+            field("ticks", self.ticks.into()),
+            field("power_ups", self.power_ups.into()),
+            field("fog", self.fog.into()),
+            field("zone", zone.unwrap_or(Json::Null)),
+            field("food_sequence", food_sequence.unwrap_or(Json::Null)),
+            field("moves_left", self.moves_left.into()),
+            field("win_conditions", win_conditions.into()),
+            field("won", self.won.map(WinCondition::to_text).into()),
+            field("food_eaten", self.food_eaten.into()),
+            field(
+                "speed_curve",
+                Json::Object(vec![
+                    field("ramp", self.speed_curve.ramp.into()),
+                    field("max", self.speed_curve.max.into()),
+                ]),
+            ),
+            field("scoring", self.rules.to_json()),
+            // Too big to be sure of surviving as a JSON number
+            field("seed", self.seed.to_string().into()),
+            field("foods_served", self.foods_served.to_string().into()),
+            field("rng_words", self.rng.get_word_pos().to_string().into()),
+        ])
+    }
+
+    // Pick a game back up from a snapshot
+    pub fn from_json(json: &Json) -> Result<Self, String> {
+        let width = json.at("width", Json::as_i32)?;
+        let height = json.at("height", Json::as_i32)?;
+        if width <= 0 || height <= 0 {
+            return Err("the board must be at least 1x1".to_string());
+        }
+        let mut new = Self::empty(width, height);
+
+        let rows = json.at("tiles", |v| v.as_array().map(<[Json]>::to_vec))?;
+        if rows.len() != height as usize {
+            return Err(format!("tiles: expected {} rows", height));
+        }
+        for (y, row) in rows.iter().enumerate() {
+            let row = row
+                .as_array()
+                .map_err(|e| format!("tiles: row {}: {}", y + 1, e))?;
+            if row.len() != width as usize {
+                return Err(format!("tiles: row {}: expected {} tiles", y + 1, width));
+            }
+            for (x, tile) in row.iter().enumerate() {
+                new.tiles[x][y] = tile
+                    .as_str()
+                    .and_then(Tile::parse)
+                    .map_err(|e| format!("tiles: row {}: {}", y + 1, e))?;
+            }
+        }
+
+        for portal in json.at("portals", |v| v.as_array().map(<[Json]>::to_vec))? {
+            let rotation = portal.at("rotation", Json::as_string)?;
+            let exit = portal.at("exit", Json::as_pos)?;
+            new.portals.insert(
+                portal.at("at", Json::as_pos)?,
+                PortalExit {
+                    x: exit.0,
+                    y: exit.1,
+                    rotation: Rotation::parse(&rotation)
+                        .ok_or_else(|| format!("portals: unknown rotation {}", rotation))?,
+                },
+            );
+        }
+
+        let topology = json.at("topology", Json::as_string)?;
+        new.topology = topology::from_key(&topology, width, height)
+            .ok_or_else(|| format!("topology: unknown topology {}", topology))?;
+
+        new.snakes = json.at("snakes", |v| {
+            v.as_array()?.iter().map(Snake::from_json).collect()
+        })?;
+        if new.snakes.is_empty() || new.snakes.len() > SNAKE_COLORS.len() {
+            return Err(format!("snakes: there must be 1 to {}", SNAKE_COLORS.len()));
+        }
+
+        new.poison_chance = json.at("poison_chance", Json::as_f32)?;
+        new.prey_chance = json.at("prey_chance", Json::as_f32)?;
+        let policy = json.at("food_policy", Json::as_string)?;
+        new.food_policy = FoodPolicy::parse(&policy)
+            .ok_or_else(|| format!("food_policy: unknown policy {}", policy))?;
+        new.food_ttl = json.at_option("food_ttl", Json::as_u32)?;
+        new.food_timers = json.at("food_timers", |v| {
+            v.as_array()?
+                .iter()
+                .map(|timer| {
+                    let (x, y) = timer.at("at", Json::as_pos)?;
+                    let ticks_left = timer.at("ticks_left", Json::as_u32)?;
+                    Ok(FoodTimer { x, y, ticks_left })
+                })
+                .collect()
+        })?;
+        new.nibbles = json.at_option("nibbles", Json::as_u32)?.map(|n| n as u8);
+        new.ticks = json.at("ticks", Json::as_u32)?;
+        new.power_ups = json.at("power_ups", Json::as_bool)?;
+        new.fog = json.at_option("fog", Json::as_i32)?;
+        new.zone = json.at_option("zone", |zone| {
+            Ok(ZoneTimer {
+                margin: zone.at("margin", Json::as_i32)?,
+                ticks_left: zone.at("ticks_left", Json::as_u32)?,
+            })
+        })?;
+        new.food_sequence = json.at_option("food_sequence", |v| {
+            v.as_array()?.iter().map(Json::as_pos).collect()
+        })?;
+        new.moves_left = json.at_option("moves_left", Json::as_u32)?;
+        let condition = |v: &Json| {
+            let text = v.as_str()?;
+            WinCondition::parse(&text.split_whitespace().collect::<Vec<_>>())
+        };
+        new.win_conditions = json.at("win_conditions", |v| {
+            v.as_array()?.iter().map(condition).collect()
+        })?;
+        new.won = json.at_option("won", condition)?;
+        new.food_eaten = json.at("food_eaten", Json::as_u32)?;
+        new.speed_curve = json.at("speed_curve", |curve| {
+            Ok(SpeedCurve {
+                ramp: curve.at("ramp", Json::as_f32)?,
+                max: curve.at("max", Json::as_f32)?,
+            })
+        })?;
+        new.rules = json.at("scoring", ScoreRules::from_json)?;
+
+        let whole = |v: &Json| {
+            v.as_str()?
+                .parse::<u64>()
+                .map_err(|_| "expected a whole number in a string".to_string())
+        };
+        new.seed = json.at("seed", whole)?;
+        new.foods_served = json.at("foods_served", whole)?;
+        // The generator carries on from as many words into the seed's stream
+        // as had been drawn
+        let words = json.at("rng_words", |v| {
+            v.as_str()?
+                .parse::<u128>()
+                .map_err(|_| "expected a whole number in a string".to_string())
+        })?;
+        new.rng = seeded_rng(new.seed);
+        new.rng.set_word_pos(words);
+        new.check_snapshot()?;
+        Ok(new)
+    }
+
+    // Make sure everything a snapshot points at is on the board and in range,
+    // since the rest of the game takes that for granted
+    fn check_snapshot(&self) -> Result<(), String> {
+        let on_board = |what: &str, (x, y): (i32, i32)| {
+            if self.in_bounds(x, y) {
+                Ok(())
+            } else {
+                Err(format!("{}: {},{} is off the board", what, x, y))
+            }
+        };
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if let Tile::Snake(id, _) = self.tiles[x as usize][y as usize] {
+                    if id as usize >= self.snakes.len() {
+                        return Err(format!("tiles: row {}: there's no snake {}", y + 1, id));
+                    }
+                }
+            }
+        }
+        for snake in &self.snakes {
+            on_board("snakes: head", (snake.head_x, snake.head_y))?;
+            on_board("snakes: spawn", snake.spawn)?;
+            for &(x, y, _) in &snake.overlaps {
+                on_board("snakes: overlaps", (x, y))?;
+            }
+        }
+        for (&at, exit) in &self.portals {
+            on_board("portals: at", at)?;
+            on_board("portals: exit", (exit.x, exit.y))?;
+        }
+        for timer in &self.food_timers {
+            on_board("food_timers", (timer.x, timer.y))?;
+        }
+        for &pos in self.food_sequence.iter().flatten() {
+            on_board("food_sequence", pos)?;
+        }
+        Ok(())
+    }
+
+    // Create a world from a drawing of the board, one character per tile:
+    //   '.' or ' '  empty
+    //   '#'         wall
+    //   '*'         food
+    //   '@'         the snake's head
+    //   'o'         the snake's body
+    // Short rows are padded out with empty tiles. The body has to run in one
+    // line from the head, so it can't touch itself anywhere it would be
+    // unclear which way it goes, and the snake faces away from its neck.
+    // Random food is spawned if none is drawn.
+    pub fn from_ascii(text: &str) -> Result<Self, String> {
+        let rows: Vec<Vec<char>> = text
+            .trim_end_matches(['\n', '\r'])
+            .lines()
+            .map(|line| line.chars().collect())
+            .collect();
+        let width = rows.iter().map(Vec::len).max().unwrap_or(0) as i32;
+        let height = rows.len() as i32;
+        if width == 0 {
+            return Err("the board is empty".to_string());
+        }
+        let mut new = Self::empty(width, height);
+
+        let mut head = None;
+        let mut body = Vec::new();
+        let mut food = false;
+        for (y, row) in rows.iter().enumerate() {
+            let line_no = y + 1;
+            for (x, &c) in row.iter().enumerate() {
+                let pos = (x as i32, y as i32);
+                match c {
+                    '.' | ' ' => (),
+                    '#' => new.tiles[x][y] = Tile::Wall,
+                    '*' => {
+                        new.tiles[x][y] = Tile::Food(FoodKind::Normal);
+                        food = true;
+                    }
+                    '@' if head.is_some() => {
+                        return Err(format!(
+                            "line {}: the board can only have one head",
+                            line_no
+                        ))
+                    }
+                    '@' => head = Some(pos),
+                    'o' => body.push(pos),
+                    _ => return Err(format!("line {}: unknown tile '{}'", line_no, c)),
+                }
+            }
+        }
+        let head = head.ok_or("the board needs a head (@)")?;
+
+        // Follow the body from the head, one segment at a time
+        let mut segments = vec![head];
+        loop {
+            let &(x, y) = segments.last().unwrap();
+            let next: Vec<(i32, i32)> = Direction::ALL
+                .iter()
+                .map(|direction| direction.step(x, y))
+                .filter(|pos| body.contains(pos) && !segments.contains(pos))
+                .collect();
+            match next.as_slice() {
+                [] => break,
+                [pos] => segments.push(*pos),
+                _ => {
+                    return Err(format!(
+                        "line {}: can't tell which way the body goes from here",
+                        y + 1
+                    ))
+                }
+            }
+        }
+        if let Some(&(_, y)) = body.iter().find(|pos| !segments.contains(pos)) {
+            return Err(format!(
+                "line {}: body segment isn't joined to the head",
+                y + 1
+            ));
+        }
+
+        let direction = match segments.get(1) {
+            Some(&neck) => Direction::ALL
+                .iter()
+                .copied()
+                .find(|direction| direction.opposite().step(head.0, head.1) == neck)
+                .unwrap(),
+            None => Direction::Up,
+        };
+        let snake = &mut new.snakes[0];
+        snake.spawn = head;
+        snake.spawn_direction = direction;
+        snake.head_x = head.0;
+        snake.head_y = head.1;
+        snake.direction = direction;
+        snake.heading = direction;
+        snake.length = segments.len() as i32;
+        snake.start_length = snake.length;
+        for (val, &(x, y)) in (1..).zip(&segments) {
+            new.tiles[x as usize][y as usize] = Tile::Snake(0, val);
+        }
+
+        if !food {
+            new.spawn_food();
+        }
+        Ok(new)
+    }
+
+    // The board drawn the way from_ascii reads it, with one row per line
+    // Only the first snake is drawn. All food looks the same, and portals,
+    // traps, power-ups and the other snakes are left out.
+    pub fn to_ascii(&self) -> String {
+        let (width, height) = self.dimensions();
+        let mut rows: Vec<Vec<char>> = (0..height)
+            .map(|y| {
+                (0..width)
+                    .map(|x| match self.tile_at(x, y) {
+                        Some(Tile::Wall) => '#',
+                        Some(Tile::Food(_)) => '*',
+                        _ => '.',
+                    })
+                    .collect()
+            })
+            .collect();
+        for (i, (x, y)) in self.segments(0).enumerate() {
+            rows[y as usize][x as usize] = if i == 0 { '@' } else { 'o' };
+        }
+        rows.into_iter()
+            .map(|row| row.into_iter().collect::<String>() + "\n")
+            .collect()
+    }
+
+    fn empty(width: i32, height: i32) -> Self {
+        let seed = thread_rng().gen();
+        Self {
+            width,
+            height,
+            tiles: vec![vec![Tile::Empty; height as usize]; width as usize],
+            portals: HashMap::new(),
+            topology: topology::from_key(topology::DEFAULT_KEY, width, height).unwrap(),
+            obstacles: Vec::new(),
+            snakes: vec![Snake::new((width / 2, height / 2))],
+            poison_chance: 0.0,
+            prey_chance: 0.0,
+            food_policy: FoodPolicy::Uniform,
+            food_ttl: None,
+            food_timers: Vec::new(),
+            nibbles: None,
+            ticks: 0,
+            power_ups: false,
+            fog: None,
+            zone: None,
+            boss: None,
+            events: None,
+            missions: Vec::new(),
+            food_sequence: None,
+            moves_left: None,
+            win_conditions: Vec::new(),
+            won: None,
+            food_eaten: 0,
+            speed_curve: SpeedCurve::FLAT,
+            waves: None,
+            co_op: None,
+            skin: Skin::Classic,
+            rules: ScoreRules::default(),
+            popup: None,
+            theme: Theme::default(),
+            zoom: Zoom::DEFAULT,
+            controls: InputTransform::default(),
+            happened: Vec::new(),
+            hooks: Vec::new(),
+            rng: seeded_rng(seed),
+            seed,
+            foods_served: 0,
+        }
+    }
+
+    // Join the board's tiles up another way, laying out the snakes already on
+    // it again to follow
+    pub fn set_topology(&mut self, topology: Rc<dyn Topology>) {
+        self.topology = topology;
+        for id in 0..self.snakes.len() as SnakeId {
+            if self.snakes[id as usize].alive {
+                self.clear_body(id);
+                self.lay_body(id);
+            }
+        }
+    }
+
+    // Seed the game's randomness, so games set up the same way with the same
+    // seed get the same board and serve the same food in the same order
+    // Call it before anything else that adds to the board
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = seeded_rng(seed);
+        self.seed = seed;
+        self.foods_served = 0;
+
+        // Serve the first food again from the new seed
+        if self.food_sequence.is_none() {
+            for column in &mut self.tiles {
+                for tile in column {
+                    if matches!(tile, Tile::Food(kind) if kind.respawns()) {
+                        *tile = Tile::Empty;
+                    }
+                }
+            }
+            self.food_timers.clear();
+            self.spawn_food();
+        }
+    }
+
+    // A generator for the next food, which doesn't depend on what else the
+    // game has drawn, so boards sharing a seed keep serving the same food
+    fn food_rng(&self) -> StdRng {
+        StdRng::seed_from_u64(self.seed.wrapping_add(self.foods_served))
+    }
+
+    // An independent generator split off from the game's own
+    fn fork_rng(&mut self) -> StdRng {
+        StdRng::seed_from_u64(self.rng.gen())
+    }
+
+    // Replace the snakes with `count` new ones spread across the middle row
+    pub fn set_players(&mut self, count: usize) {
+        let count = count.clamp(1, SNAKE_COLORS.len());
+        self.snakes = (0..count)
+            .map(|i| {
+                let x = self.width * (i as i32 + 1) / (count as i32 + 1);
+                Snake::new((x, self.height / 2))
+            })
+            .collect();
+        for id in 0..count {
+            self.clear_body(id as SnakeId);
+            self.lay_body(id as SnakeId);
+        }
+    }
+
+    // Start a snake on a tile with its body laid out straight behind it
+    fn place_snake(&mut self, id: SnakeId, head: (i32, i32), direction: Direction, length: i32) {
+        let snake = &mut self.snakes[id as usize];
+        snake.spawn = head;
+        snake.spawn_direction = direction;
+        snake.head_x = head.0;
+        snake.head_y = head.1;
+        snake.direction = direction;
+        snake.heading = direction;
+        snake.length = length;
+        snake.start_length = length;
+        self.clear_body(id);
+        self.lay_body(id);
+    }
+
+    // Lay a snake's body out straight behind its head, cut short by anything
+    // in the way
+    fn lay_body(&mut self, id: SnakeId) {
+        let snake = &self.snakes[id as usize];
+        let (direction, length) = (snake.direction, snake.length);
+
+        // Segments are aged the same as after a tick, counting up from the head
+        let mut pos = Some((snake.head_x, snake.head_y));
+        for val in 1..=length {
+            let (x, y) = match pos {
+                Some((x, y)) if self.tile_at(x, y) == Some(Tile::Empty) => (x, y),
+                _ => break,
+            };
+            self.tiles[x as usize][y as usize] = Tile::Snake(id, val);
+            pos = self.topology.step((x, y), direction.opposite());
+        }
+    }
+
+    fn clear_body(&mut self, id: SnakeId) {
+        for column in &mut self.tiles {
+            for tile in column {
+                if let Tile::Snake(owner, _) = tile {
+                    if *owner == id {
+                        *tile = Tile::Empty;
+                    }
+                }
+            }
+        }
+    }
+
+    // Link two tiles as twin portals
+    // Entering `a` rotates the snake by `rotation`, entering `b` undoes it
+    fn add_portal_pair(&mut self, id: PortalId, a: (i32, i32), b: (i32, i32), rotation: Rotation) {
+        self.tiles[a.0 as usize][a.1 as usize] = Tile::Portal(id);
+        self.tiles[b.0 as usize][b.1 as usize] = Tile::Portal(id);
+        self.portals.insert(
+            a,
+            PortalExit {
+                x: b.0,
+                y: b.1,
+                rotation,
+            },
+        );
+        self.portals.insert(
+            b,
+            PortalExit {
+                x: a.0,
+                y: a.1,
+                rotation: rotation.inverse(),
+            },
+        );
+    }
+
+    // Scatter `count` portal pairs over empty tiles, keeping clear of the spawn columns
+    pub fn spawn_random_portals(&mut self, count: usize) {
+        let first_id = self.portals.len() / 2;
+        for i in 0..count {
+            let id = first_id + i;
+            if id >= PORTAL_GLYPHS.len() {
+                break;
+            }
+
+            let a = match self.random_spot_off_spawn() {
+                Some(a) => a,
+                None => break,
+            };
+            self.tiles[a.0 as usize][a.1 as usize] = Tile::Portal(id as PortalId);
+            match self.random_spot_off_spawn() {
+                Some(b) => self.add_portal_pair(id as PortalId, a, b, Rotation::None),
+                None => {
+                    self.tiles[a.0 as usize][a.1 as usize] = Tile::Empty;
+                    break;
+                }
+            }
+        }
+    }
+
+    // Turn roughly `density` (0.0 to 1.0) of the free tiles into walls
+    // Walls are only placed where every open tile stays reachable from the spawn
+    // points, and never near a spawn point or on an obstacle's route
+    pub fn scatter_walls(&mut self, density: f32) {
+        let mut candidates: Vec<(i32, i32)> = Vec::new();
+        for x in 0..self.width {
+            for y in 0..self.height {
+                if self.tiles[x as usize][y as usize] == Tile::Empty
+                    && !self.near_spawn(x, y)
+                    && !self.obstacles.iter().any(|o| o.route().contains(&(x, y)))
+                {
+                    candidates.push((x, y));
+                }
+            }
+        }
+
+        let target = (density.clamp(0.0, 1.0) * candidates.len() as f32).round() as usize;
+        candidates.shuffle(&mut self.rng);
+
+        let mut reachable = self.reachable_count();
+        let mut placed = 0;
+        for (x, y) in candidates {
+            if placed >= target {
+                break;
+            }
+
+            self.tiles[x as usize][y as usize] = Tile::Wall;
+            let now_reachable = self.reachable_count();
+            if now_reachable + 1 == reachable {
+                reachable = now_reachable;
+                placed += 1;
+            } else {
+                // This wall would cut part of the board off
+                self.tiles[x as usize][y as usize] = Tile::Empty;
+            }
+        }
+    }
+
+    // Whether a tile is within the clear zone around a spawn point and ahead of it
+    fn near_spawn(&self, x: i32, y: i32) -> bool {
+        self.snakes.iter().any(|snake| {
+            let (dx, dy) = self.topology.offset(snake.spawn, (x, y));
+            let around = dx.abs() <= 1 && dy.abs() <= 1;
+            let mut ahead = false;
+            let mut pos = Some(snake.spawn);
+            for _ in 0..4 {
+                pos = pos.and_then(|pos| self.topology.step(pos, snake.direction));
+                ahead |= pos == Some((x, y));
+            }
+            around || ahead
+        })
+    }
+
+    // Count the open tiles a snake could reach from the first spawn point
+    // Every spawn point sits on open ground, so this covers them all when the
+    // board is connected
+    fn reachable_count(&self) -> usize {
+        let start = self.snakes[0].spawn;
+        let mut seen = vec![vec![false; self.height as usize]; self.width as usize];
+        let mut stack = vec![start];
+        seen[start.0 as usize][start.1 as usize] = true;
+        let mut count = 0;
+
+        while let Some((x, y)) = stack.pop() {
+            count += 1;
+            for &direction in self.directions() {
+                let (nx, ny) = match self.advance(x, y, direction) {
+                    Some((nx, ny, _)) => (nx, ny),
+                    None => continue,
+                };
+                if !seen[nx as usize][ny as usize]
+                    && self.tiles[nx as usize][ny as usize] != Tile::Wall
+                    && !self.portals.contains_key(&(nx, ny))
+                {
+                    seen[nx as usize][ny as usize] = true;
+                    stack.push((nx, ny));
+                }
+            }
+        }
+
+        count
+    }
+
+    // An empty tile outside every spawn column, or `None` if there's none left
+    fn random_spot_off_spawn(&mut self) -> Option<(i32, i32)> {
+        let mut free = Vec::new();
+        for x in 0..self.width {
+            if self.snakes.iter().any(|snake| x == snake.spawn.0) {
+                continue;
+            }
+            for y in 0..self.height {
+                if self.tiles[x as usize][y as usize] == Tile::Empty {
+                    free.push((x, y));
+                }
+            }
+        }
+        free.choose(&mut self.rng).copied()
+    }
+
+    // Scatter `count` traps over empty tiles, away from the spawn points
+    pub fn spawn_random_traps(&mut self, count: usize) {
+        for _ in 0..count {
+            let trap = *[Trap::Mirror, Trap::Reverse].choose(&mut self.rng).unwrap();
+            let (x, y) = match self.random_spot_off_spawn() {
+                Some(spot) => spot,
+                None => break,
+            };
+            self.tiles[x as usize][y as usize] = Tile::Trap(trap);
+        }
+    }
+
+    // How a snake's input should be scrambled before it's passed to `set_direction`
+    pub fn input_transform(&self, snake: SnakeId) -> InputTransform {
+        self.snakes[snake as usize]
+            .input_transform()
+            .combined(self.controls)
+    }
+
+    // Scramble every snake's controls for the whole game, on top of any traps
+    pub fn set_controls(&mut self, controls: InputTransform) {
+        self.controls = controls;
+    }
+
+    // Set a snake's direction
+    // Returns an error if direction is opposite to current direction, or
+    // doesn't lead anywhere on this board
+    pub fn set_direction(&mut self, snake: SnakeId, direction: Direction) -> Result<(), ()> {
+        let direction = self.topology.turn(direction).ok_or(())?;
+        let current = self.direction(snake);
+        let snake = &mut self.snakes[snake as usize];
+        snake.direction = current;
+        snake.set_direction(direction)
+    }
+
+    // Set the chance (0.0 to 1.0) of poison appearing each time food spawns
+    pub fn set_poison_chance(&mut self, chance: f32) {
+        self.poison_chance = chance;
+    }
+
+    // Choose where new food is placed
+    pub fn set_food_policy(&mut self, policy: FoodPolicy) {
+        self.food_policy = policy;
+    }
+
+    // Set the chance (0.0 to 1.0) of new food being prey that runs from the snake
+    pub fn set_prey_chance(&mut self, chance: f32) {
+        self.prey_chance = chance;
+    }
+
+    // Switch to Nibbles-style numbered food
+    // Food counts up from 1 to 9 and grows the snake by its number
+    pub fn set_nibbles(&mut self, enabled: bool) {
+        self.nibbles = if enabled { Some(1) } else { None };
+        let kind = self.main_food_kind(&mut self.food_rng());
+
+        for column in &mut self.tiles {
+            for tile in column {
+                if let Tile::Food(existing) = tile {
+                    if existing.respawns() {
+                        *existing = kind;
+                    }
+                }
+            }
+        }
+    }
+
+    // Speed the game up along a curve as the first snake eats
+    pub fn set_speed_curve(&mut self, curve: SpeedCurve) {
+        self.speed_curve = curve;
+    }
+
+    pub fn speed_curve(&self) -> SpeedCurve {
+        self.speed_curve
+    }
+
+    // Make normal food despawn and move elsewhere after `ttl` ticks, or never if `None`
+    pub fn set_food_ttl(&mut self, ttl: Option<u32>) {
+        self.food_ttl = ttl;
+
+        // Restart the clock on food already on the board
+        self.food_timers.clear();
+        if let Some(ttl) = ttl {
+            for x in 0..self.width {
+                for y in 0..self.height {
+                    match self.tiles[x as usize][y as usize] {
+                        Tile::Food(kind) if kind.respawns() => self.food_timers.push(FoodTimer {
+                            x,
+                            y,
+                            ticks_left: ttl,
+                        }),
+                        _ => (),
+                    }
+                }
+            }
+        }
+    }
+
+    // Draw the board with a different set of glyphs
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+    }
+
+    pub fn zoom(&self) -> Zoom {
+        self.zoom
+    }
+
+    // Draw each board cell as a bigger or smaller block of characters
+    pub fn set_zoom(&mut self, zoom: Zoom) {
+        self.zoom = zoom;
+    }
+
+    // Change how many points everything is worth
+    pub fn set_score_rules(&mut self, rules: ScoreRules) {
+        self.rules = rules;
+    }
+
+    // Let the first snake win by meeting this, as well as any other conditions
+    pub fn add_win_condition(&mut self, condition: WinCondition) {
+        if !self.win_conditions.contains(&condition) {
+            self.win_conditions.push(condition);
+        }
+    }
+
+    // Pick up the first player's score and remaining lives from an earlier level
+    pub fn carry_over(&mut self, score: i32, lives: u32) {
+        self.snakes[0].score = score;
+        self.snakes[0].lives = lives;
+    }
+
+    // Give every player this many lives in total, respawning them until they run out
+    // The boss only ever gets one
+    pub fn set_lives(&mut self, lives: u32) {
+        let boss = self.boss.as_ref().map(|boss| boss.id);
+        for (id, snake) in self.snakes.iter_mut().enumerate() {
+            if Some(id as SnakeId) != boss {
+                snake.lives = lives.max(1);
+            }
+        }
+    }
+
+    // Hide everything further than `radius` tiles from the head, or nothing if `None`
+    pub fn set_fog(&mut self, radius: Option<i32>) {
+        self.fog = radius;
+    }
+
+    // Let power-ups appear on the board
+    pub fn set_power_ups(&mut self, enabled: bool) {
+        self.power_ups = enabled;
+    }
+
+    // Close the walls in one ring at a time until only the middle of the board is left
+    pub fn set_battle_royale(&mut self, enabled: bool) {
+        self.zone = if enabled {
+            Some(ZoneTimer {
+                margin: 0,
+                ticks_left: ZONE_CLOSE_TICKS,
+            })
+        } else {
+            None
+        };
+    }
+
+    // Make every player start each life `segments` longer
+    pub fn extend_start(&mut self, segments: i32) {
+        let boss = self.boss.as_ref().map(|boss| boss.id);
+        for (id, snake) in self.snakes.iter_mut().enumerate() {
+            if Some(id as SnakeId) != boss {
+                snake.start_length += segments;
+                snake.length += segments;
+            }
+        }
+        for id in 0..self.snakes.len() as SnakeId {
+            if Some(id) != boss {
+                self.clear_body(id);
+                self.lay_body(id);
+            }
+        }
+    }
+
+    // Start the first snake somewhere else, facing another way or at another
+    // length, keeping whatever is left as `None`
+    // Returns an error if its body wouldn't fit on the board from there
+    pub fn set_start(
+        &mut self,
+        head: Option<(i32, i32)>,
+        direction: Option<Direction>,
+        length: Option<i32>,
+    ) -> Result<(), String> {
+        let snake = &self.snakes[0];
+        let direction = direction.unwrap_or(snake.spawn_direction);
+        let start = SnakeStart {
+            head: head.unwrap_or(snake.spawn),
+            direction: self
+                .topology
+                .turn(direction)
+                .ok_or_else(|| format!("the snake can't head {} on this board", direction.key()))?,
+            length: length.unwrap_or(snake.start_length),
+        };
+        if start.length > self.width * self.height {
+            return Err(format!(
+                "the snake can't be longer than the board's {} tiles",
+                self.width * self.height
+            ));
+        }
+
+        // The body goes straight back from the head the way the board joins up
+        let mut body = Vec::new();
+        let mut pos = Some(start.head).filter(|&(x, y)| self.in_bounds(x, y));
+        for _ in 0..start.length {
+            match pos {
+                Some(tile) if !body.contains(&tile) => body.push(tile),
+                _ => break,
+            }
+            pos = pos.and_then(|pos| self.topology.step(pos, start.direction.opposite()));
+        }
+        let movable_food = self.food_sequence.is_none();
+        let fits = body.len() == start.length as usize
+            && body
+                .iter()
+                .all(|&(x, y)| match self.tiles[x as usize][y as usize] {
+                    Tile::Empty | Tile::Snake(0, _) => true,
+                    Tile::Food(_) => movable_food,
+                    _ => false,
+                });
+        if !fits {
+            return Err("the snake doesn't fit on the board from its start".to_string());
+        }
+
+        // Random food in the way is moved rather than eaten
+        let mut moved_food = 0;
+        for (x, y) in body {
+            if let Tile::Food(_) = self.tiles[x as usize][y as usize] {
+                self.tiles[x as usize][y as usize] = Tile::Empty;
+                moved_food += 1;
+            }
+        }
+        self.place_snake(0, start.head, start.direction, start.length);
+        for _ in 0..moved_food {
+            self.spawn_food();
+        }
+        Ok(())
+    }
+
+    // Add a giant computer-controlled snake that the first snake has to
+    // outlast or outgrow
+    pub fn set_boss(&mut self, enabled: bool) {
+        if let Some(boss) = self.boss.take() {
+            self.snakes.remove(boss.id as usize);
+        }
+        if !enabled {
+            return;
+        }
+
+        // Start in a bottom corner, out of the player's way
+        let spawn = (self.width / 4, self.height - 3);
+        let mut snake = Snake::new(spawn);
+        snake.length = BOSS_LENGTH;
+        self.boss = Some(Boss::new(self.snakes.len() as SnakeId, spawn.1));
+        self.snakes.push(snake);
+    }
+
+    // Have random events break out every so often
+    pub fn set_random_events(&mut self, enabled: bool) {
+        self.events = if enabled {
+            Some(EventScheduler::new(&mut self.rng))
+        } else {
+            None
+        };
+    }
+
+    // Give the first snake a few optional goals for bonus points
+    pub fn set_missions(&mut self, enabled: bool) {
+        self.missions = if enabled {
+            Mission::random_set(&mut self.rng)
+        } else {
+            Vec::new()
+        };
+    }
+
+    pub fn missions(&self) -> &[Mission] {
+        &self.missions
+    }
+
+    // Split the run into waves of food, with a shop break after each
+    pub fn set_waves(&mut self, enabled: bool) {
+        self.waves = if enabled {
+            Some(Waves::default())
+        } else {
+            None
+        };
+    }
+
+    pub fn waves(&self) -> Option<&Waves> {
+        self.waves.as_ref()
+    }
+
+    // Have two players share the first snake, taking turns to steer it
+    pub fn set_co_op(&mut self, handoff: Option<Handoff>) {
+        self.co_op = handoff.map(CoOp::new);
+    }
+
+    // The snake a player's keys steer, if any
+    // In co-op only the player whose turn it is steers
+    pub fn controller(&self, player: usize) -> Option<SnakeId> {
+        match &self.co_op {
+            Some(co_op) if co_op.driver == player => Some(0),
+            Some(_) => None,
+            None => Some(player as SnakeId),
+        }
+    }
+
+    // Change how the first snake looks
+    pub fn set_skin(&mut self, skin: Skin) {
+        self.skin = skin;
+    }
+
+    // Whether the game is paused for the shop between waves
+    pub fn shop_open(&self) -> bool {
+        self.waves.as_ref().is_some_and(|waves| waves.shop_open)
+    }
+
+    // Spend coins in the shop
+    // Returns an error if the shop isn't open or the upgrade can't be afforded
+    pub fn buy(&mut self, upgrade: Upgrade) -> Result<(), ()> {
+        self.waves.as_mut().ok_or(())?.buy(upgrade)
+    }
+
+    // Leave the shop and carry on with the next wave
+    pub fn close_shop(&mut self) {
+        if let Some(waves) = &mut self.waves {
+            waves.close_shop();
+        }
+    }
+
+    // How many times faster than normal the game should currently run
+    pub fn speed(&self) -> f32 {
+        let speed = self.speed_curve.at(self.food_eaten);
+        match &self.events {
+            Some(events) if events.running(RandomEvent::SpeedSurge) => speed * SURGE_SPEED,
+            _ => speed,
+        }
+    }
+
+    // How many snakes are played by someone, leaving out the boss
+    pub fn players(&self) -> usize {
+        self.snakes.len() - self.boss.is_some() as usize
+    }
+
+    // Whether the first snake is still in the game
+    pub fn alive(&self) -> bool {
+        self.snakes[0].alive
+    }
+
+    // The way a snake is facing
+    // Which way a snake is going, as the board steers it
+    pub fn direction(&self, snake: SnakeId) -> Direction {
+        let direction = self.snakes[snake as usize].direction;
+        self.topology.turn(direction).unwrap_or(direction)
+    }
+
+    // The ways snakes can go from a tile on this board
+    pub fn directions(&self) -> &'static [Direction] {
+        self.topology.directions()
+    }
+
+    // The first snake's score
+    pub fn score(&self) -> i32 {
+        self.snakes[0].score
+    }
+
+    // The first snake's lives, counting the one it's on
+    pub fn lives(&self) -> u32 {
+        self.snakes[0].lives
+    }
+
+    // The first snake's length
+    pub fn length(&self) -> i32 {
+        self.snakes[0].length
+    }
+
+    // How many updates the game has had
+    pub fn ticks(&self) -> u32 {
+        self.ticks
+    }
+
+    // Whether the game is over: the only snake has died, or at most one of
+    // several is left standing, or the level's food or moves have run out
+    pub fn finished(&self) -> bool {
+        if self.solved() || self.moves_left == Some(0) {
+            return true;
+        }
+        let alive = self.snakes.iter().filter(|snake| snake.alive).count();
+        if self.snakes.len() == 1 {
+            alive == 0
+        } else {
+            alive <= 1
+        }
+    }
+
+    // Whether the first snake has met one of its win conditions
+    pub fn solved(&self) -> bool {
+        self.won.is_some()
+    }
+
+    // The win condition the first snake met
+    pub fn won(&self) -> Option<WinCondition> {
+        self.won
+    }
+
+    fn met(&self, condition: WinCondition) -> bool {
+        let snake = &self.snakes[0];
+        match condition {
+            WinCondition::ReachLength(length) => snake.length >= length,
+            WinCondition::Score(score) => snake.score >= score,
+            WinCondition::Survive(seconds) => self.ticks >= seconds * TICKS_PER_SECOND,
+            WinCondition::ClearFood => {
+                self.food_sequence
+                    .as_ref()
+                    .is_some_and(|sequence| sequence.is_empty())
+                    && self.food_left() == 0
+            }
+            WinCondition::EatFood(food) => self.food_eaten >= food,
+        }
+    }
+
+    // Whether the game should only move on when the player steps, as in puzzles
+    pub fn turn_based(&self) -> bool {
+        self.moves_left.is_some()
+    }
+
+    // The last snake standing in a game between several
+    pub fn winner(&self) -> Option<SnakeId> {
+        if self.snakes.len() < 2 {
+            return None;
+        }
+        let mut alive = self.snakes.iter().enumerate().filter(|(_, s)| s.alive);
+        match (alive.next(), alive.next()) {
+            (Some((id, _)), None) => Some(id as SnakeId),
+            _ => None,
+        }
+    }
+
+    // The board's width and height in tiles
+    pub fn dimensions(&self) -> (i32, i32) {
+        (self.width, self.height)
+    }
+
+    // What's on a tile, or None off the board
+    pub fn tile_at(&self, x: i32, y: i32) -> Option<Tile> {
+        if self.in_bounds(x, y) {
+            Some(self.tiles[x as usize][y as usize])
+        } else {
+            None
+        }
+    }
+
+    // Every food on the board, where it is and what kind it is
+    pub fn food_positions(&self) -> impl Iterator<Item = (i32, i32, FoodKind)> + '_ {
+        (0..self.width)
+            .flat_map(move |x| (0..self.height).map(move |y| (x, y)))
+            .filter_map(move |(x, y)| match self.tiles[x as usize][y as usize] {
+                Tile::Food(kind) => Some((x, y, kind)),
+                _ => None,
+            })
+    }
+
+    // The tiles a snake's body covers, from its head to its tail, including
+    // any hidden under newer segments by ghosting
+    pub fn segments(&self, snake: SnakeId) -> impl Iterator<Item = (i32, i32)> {
+        let mut segments: Vec<(SnakeVal, i32, i32)> = self.snakes[snake as usize]
+            .overlaps
+            .iter()
+            .map(|&(x, y, val)| (val, x, y))
+            .collect();
+        for x in 0..self.width {
+            for y in 0..self.height {
+                if let Tile::Snake(owner, val) = self.tiles[x as usize][y as usize] {
+                    if owner == snake {
+                        segments.push((val, x, y));
+                    }
+                }
+            }
+        }
+        segments.sort_by_key(|&(val, ..)| val);
+        segments.into_iter().map(|(_, x, y)| (x, y))
+    }
+
+    // Where one step from a tile lands, through any portals, or None off the
+    // board
+    pub fn neighbor(&self, x: i32, y: i32, direction: Direction) -> Option<(i32, i32)> {
+        self.advance(x, y, direction).map(|(x, y, _)| (x, y))
+    }
+
+    // Whether a snake could move onto a tile without dying: on the board and
+    // clear of walls, snakes and obstacles
+    // Portals count as unsafe, since they're only safe entered the right way
+    pub fn is_cell_safe(&self, x: i32, y: i32) -> bool {
+        !self.obstacle_at(x, y)
+            && !matches!(
+                self.tile_at(x, y),
+                None | Some(Tile::Wall | Tile::Portal(_) | Tile::Snake(..))
+            )
+    }
+
+    // Add hooks to run around every update and food spawn from now on
+    pub fn add_hooks(&mut self, hooks: SharedHooks) {
+        self.hooks.push(hooks);
+    }
+
+    pub fn update(&mut self) {
+        self.happened.clear();
+        for hooks in self.hooks.clone() {
+            hooks.borrow_mut().before_tick(self);
+        }
+        self.tick();
+        for hooks in self.hooks.clone() {
+            hooks.borrow_mut().after_tick(self);
+        }
+    }
+
+    fn tick(&mut self) {
+        self.ticks += 1;
+        if let Some((_, ticks_left)) = &mut self.popup {
+            *ticks_left -= 1;
+            if *ticks_left == 0 {
+                self.popup = None;
+            }
+        }
+        if let Some(moves) = &mut self.moves_left {
+            *moves = moves.saturating_sub(1);
+        }
+        self.steer_boss();
+
+        // Work out where every head is going before moving any of them, so
+        // snakes reaching the same tile at once both lose
+        let mut moves = Vec::new();
+        for (id, snake) in self.snakes.iter().enumerate() {
+            if snake.alive {
+                let step = self.advance(snake.head_x, snake.head_y, snake.direction);
+                moves.push((id as SnakeId, step));
+            }
+        }
+        for &(id, step) in &moves {
+            let snake = &mut self.snakes[id as usize];
+            snake.invincible = snake.invincible.saturating_sub(1);
+            match step {
+                Some((x, y, direction)) => {
+                    let head_on = moves.iter().any(|&(other, other_step)| {
+                        other != id && other_step.is_some_and(|(ox, oy, _)| (ox, oy) == (x, y))
+                    });
+                    self.move_snake(id, x, y, direction, head_on);
+                }
+                None => self.die(id, DeathCause::Edge),
+            }
+        }
+
+        // Snakes still going earn points for surviving the tick
+        if self.rules.tick != 0 {
+            for id in 0..self.snakes.len() {
+                if self.snakes[id].alive {
+                    self.award(id as SnakeId, self.rules.tick);
+                }
+            }
+        }
+
+        if self.snakes[0].alive {
+            for mission in &mut self.missions {
+                if let Some(points) = mission.ticked(self.snakes[0].length) {
+                    self.snakes[0].score += points;
+                }
+            }
+        }
+
+        // Update the grid's snake values
+        for x in 0..self.width {
+            for y in 0..self.height {
+                if let Tile::Snake(id, val) = self.tiles[x as usize][y as usize] {
+                    self.tiles[x as usize][y as usize] = if val >= self.snakes[id as usize].length {
+                        Tile::Empty
+                    } else {
+                        Tile::Snake(id, val + 1)
+                    }
+                }
+            }
+        }
+        for snake in &mut self.snakes {
+            let length = snake.length;
+            snake.overlaps.retain(|&(_, _, val)| val < length);
+            for overlap in &mut snake.overlaps {
+                overlap.2 += 1;
+            }
+        }
+
+        // Ghosting running out while the body still overlaps itself is a collision
+        for id in 0..self.snakes.len() {
+            let snake = &mut self.snakes[id];
+            let ended = snake.tick_effects();
+            let overlapping =
+                snake.alive && !snake.has_effect(PowerUp::Ghost) && !snake.overlaps.is_empty();
+            let id = id as SnakeId;
+            for power_up in ended {
+                self.happened.push(GameEvent::PowerUpEnded {
+                    snake: id,
+                    power_up,
+                });
+            }
+            if overlapping {
+                self.die(id, DeathCause::Body(id));
+            }
+        }
+
+        self.tick_food_timers();
+
+        // Fixed food waits for its tile to be free
+        if self.food_sequence.is_some() && self.food_left() == 0 {
+            self.spawn_food();
+        }
+
+        if self.ticks.is_multiple_of(PREY_MOVE_TICKS) {
+            self.move_prey();
+        }
+
+        // Obstacles move after the snakes and kill any they walk into
+        let mut hit = Vec::new();
+        for obstacle in &mut self.obstacles {
+            obstacle.advance();
+            let (x, y) = obstacle.position();
+            if let Tile::Snake(id, _) = self.tiles[x as usize][y as usize] {
+                hit.push(id);
+            }
+        }
+        for id in hit {
+            if self.snakes[id as usize].invincible == 0 {
+                self.die(id, DeathCause::Obstacle);
+            }
+        }
+
+        self.tick_zone();
+        self.tick_events();
+
+        // The boss is beaten by surviving the fight or growing longer than it
+        if let Some(boss) = &mut self.boss {
+            boss.tick();
+            let id = boss.id;
+            let out_lasted = boss.fight_ticks_left == 0;
+            let out_grown = self.snakes[0].length > self.snakes[id as usize].length;
+            if self.snakes[0].alive && self.snakes[id as usize].alive && (out_lasted || out_grown) {
+                self.die(id, DeathCause::Beaten);
+            }
+        }
+
+        self.follow_missions();
+
+        if self.won.is_none() && self.snakes[0].alive {
+            self.won = self
+                .win_conditions
+                .iter()
+                .copied()
+                .find(|&condition| self.met(condition));
+            if let Some(condition) = self.won {
+                self.happened.push(GameEvent::Won(condition));
+            }
+        }
+
+        // Only food that comes back counts as a meal for handing over control
+        let ate = self.happened.iter().any(
+            |event| matches!(event, GameEvent::FoodEaten { snake: 0, kind, .. } if kind.respawns()),
+        );
+        if let Some(co_op) = &mut self.co_op {
+            if co_op.tick(ate) {
+                let turn = tr("popup.turn", &[&(co_op.driver + 1)]);
+                self.popup = Some((turn, POPUP_TICKS));
+            }
+        }
+    }
+
+    // Point the boss at whatever its current attack is after, without
+    // running into anything on the way if it can help it
+    fn steer_boss(&mut self) {
+        let id = match &self.boss {
+            Some(boss) if self.snakes[boss.id as usize].alive => boss.id,
+            _ => return,
+        };
+        let (player, boss) = (&self.snakes[0], &self.snakes[id as usize]);
+        let head = (boss.head_x, boss.head_y);
+        let current = self.direction(id);
+        let target = self.boss.as_mut().unwrap().target(
+            head,
+            (player.head_x, player.head_y),
+            player.direction,
+            self.width,
+            self.height,
+        );
+
+        let best = self
+            .directions()
+            .iter()
+            .copied()
+            .filter(|&direction| direction != current.opposite())
+            .filter_map(|direction| Some((direction, self.advance(head.0, head.1, direction)?)))
+            .filter(|&(_, (x, y, _))| self.is_cell_safe(x, y))
+            .min_by_key(|&(_, (x, y, _))| (x - target.0).abs() + (y - target.1).abs());
+
+        if let Some((direction, _)) = best {
+            let _ = self.snakes[id as usize].set_direction(direction);
+        }
+    }
+
+    // Give a snake points outside the scoring rules, never going below zero
+    pub fn add_points(&mut self, snake: SnakeId, points: i32) {
+        let snake = &mut self.snakes[snake as usize];
+        snake.score = (snake.score + points).max(0);
+    }
+
+    // Put bonus food somewhere risky, as if one had come up by itself
+    pub fn add_bonus(&mut self) {
+        self.spawn_bonus();
+    }
+
+    // Put down one food of the kind a food frenzy brings, lasting as long as
+    // a frenzy would
+    pub fn add_frenzy_food(&mut self) {
+        self.spawn_timed_item(
+            Tile::Food(FoodKind::Frenzy),
+            RandomEvent::FoodFrenzy.duration(),
+        );
+    }
+
+    // Put a wall that stays for good somewhere well away from every head
+    pub fn add_wall(&mut self) {
+        self.drop_wall();
+    }
+
+    // Show the first player a message for a moment
+    pub fn show_popup(&mut self, text: String) {
+        self.popup = Some((text, POPUP_TICKS));
+    }
+
+    // What happened on the last update, in the order it happened
+    pub fn happened(&self) -> &[GameEvent] {
+        &self.happened
+    }
+
+    // Keep the first snake's missions up to date with what it did this update
+    fn follow_missions(&mut self) {
+        for event in &self.happened {
+            match *event {
+                GameEvent::Turned { snake: 0, from, to } if to == from.clockwise().opposite() => {
+                    for mission in &mut self.missions {
+                        mission.turned_left();
+                    }
+                }
+                GameEvent::FoodEaten { snake: 0, kind, .. } => {
+                    for mission in &mut self.missions {
+                        if let Some(points) = mission.ate(kind) {
+                            self.snakes[0].score += points;
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
+    fn start_effect(&mut self, id: SnakeId, power_up: PowerUp) {
+        self.snakes[id as usize].start_effect(power_up);
+        self.happened.push(GameEvent::PowerUpStarted {
+            snake: id,
+            power_up,
+        });
+    }
+
+    // Move a snake's head to a new tile and deal with whatever is there
+    fn move_snake(&mut self, id: SnakeId, x: i32, y: i32, direction: Direction, head_on: bool) {
+        let snake = &mut self.snakes[id as usize];
+        if direction != snake.heading {
+            self.happened.push(GameEvent::Turned {
+                snake: id,
+                from: snake.heading,
+                to: direction,
+            });
+        }
+        self.happened.push(GameEvent::Moved { snake: id, x, y });
+        snake.head_x = x;
+        snake.head_y = y;
+        snake.direction = direction;
+        snake.heading = direction;
+        let invincible = snake.invincible > 0;
+        let ghost = snake.has_effect(PowerUp::Ghost);
+
+        // Die if the head ran into an obstacle or another head
+        if (self.obstacle_at(x, y) || head_on) && !invincible {
+            let cause = if head_on {
+                DeathCause::HeadOn
+            } else {
+                DeathCause::Obstacle
+            };
+            self.die(id, cause);
+            return;
+        }
+
+        // Food that gets eaten no longer needs its timer
+        self.food_timers
+            .retain(|timer| (timer.x, timer.y) != (x, y));
+
+        // Check for collision
+        let mut passing = false;
+        match self.tiles[x as usize][y as usize] {
+            Tile::Snake(owner, val) if owner == id && ghost => {
+                // Slip over our own body, remembering the segment underneath
+                self.snakes[id as usize].overlaps.push((x, y, val));
+            }
+            // Pass through without taking the tile, so the segment there is
+            // still cleared when its own snake's tail gets to it
+            Tile::Snake(..) if invincible => passing = true,
+            Tile::Snake(owner, _) => {
+                // Die if collided
+                self.die(id, DeathCause::Body(owner));
+                return;
+            }
+            Tile::Wall | Tile::Portal(_) => {
+                self.die(id, DeathCause::Wall);
+                return;
+            }
+            Tile::Food(kind) => {
+                if !self.eat(id, kind) {
+                    return;
+                }
+            }
+            Tile::Trap(trap) => {
+                // Traps spring once and are used up
+                self.start_effect(id, trap.effect());
+            }
+            Tile::PowerUp(power_up) => {
+                // Picking up an effect that's already running restarts it
+                self.start_effect(id, power_up);
+            }
+            Tile::Empty => (),
+        }
+
+        // Set head position to snake tile
+        if !passing {
+            self.tiles[x as usize][y as usize] = Tile::Snake(id, 0);
+        }
+
+        // Grow one segment at a time from numbered food
+        let snake = &mut self.snakes[id as usize];
+        if snake.growth > 0 {
+            snake.growth -= 1;
+            snake.length += 1;
+        }
+    }
+
+    // Feed a snake, returning whether it survived the meal
+    fn eat(&mut self, id: SnakeId, kind: FoodKind) -> bool {
+        let rules = &self.rules;
+        let snake = &mut self.snakes[id as usize];
+        let grown = (snake.length - snake.start_length).max(0);
+        let points = match kind {
+            FoodKind::Number(n) => {
+                snake.growth += n as i32;
+                self.nibbles = Some(n % 9 + 1);
+                rules.food * n as i32 + rules.length * grown
+            }
+            FoodKind::Prey => {
+                snake.length += 1;
+                rules.prey + rules.length * grown
+            }
+            FoodKind::Normal | FoodKind::Frenzy => {
+                snake.length += 1;
+                rules.food + rules.length * grown
+            }
+            FoodKind::Bonus => {
+                snake.length += 1;
+                rules.bonus + rules.length * grown
+            }
+            FoodKind::Poison => {
+                // Shrink, or die if there isn't enough snake left
+                if snake.length <= POISON_SHRINK {
+                    self.die(id, DeathCause::Poison);
+                    return false;
+                }
+                snake.length -= POISON_SHRINK;
+                rules.poison
+            }
+            FoodKind::Shrink => {
+                // Drop tail segments, but don't touch the score
+                snake.length = (snake.length - SHRINK_SEGMENTS).max(START_LENGTH);
+                0
+            }
+        };
+        let points = if points > 0 {
+            points * self.combo(id)
+        } else {
+            points
+        };
+        self.award(id, points);
+        if points > 0 {
+            self.risk_bonus(id);
+        }
+        self.happened.push(GameEvent::FoodEaten {
+            snake: id,
+            kind,
+            points,
+        });
+
+        if let (0, Some(waves)) = (id, &mut self.waves) {
+            if kind.respawns() {
+                waves.ate();
+            }
+        }
+
+        if id == 0 && kind.respawns() {
+            self.food_eaten += 1;
+        }
+
+        // Every bite the player takes hurts the boss
+        if let Some(boss) = &self.boss {
+            if id != boss.id && kind.respawns() {
+                let boss = &mut self.snakes[boss.id as usize];
+                boss.length = (boss.length - BITE_DAMAGE).max(START_LENGTH);
+            }
+        }
+
+        if kind.respawns() {
+            let length = self.snakes[id as usize].length;
+            self.spawn_food();
+            if !self.bonus_active() && self.rng.gen_range(0, BONUS_ODDS) == 0 {
+                self.spawn_bonus();
+            }
+            if self.power_ups && self.rng.gen_range(0, POWER_UP_ODDS) == 0 {
+                let power_up = *PowerUp::PICKUPS.choose(&mut self.rng).unwrap();
+                self.spawn_timed_item(Tile::PowerUp(power_up), POWER_UP_TTL);
+            }
+            if length >= SHRINK_MIN_LENGTH && self.rng.gen_range(0, SHRINK_ODDS) == 0 {
+                self.spawn_timed_item(Tile::Food(FoodKind::Shrink), SHRINK_TTL);
+            }
+        }
+
+        true
+    }
+
+    // Add points to a snake's score, never letting it go below zero
+    // Points gained are scaled by the multipliers for the modes being played
+    fn award(&mut self, id: SnakeId, points: i32) {
+        let mut multiplier = match (id, &self.waves) {
+            (0, Some(waves)) if points > 0 => waves.multiplier,
+            _ => 1,
+        };
+        if points > 0 {
+            for mode in Mode::ALL {
+                if self.playing(mode) {
+                    multiplier *= self.rules.multiplier(mode);
+                }
+            }
+        }
+        let snake = &mut self.snakes[id as usize];
+        snake.score = (snake.score + points * multiplier).max(0);
+    }
+
+    // Count a meal towards the snake's combo, returning the multiplier it earns
+    fn combo(&mut self, id: SnakeId) -> i32 {
+        let quick = self.combo_running(id);
+        let snake = &mut self.snakes[id as usize];
+        snake.combo = if quick {
+            (snake.combo + 1).min(COMBO_MAX)
+        } else {
+            1
+        };
+        snake.last_meal = Some(self.ticks);
+        snake.combo
+    }
+
+    // Whether food eaten now would keep the snake's combo going
+    fn combo_running(&self, id: SnakeId) -> bool {
+        let window = self.rules.combo;
+        self.snakes[id as usize]
+            .last_meal
+            .is_some_and(|tick| window > 0 && self.ticks - tick <= window)
+    }
+
+    // Reward eating with the head beside a wall or the snake's own body
+    fn risk_bonus(&mut self, id: SnakeId) {
+        let snake = &self.snakes[id as usize];
+        let head = (snake.head_x, snake.head_y);
+        let mut wall = false;
+        let mut body = false;
+        for &direction in self.directions() {
+            let (x, y) = match self.topology.step(head, direction) {
+                Some(pos) => pos,
+                None => {
+                    wall = true;
+                    continue;
+                }
+            };
+            match self.tiles[x as usize][y as usize] {
+                Tile::Wall => wall = true,
+                // The segment just behind the head is always there
+                Tile::Snake(owner, val) if owner == id && val > 1 => body = true,
+                _ => (),
+            }
+        }
+
+        let mut popup = Vec::new();
+        if wall && self.rules.wall_hug != 0 {
+            self.award(id, self.rules.wall_hug);
+            popup.push(tr("popup.wall-hug", &[&self.rules.wall_hug]));
+        }
+        if body && self.rules.close_call != 0 {
+            self.award(id, self.rules.close_call);
+            popup.push(tr("popup.close-call", &[&self.rules.close_call]));
+        }
+        if id == 0 && !popup.is_empty() {
+            self.popup = Some((popup.join("  "), POPUP_TICKS));
+        }
+    }
+
+    fn playing(&self, mode: Mode) -> bool {
+        match mode {
+            Mode::Nibbles => self.nibbles.is_some(),
+            Mode::BattleRoyale => self.zone.is_some(),
+            Mode::Boss => self.boss.is_some(),
+            Mode::Waves => self.waves.is_some(),
+            Mode::Puzzle => self.moves_left.is_some(),
+        }
+    }
+
+    // Lose a life, respawning if there are any left
+    // A shield charge takes the hit instead of a life
+    fn die(&mut self, id: SnakeId, cause: DeathCause) {
+        self.happened.push(GameEvent::Died { snake: id, cause });
+        let shielded = match (id, &mut self.waves) {
+            (0, Some(waves)) if waves.shields > 0 => {
+                waves.shields -= 1;
+                true
+            }
+            _ => false,
+        };
+        let snake = &mut self.snakes[id as usize];
+        if !shielded {
+            snake.lives = snake.lives.saturating_sub(1);
+        }
+        let out = snake.lives == 0;
+        if out {
+            snake.alive = false;
+
+            // A lone snake's body is left where it fell, but in a game between
+            // several it would only get in the way of the others
+            if self.snakes.len() == 1 {
+                return;
+            }
+        } else {
+            snake.respawn(INVINCIBLE_TICKS);
+        }
+
+        self.clear_body(id);
+        if !out {
+            self.clear_spawn(id);
+            self.lay_body(id);
+        }
+    }
+
+    // Move a respawning snake's head to the nearest free tile if something's
+    // in the way of its spawn point
+    fn clear_spawn(&mut self, id: SnakeId) {
+        let snake = &self.snakes[id as usize];
+        let spawn = (snake.head_x, snake.head_y);
+        let free = |(x, y): (i32, i32)| {
+            self.tiles[x as usize][y as usize] == Tile::Empty && !self.obstacle_at(x, y)
+        };
+        if free(spawn) {
+            return;
+        }
+        let nearest = (0..self.width)
+            .flat_map(|x| (0..self.height).map(move |y| (x, y)))
+            .filter(|&pos| free(pos))
+            .min_by_key(|&(x, y)| (x - spawn.0).abs() + (y - spawn.1).abs());
+        if let Some((x, y)) = nearest {
+            let snake = &mut self.snakes[id as usize];
+            snake.head_x = x;
+            snake.head_y = y;
+        }
+    }
+
+    // Wall off the next ring of the board when the battle royale timer runs out
+    fn tick_zone(&mut self) {
+        let margin = match self.closing_ring() {
+            Some(margin) => margin,
+            None => return,
+        };
+        let zone = self.zone.as_mut().unwrap();
+        zone.ticks_left = zone.ticks_left.saturating_sub(1);
+        if zone.ticks_left > 0 {
+            return;
+        }
+        zone.ticks_left = ZONE_CLOSE_TICKS;
+        zone.margin += 1;
+
+        let mut crushed = Vec::new();
+        let mut respawn_food = false;
+        for x in 0..self.width {
+            for y in 0..self.height {
+                if self.ring(x, y) != margin {
+                    continue;
+                }
+
+                match self.tiles[x as usize][y as usize] {
+                    Tile::Snake(id, _) => crushed.push(id),
+                    Tile::Food(kind) if kind.respawns() => respawn_food = true,
+                    Tile::Portal(_) => {
+                        // The twin would lead into the wall, so it goes too
+                        if let Some(exit) = self.portals.remove(&(x, y)) {
+                            self.portals.remove(&(exit.x, exit.y));
+                            self.tiles[exit.x as usize][exit.y as usize] = Tile::Empty;
+                        }
+                    }
+                    _ => (),
+                }
+                self.tiles[x as usize][y as usize] = Tile::Wall;
+                self.food_timers
+                    .retain(|timer| (timer.x, timer.y) != (x, y));
+            }
+        }
+
+        // Obstacles patrolling the closed ring would walk through the walls
+        let (width, height) = (self.width, self.height);
+        self.obstacles.retain(|obstacle| {
+            obstacle.route().iter().all(|&(x, y)| {
+                x > margin && y > margin && x < width - 1 - margin && y < height - 1 - margin
+            })
+        });
+
+        crushed.sort_unstable();
+        crushed.dedup();
+        for id in crushed {
+            if self.snakes[id as usize].alive {
+                self.die(id, DeathCause::Zone);
+            }
+        }
+        if respawn_food {
+            self.spawn_food();
+        }
+    }
+
+    // The ring the battle royale walls will close next, if they haven't
+    // finished closing in
+    fn closing_ring(&self) -> Option<i32> {
+        let zone = self.zone.as_ref()?;
+        let inner = self.width.min(self.height) - 2 * zone.margin;
+        if inner > ZONE_MIN_SIZE {
+            Some(zone.margin)
+        } else {
+            None
+        }
+    }
+
+    // Start any random event that's due, and keep the running one going
+    fn tick_events(&mut self) {
+        let started = match &mut self.events {
+            Some(events) => events.tick(&mut self.rng),
+            None => return,
+        };
+
+        if started == Some(RandomEvent::FoodFrenzy) {
+            for _ in 0..FRENZY_FOOD {
+                self.spawn_timed_item(
+                    Tile::Food(FoodKind::Frenzy),
+                    RandomEvent::FoodFrenzy.duration(),
+                );
+            }
+        }
+
+        let meteors_due = self.ticks.is_multiple_of(METEOR_INTERVAL)
+            && self
+                .events
+                .as_ref()
+                .is_some_and(|events| events.running(RandomEvent::MeteorShower));
+        if meteors_due {
+            self.drop_meteor();
+        }
+    }
+
+    // Drop a temporary wall on an empty tile well away from every head
+    fn drop_meteor(&mut self) {
+        if let Some((x, y)) = self.drop_wall() {
+            self.food_timers.push(FoodTimer {
+                x,
+                y,
+                ticks_left: METEOR_TTL,
+            });
+        }
+    }
+
+    // Put a wall on an empty tile well away from every head, and return
+    // where it went
+    fn drop_wall(&mut self) -> Option<(i32, i32)> {
+        let mut open = Vec::new();
+        for x in 0..self.width {
+            for y in 0..self.height {
+                if self.tiles[x as usize][y as usize] == Tile::Empty
+                    && !self.obstacle_at(x, y)
+                    && self.distance_to_nearest_head(x, y) > METEOR_CLEARANCE
+                {
+                    open.push((x, y));
+                }
+            }
+        }
+
+        let &(x, y) = open.choose(&mut self.rng)?;
+        self.tiles[x as usize][y as usize] = Tile::Wall;
+        Some((x, y))
+    }
+
+    // How many rings in from the edge of the board a tile is
+    fn ring(&self, x: i32, y: i32) -> i32 {
+        x.min(y).min(self.width - 1 - x).min(self.height - 1 - y)
+    }
+
+    // Take one step from a tile, following portals at most once per pair
+    // Returns where the step lands and the direction it ends up facing, or
+    // None if it runs off the board
+    fn advance(&self, x: i32, y: i32, direction: Direction) -> Option<(i32, i32, Direction)> {
+        let (mut x, mut y) = self.topology.step((x, y), direction)?;
+        let mut direction = direction;
+
+        for _ in 0..=self.portals.len() / 2 {
+            let exit = match self.portals.get(&(x, y)) {
+                Some(exit) => *exit,
+                None => break,
+            };
+            direction = exit.rotation.apply(direction);
+            (x, y) = self.topology.step((exit.x, exit.y), direction)?;
+        }
+
+        Some((x, y, direction))
+    }
+
+    fn obstacle_at(&self, x: i32, y: i32) -> bool {
+        self.obstacles.iter().any(|o| o.position() == (x, y))
+    }
+
+    fn in_bounds(&self, x: i32, y: i32) -> bool {
+        x >= 0 && x < self.width && y >= 0 && y < self.height
+    }
+
+    // Manhattan distance from a tile to the nearest living snake's head
+    fn distance_to_nearest_head(&self, x: i32, y: i32) -> i32 {
+        self.snakes
+            .iter()
+            .filter(|snake| snake.alive)
+            .map(|snake| {
+                let (dx, dy) = self.topology.offset((snake.head_x, snake.head_y), (x, y));
+                dx.abs() + dy.abs()
+            })
+            .min()
+            .unwrap_or(i32::MAX)
+    }
+
+    // Place an item that disappears after `ttl` ticks
+    fn spawn_timed_item(&mut self, item: Tile, ttl: u32) {
+        let mut rng = self.fork_rng();
+        if let Some((x, y)) = self.place_item(item, &mut rng) {
+            self.food_timers.push(FoodTimer {
+                x,
+                y,
+                ticks_left: ttl,
+            });
+        }
+    }
+
+    // Count down expiring items, moving any food that runs out
+    fn tick_food_timers(&mut self) {
+        let mut expired = Vec::new();
+        for timer in &mut self.food_timers {
+            timer.ticks_left = timer.ticks_left.saturating_sub(1);
+            if timer.ticks_left == 0 {
+                expired.push((timer.x, timer.y));
+            }
+        }
+        self.food_timers.retain(|timer| timer.ticks_left > 0);
+
+        for (x, y) in expired {
+            match self.tiles[x as usize][y as usize] {
+                Tile::Food(kind) => {
+                    self.tiles[x as usize][y as usize] = Tile::Empty;
+                    if kind.respawns() {
+                        self.spawn_food();
+                    }
+                }
+                Tile::PowerUp(_) | Tile::Wall => self.tiles[x as usize][y as usize] = Tile::Empty,
+                _ => (),
+            }
+        }
+    }
+
+    // Ticks left on the food at a tile, if it expires
+    fn food_ticks_left(&self, x: i32, y: i32) -> Option<u32> {
+        self.food_timers
+            .iter()
+            .find(|timer| (timer.x, timer.y) == (x, y))
+            .map(|timer| timer.ticks_left)
+    }
+
+    // Step any prey near a head one tile further away
+    // Prey avoids dead ends so it can't corner itself
+    fn move_prey(&mut self) {
+        let mut prey = Vec::new();
+        for x in 0..self.width {
+            for y in 0..self.height {
+                if self.tiles[x as usize][y as usize] == Tile::Food(FoodKind::Prey) {
+                    prey.push((x, y));
+                }
+            }
+        }
+
+        for (x, y) in prey {
+            let distance = self.distance_to_nearest_head(x, y);
+            if distance > PREY_ALERT_DISTANCE {
+                continue;
+            }
+
+            let best = self
+                .topology
+                .neighbours((x, y))
+                .into_iter()
+                .filter(|&(nx, ny)| self.free_for_prey(nx, ny))
+                .map(|(nx, ny)| {
+                    let away = self.distance_to_nearest_head(nx, ny);
+                    // Only count exits other than the tile the prey is leaving
+                    let exits = self
+                        .topology
+                        .neighbours((nx, ny))
+                        .into_iter()
+                        .filter(|&(ex, ey)| (ex, ey) != (x, y) && self.free_for_prey(ex, ey))
+                        .count();
+                    ((nx, ny), away, exits)
+                })
+                .filter(|&(_, away, exits)| away > distance && exits > 0)
+                .max_by_key(|&(_, away, exits)| (exits.min(2), away));
+
+            if let Some(((nx, ny), _, _)) = best {
+                self.tiles[x as usize][y as usize] = Tile::Empty;
+                self.tiles[nx as usize][ny as usize] = Tile::Food(FoodKind::Prey);
+                for timer in &mut self.food_timers {
+                    if (timer.x, timer.y) == (x, y) {
+                        timer.x = nx;
+                        timer.y = ny;
+                    }
+                }
+            }
+        }
+    }
+
+    fn free_for_prey(&self, x: i32, y: i32) -> bool {
+        self.tiles[x as usize][y as usize] == Tile::Empty && !self.obstacle_at(x, y)
+    }
+
+    // The kind of food that is always on the board
+    fn main_food_kind(&self, rng: &mut StdRng) -> FoodKind {
+        match self.nibbles {
+            Some(n) => FoodKind::Number(n),
+            None if rng.gen::<f32>() < self.prey_chance => FoodKind::Prey,
+            None => FoodKind::Normal,
+        }
+    }
+
+    fn spawn_food(&mut self) {
+        let mut rng = self.food_rng();
+        let kind = self.main_food_kind(&mut rng);
+
+        // Levels with fixed food put it exactly where they say, in order
+        if let Some(sequence) = &mut self.food_sequence {
+            if let Some(&(x, y)) = sequence.last() {
+                if self.tiles[x as usize][y as usize] == Tile::Empty {
+                    sequence.pop();
+                    self.tiles[x as usize][y as usize] = Tile::Food(kind);
+                    self.food_spawned(x, y, kind);
+                }
+            }
+            return;
+        }
+        self.foods_served += 1;
+        let placed = self.place_item(Tile::Food(kind), &mut rng);
+        if let (Some((x, y)), Some(ttl)) = (placed, self.food_ttl) {
+            self.food_timers.push(FoodTimer {
+                x,
+                y,
+                ticks_left: ttl,
+            });
+        }
+        if let Some((x, y)) = placed {
+            self.food_spawned(x, y, kind);
+        }
+
+        // Poison only sticks around until the next normal food is eaten
+        for column in &mut self.tiles {
+            for tile in column {
+                if *tile == Tile::Food(FoodKind::Poison) {
+                    *tile = Tile::Empty;
+                }
+            }
+        }
+        if rng.gen::<f32>() < self.poison_chance {
+            self.place_item(Tile::Food(FoodKind::Poison), &mut rng);
+        }
+    }
+
+    fn food_spawned(&mut self, x: i32, y: i32, kind: FoodKind) {
+        for hooks in self.hooks.clone() {
+            hooks.borrow_mut().on_spawn_food(self, x, y, kind);
+        }
+    }
+
+    // Count the food that respawns, on the board and still to come
+    fn food_left(&self) -> usize {
+        let on_board = self
+            .food_positions()
+            .filter(|&(_, _, kind)| kind.respawns())
+            .count();
+        on_board
+            + self
+                .food_sequence
+                .as_ref()
+                .map_or(0, |sequence| sequence.len())
+    }
+
+    // Place bonus food somewhere risky: next to a wall, the board's edge, or
+    // a snake
+    fn spawn_bonus(&mut self) {
+        let mut risky = Vec::new();
+        for x in 0..self.width {
+            for y in 0..self.height {
+                if self.tiles[x as usize][y as usize] != Tile::Empty || self.obstacle_at(x, y) {
+                    continue;
+                }
+                let near_danger = self.directions().iter().any(|&direction| {
+                    match self.topology.step((x, y), direction) {
+                        Some((nx, ny)) => matches!(
+                            self.tiles[nx as usize][ny as usize],
+                            Tile::Wall | Tile::Snake(..)
+                        ),
+                        None => true,
+                    }
+                });
+                if near_danger {
+                    risky.push((x, y));
+                }
+            }
+        }
+
+        let (x, y) = match risky.choose(&mut self.rng) {
+            Some(&pos) => pos,
+            None => return,
+        };
+        self.tiles[x as usize][y as usize] = Tile::Food(FoodKind::Bonus);
+        self.food_timers.push(FoodTimer {
+            x,
+            y,
+            ticks_left: BONUS_TTL,
+        });
+    }
+
+    fn bonus_active(&self) -> bool {
+        self.bonus_ticks_left().is_some()
+    }
+
+    // Ticks left before the bonus food on the board disappears
+    fn bonus_ticks_left(&self) -> Option<u32> {
+        self.food_timers
+            .iter()
+            .find(|timer| {
+                self.tiles[timer.x as usize][timer.y as usize] == Tile::Food(FoodKind::Bonus)
+            })
+            .map(|timer| timer.ticks_left)
+    }
+
+    // Put food or a power-up on an empty tile chosen by the food policy, and
+    // return where it went
+    // Returns `None` if the board has no room left
+    fn place_item(&mut self, item: Tile, rng: &mut StdRng) -> Option<(i32, i32)> {
+        let mut empty = Vec::new();
+        for x in 0..self.width {
+            for y in 0..self.height {
+                if self.tiles[x as usize][y as usize] == Tile::Empty && !self.obstacle_at(x, y) {
+                    empty.push((x, y));
+                }
+            }
+        }
+
+        let (x, y) = match self.food_policy {
+            // Boards sharing a seed pick the same tile as long as the same
+            // tiles are empty on both
+            FoodPolicy::Uniform => *empty.choose(rng)?,
+            FoodPolicy::Far => {
+                let far: Vec<(i32, i32)> = empty
+                    .iter()
+                    .copied()
+                    .filter(|&(x, y)| self.distance_to_nearest_head(x, y) >= FAR_FOOD_DISTANCE)
+                    .collect();
+                // Settle for anywhere on boards too cramped to keep the distance
+                *far.choose(rng).or_else(|| empty.choose(rng))?
+            }
+            FoodPolicy::Open => *empty
+                .choose_weighted(rng, |&(x, y)| {
+                    let open = self.open_tiles_around(x, y);
+                    open * open
+                })
+                .ok()?,
+        };
+
+        self.tiles[x as usize][y as usize] = item;
+        Some((x, y))
+    }
+
+    // Count the empty tiles in the 5x5 square centred on a tile
+    fn open_tiles_around(&self, x: i32, y: i32) -> u32 {
+        let mut count = 0;
+        for nx in x - 2..=x + 2 {
+            for ny in y - 2..=y + 2 {
+                if self.in_bounds(nx, ny) && self.tiles[nx as usize][ny as usize] == Tile::Empty {
+                    count += 1;
+                }
+            }
+        }
+        count
+    }
+
+    // Work out which tiles the first snake can see through the fog
+    // The snake always knows where its own body is
+    fn visible_tiles(&self) -> Vec<Vec<bool>> {
+        let mut visible = vec![vec![true; self.height as usize]; self.width as usize];
+        if let Some(radius) = self.fog {
+            let snake = &self.snakes[0];
+            for x in 0..self.width {
+                for y in 0..self.height {
+                    let (dx, dy) = self.topology.offset((snake.head_x, snake.head_y), (x, y));
+                    visible[x as usize][y as usize] = dx * dx + dy * dy <= radius * radius
+                        || matches!(self.tiles[x as usize][y as usize], Tile::Snake(0, _));
+                }
+            }
+        }
+        visible
+    }
+
+    // Arrows at the edge of the fog pointing towards food hidden inside it
+    fn fog_hints(&self, visible: &[Vec<bool>]) -> Vec<(i32, i32, &'static str)> {
+        let radius = match self.fog {
+            Some(radius) => radius,
+            None => return Vec::new(),
+        };
+        let snake = &self.snakes[0];
+
+        let mut hints = Vec::new();
+        for (x, y, kind) in self.food_positions() {
+            if !kind.respawns() || visible[x as usize][y as usize] {
+                continue;
+            }
+
+            // Step just past the edge of sight, in the direction of the food
+            let (dx, dy) = self.topology.offset((snake.head_x, snake.head_y), (x, y));
+            let (dx, dy) = (dx as f32, dy as f32);
+            let scale = (radius + 1) as f32 / (dx * dx + dy * dy).sqrt();
+            let hint = self.topology.settle((
+                snake.head_x + (dx * scale).round() as i32,
+                snake.head_y + (dy * scale).round() as i32,
+            ));
+            let (hint_x, hint_y) = match hint {
+                Some(hint) => hint,
+                None => continue,
+            };
+            let (wide, compact) = if dx.abs() > dy.abs() {
+                if dx > 0.0 {
+                    (">>", ">")
+                } else {
+                    ("<<", "<")
+                }
+            } else if dy > 0.0 {
+                ("vv", "v")
+            } else {
+                ("^^", "^")
+            };
+            let glyph = if self.zoom.compact() { compact } else { wide };
+            hints.push((hint_x, hint_y, glyph));
+        }
+        hints
+    }
+
+    // The glyph to draw a tile with, in the current theme
+    fn glyph(&self, tile: Tile) -> &str {
+        let compact = self.zoom.compact();
+        let glyphs = self.theme.glyphs(compact);
+        match tile {
+            Tile::Empty => &glyphs.empty,
+            Tile::Wall => &glyphs.wall,
+            Tile::Food(FoodKind::Normal | FoodKind::Frenzy) => &glyphs.food,
+            Tile::Snake(id, val) => {
+                // The head is the newest segment once a tick has aged it
+                let head = glyphs.head.as_deref().filter(|_| val <= 1);
+                let skin = self.skin.ascii_rep(compact).filter(|_| id == 0);
+                head.or(skin).unwrap_or(&glyphs.body)
+            }
+            _ if compact => tile.compact_rep(),
+            _ => tile.ascii_rep(),
+        }
+    }
+
+    // Work out how to draw the tile at x,y: its glyph, and the color if it
+    // isn't drawn plainly
+    fn cell(
+        &self,
+        x: i32,
+        y: i32,
+        visible: &[Vec<bool>],
+        hints: &[(i32, i32, &'static str)],
+        closing_ring: Option<i32>,
+    ) -> (&str, Option<Color>) {
+        let mut tile = self.tiles[x as usize][y as usize];
+        let compact = self.zoom.compact();
+
+        // Draw the fog, and any hints showing through it
+        if !visible[x as usize][y as usize] {
+            let glyph = hints
+                .iter()
+                .find(|&&(hint_x, hint_y, _)| (hint_x, hint_y) == (x, y))
+                .map(|&(_, _, glyph)| glyph)
+                .unwrap_or(if compact { FOG_COMPACT } else { FOG_GLYPH });
+            return (glyph, Some(Color::DarkGrey));
+        }
+
+        // Mark the edge of the battle royale safe zone
+        if tile == Tile::Empty && closing_ring == Some(self.ring(x, y)) {
+            let glyph = if compact {
+                ZONE_EDGE_COMPACT
+            } else {
+                ZONE_EDGE_GLYPH
+            };
+            return (glyph, Some(Color::DarkRed));
+        }
+
+        if self.obstacle_at(x, y) {
+            let glyph = if compact {
+                OBSTACLE_COMPACT
+            } else {
+                OBSTACLE_GLYPH
+            };
+            return (glyph, None);
+        }
+
+        // Blink food that is about to expire
+        if let Some(ticks_left) = self.food_ticks_left(x, y) {
+            if ticks_left <= FOOD_BLINK_TICKS && ticks_left % 2 == 0 {
+                tile = Tile::Empty;
+            }
+        }
+
+        let mut color = tile.color();
+        if let Tile::Snake(id, _) = tile {
+            let snake = &self.snakes[id as usize];
+            if self.boss.as_ref().map(|boss| boss.id) == Some(id) {
+                color = Some(Color::Red);
+            } else if id == 0 {
+                color = self.skin.color();
+            }
+
+            // Blink a freshly respawned snake while it's protected
+            if snake.invincible > 0 && self.ticks.is_multiple_of(2) {
+                tile = Tile::Empty;
+            }
+
+            // Ghosts are drawn faded, flashing when about to solidify mid-overlap
+            if let Some(ghost) = snake.effect(PowerUp::Ghost) {
+                color = if ghost.expiring()
+                    && !snake.overlaps.is_empty()
+                    && self.ticks.is_multiple_of(2)
+                {
+                    Some(Color::Red)
+                } else {
+                    Some(Color::DarkGrey)
+                };
+            }
+        }
+
+        (self.glyph(tile), color)
+    }
+
+    // Draw the board centered in the terminal, with the HUD and any footer
+    // lines beneath it
+    pub fn render_ascii(&self, footer: &[String]) {
+        // The frame is sized for every line the HUD could show, so the board
+        // stays put as lines come and go beneath it
+        let hud = self.hud();
+        let width = hud
+            .iter()
+            .chain(footer)
+            .map(|line| layout::text_width(line))
+            .fold(self.board_width(), usize::max);
+        let rows = self.board_rows();
+        let mut layout = Layout::centered(width, rows.len() + self.hud_rows() + footer.len());
+
+        for row in rows.iter().chain(&hud).chain(footer) {
+            layout.print_row(row);
+        }
+        stdout().flush().unwrap();
+    }
+
+    // The board and HUD as a column of rows under a label, each row with how
+    // wide it's drawn, for screens showing several boards side by side
+    // The column has room for every line the HUD could show
+    pub fn column(&self, label: &str) -> Vec<(String, usize)> {
+        let label = format!("  {}", label);
+        let mut column = vec![(label.clone(), layout::text_width(&label))];
+        for row in self.board_rows() {
+            column.push((row, self.board_width()));
+        }
+        let tall = column.len() + self.hud_rows();
+        for line in self.hud() {
+            let width = layout::text_width(&line);
+            column.push((line, width));
+        }
+        column.resize(tall, (String::new(), 0));
+        column
+    }
+
+    // How many columns the board is drawn across, borders and all
+    fn board_width(&self) -> usize {
+        let pad = if self.zoom.compact() { 0 } else { 1 };
+        self.zoom.width * self.width as usize + self.stagger() + 2 * (1 + pad)
+    }
+
+    // How far odd rows are drawn to the right of even ones
+    fn stagger(&self) -> usize {
+        if self.topology.staggered() {
+            self.zoom.width / 2
+        } else {
+            0
+        }
+    }
+
+    // The board drawn as rows of text, borders and all, colored as they go
+    fn board_rows(&self) -> Vec<String> {
+        let visible = self.visible_tiles();
+        let hints = self.fog_hints(&visible);
+        let closing_ring = self
+            .closing_ring()
+            .filter(|_| self.zone.as_ref().unwrap().ticks_left <= ZONE_WARNING_TICKS);
+
+        // Each cell is drawn as a block of glyphs, as big as the zoom says
+        // Compact boards also leave out the padding inside the side borders
+        let repeat = (self.zoom.width / CELL_WIDTH).max(1);
+        let pad = if self.zoom.compact() { "" } else { " " };
+        let stagger = self.stagger();
+
+        // Edges that wrap round are drawn dotted
+        let border = |edge: Direction| {
+            let wraps = self.topology.wraps(edge);
+            match edge {
+                Direction::Up | Direction::Down => {
+                    let line = if wraps { "." } else { "-" };
+                    let width = self.zoom.width * self.width as usize + stagger;
+                    format!(" {}{}", pad, line.repeat(width))
+                }
+                _ => (if wraps { ":" } else { "|" }).to_string(),
+            }
+        };
+        let (left, right) = (border(Direction::Left), border(Direction::Right));
+
+        // Top border
+        let mut rows = vec![border(Direction::Up)];
+
+        for y in 0..self.height {
+            // Staggered rows are shifted over by padding one side or the other
+            let (before, after) = if y % 2 == 1 {
+                (" ".repeat(stagger), String::new())
+            } else {
+                (String::new(), " ".repeat(stagger))
+            };
+            for _ in 0..self.zoom.height {
+                // Left border
+                let mut row = format!("{}{}{}", left, pad, before);
+
+                // Tiles
+                for x in 0..self.width {
+                    let (glyph, color) = self.cell(x, y, &visible, &hints, closing_ring);
+                    let glyph = glyph.repeat(repeat);
+                    match color {
+                        Some(color) => row += &style::style(glyph).with(color).to_string(),
+                        None => row += &glyph,
+                    }
+                }
+
+                // Right border
+                row += &format!("{}{}{}", after, pad, right);
+                rows.push(row);
+            }
+        }
+
+        // Bottom border
+        rows.push(border(Direction::Down));
+        rows
+    }
+
+    // The most lines the HUD can show: one per snake and mission, then the
+    // win goals, a popup and the timers
+    fn hud_rows(&self) -> usize {
+        let co_op = if self.co_op.is_some() { 1 } else { 0 };
+        self.snakes.len() + self.missions.len() + co_op + 3
+    }
+
+    // The lines of text shown beneath the board
+    fn hud(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+
+        // Score lines, one per snake
+        for (id, snake) in self.snakes.iter().enumerate() {
+            if let Some(boss) = self.boss.as_ref().filter(|boss| boss.id as usize == id) {
+                if snake.alive {
+                    let line = tr(
+                        "hud.boss",
+                        &[&snake.length, &boss.attack.name(), &boss.fight_ticks_left],
+                    );
+                    lines.push(format!("  {}", line));
+                } else {
+                    lines.push(format!("  {}", tr("hud.boss-beaten", &[])));
+                }
+                continue;
+            }
+            let mut line = String::new();
+            if self.snakes.len() > 1 {
+                line += &format!("  {}", tr("hud.player", &[&(id + 1)]));
+                if !snake.alive {
+                    lines.push(format!("{}  {}", line, tr("hud.out", &[])));
+                    continue;
+                }
+            }
+            line += &format!(
+                "  {}  {}",
+                tr("hud.score", &[&snake.score]),
+                tr("hud.length", &[&snake.length])
+            );
+            if snake.lives > 1 {
+                line += &format!("  {}", tr("hud.lives", &[&snake.lives]));
+            }
+            if snake.combo > 1 && self.combo_running(id as SnakeId) {
+                line += &format!("  {}", tr("hud.combo", &[&snake.combo]));
+            }
+            for effect in &snake.effects {
+                let mark = if effect.expiring() { "!" } else { "" };
+                line += &format!(
+                    "  {}: {}{}",
+                    effect.power_up.name(),
+                    effect.ticks_left,
+                    mark
+                );
+            }
+            lines.push(line);
+        }
+
+        for mission in &self.missions {
+            lines.push(format!("  {}", tr("hud.mission", &[&mission.describe()])));
+        }
+
+        // The warning flashes in place of whose turn it is before control
+        // changes hands
+        if let Some(co_op) = &self.co_op {
+            let line = match co_op.handoff {
+                Handoff::Seconds(_) => {
+                    let seconds = co_op.ticks_left.div_ceil(TICKS_PER_SECOND);
+                    if co_op.warning() && self.ticks.is_multiple_of(2) {
+                        tr(
+                            "hud.handoff-warning",
+                            &[&(co_op.next_driver() + 1), &seconds],
+                        )
+                    } else {
+                        tr("hud.steering", &[&(co_op.driver + 1), &seconds])
+                    }
+                }
+                Handoff::Food => tr("hud.steering-food", &[&(co_op.driver + 1)]),
+            };
+            lines.push(format!("  {}", line));
+        }
+
+        // Eating the food is already counted down in the timers
+        let goals: Vec<String> = self
+            .win_conditions
+            .iter()
+            .filter(|condition| {
+                !matches!(
+                    condition,
+                    WinCondition::ClearFood | WinCondition::EatFood(_)
+                )
+            })
+            .map(|condition| condition.goal())
+            .collect();
+        if !goals.is_empty() {
+            let or = format!(" {} ", tr("hud.or", &[]));
+            lines.push(format!("  {}", tr("hud.win", &[&goals.join(&or)])));
+        }
+
+        if let Some((popup, _)) = &self.popup {
+            lines.push(format!("  {}", popup));
+        }
+
+        // Timers shared by everyone
+        let mut timers = String::new();
+        if let Some(ticks_left) = self.bonus_ticks_left() {
+            timers += &format!("  {}", tr("hud.bonus", &[&ticks_left]));
+        }
+        if let Some(waves) = &self.waves {
+            let wave = tr("hud.wave", &[&waves.wave, &waves.food_left, &waves.coins]);
+            timers += &format!("  {}", wave);
+            if waves.shields > 0 {
+                timers += &format!("  {}", tr("hud.shields", &[&waves.shields]));
+            }
+            if waves.multiplier > 1 {
+                timers += &format!("  x{}", waves.multiplier);
+            }
+        }
+        if let Some(moves) = self.moves_left {
+            timers += &format!("  {}", tr("hud.moves", &[&moves, &self.food_left()]));
+        }
+        for condition in &self.win_conditions {
+            if let WinCondition::EatFood(food) = condition {
+                let left = food.saturating_sub(self.food_eaten);
+                timers += &format!("  {}", tr("hud.food-to-go", &[&left]));
+            }
+        }
+        if let Some((event, ticks_left)) = self.events.as_ref().and_then(|events| events.active) {
+            timers += &format!("  {}: {}", event.name(), ticks_left);
+        }
+        if let (Some(zone), Some(_)) = (&self.zone, self.closing_ring()) {
+            timers += &format!("  {}", tr("hud.walls-close", &[&zone.ticks_left]));
+        }
+        if !timers.is_empty() {
+            lines.push(timers);
+        }
+        lines
+    }
+}
+
+// How many characters across and rows down each board cell is drawn as
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Zoom {
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Zoom {
+    pub const DEFAULT: Zoom = Zoom {
+        width: CELL_WIDTH,
+        height: 1,
+    };
+
+    // One character per cell, for narrow terminals and big boards
+    pub const COMPACT: Zoom = Zoom {
+        width: 1,
+        height: 1,
+    };
+
+    // Parse a zoom like 4x2
+    // Widths have to be whole numbers of glyphs, unless the board is compact
+    pub fn parse(s: &str) -> Option<Self> {
+        let (width, height) = s.split_once('x')?;
+        let zoom = Zoom {
+            width: width.parse().ok()?,
+            height: height.parse().ok()?,
+        };
+        let fits = zoom == Zoom::COMPACT
+            || (zoom.width > 0
+                && zoom.width.is_multiple_of(CELL_WIDTH)
+                && (1..=MAX_ZOOM_HEIGHT).contains(&zoom.height));
+        Some(zoom).filter(|_| fits)
+    }
+
+    pub fn compact(self) -> bool {
+        self == Zoom::COMPACT
+    }
+
+    // One glyph wider and a row taller, if that isn't too big
+    pub fn zoomed_in(self) -> Self {
+        if self.compact() {
+            return Zoom::DEFAULT;
+        }
+        if self.height >= MAX_ZOOM_HEIGHT {
+            return self;
+        }
+        Zoom {
+            width: self.width + CELL_WIDTH,
+            height: self.height + 1,
+        }
+    }
+
+    // One glyph narrower and a row shorter, if there's room to shrink,
+    // going compact from the usual size
+    pub fn zoomed_out(self) -> Self {
+        if self == Zoom::DEFAULT {
+            return Zoom::COMPACT;
+        }
+        if self.width <= CELL_WIDTH || self.height <= 1 {
+            return self;
+        }
+        Zoom {
+            width: self.width - CELL_WIDTH,
+            height: self.height - 1,
+        }
+    }
+}
+
+// The game's own generator for a seed
+// Moving it to the start of its stream straight away fills its first block,
+// which has to be there before it can say how far along it is
+fn seeded_rng(seed: u64) -> ChaCha20Rng {
+    let mut rng = ChaCha20Rng::seed_from_u64(seed);
+    rng.set_word_pos(0);
+    rng
+}
+
+// Snake direction controls
+// The diagonals are only for steering on hex boards, where they lead to the
+// tiles touching a tile from the rows above and below
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
+}
+
+impl Direction {
+    // The ways to steer on a square board
+    pub const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+    ];
+
+    // The ways to steer on a hex board
+    pub const HEX: [Direction; 6] = [
+        Direction::UpLeft,
+        Direction::UpRight,
+        Direction::Left,
+        Direction::Right,
+        Direction::DownLeft,
+        Direction::DownRight,
+    ];
+
+    // The name the direction goes by in files
+    pub fn key(self) -> &'static str {
+        match self {
+            Direction::Up => "up",
+            Direction::Down => "down",
+            Direction::Left => "left",
+            Direction::Right => "right",
+            Direction::UpLeft => "up-left",
+            Direction::UpRight => "up-right",
+            Direction::DownLeft => "down-left",
+            Direction::DownRight => "down-right",
+        }
+    }
+
+    // Get the opposite direction
+    pub fn opposite(self) -> Self {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+            Direction::UpLeft => Direction::DownRight,
+            Direction::UpRight => Direction::DownLeft,
+            Direction::DownLeft => Direction::UpRight,
+            Direction::DownRight => Direction::UpLeft,
+        }
+    }
+
+    // Get the direction a quarter turn clockwise
+    pub fn clockwise(self) -> Self {
+        match self {
+            Direction::Up => Direction::Right,
+            Direction::Right => Direction::Down,
+            Direction::Down => Direction::Left,
+            Direction::Left => Direction::Up,
+            Direction::UpLeft => Direction::UpRight,
+            Direction::UpRight => Direction::DownRight,
+            Direction::DownRight => Direction::DownLeft,
+            Direction::DownLeft => Direction::UpLeft,
+        }
+    }
+
+    // Get the direction flipped left to right
+    pub fn mirrored(self) -> Self {
+        match self {
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+            Direction::UpLeft => Direction::UpRight,
+            Direction::UpRight => Direction::UpLeft,
+            Direction::DownLeft => Direction::DownRight,
+            Direction::DownRight => Direction::DownLeft,
+            other => other,
+        }
+    }
+
+    // Get the position one tile over in this direction on a square board
+    pub fn step(self, x: i32, y: i32) -> (i32, i32) {
+        match self {
+            Direction::Up => (x, y - 1),
+            Direction::Down => (x, y + 1),
+            Direction::Left => (x - 1, y),
+            Direction::Right => (x + 1, y),
+            Direction::UpLeft => (x - 1, y - 1),
+            Direction::UpRight => (x + 1, y - 1),
+            Direction::DownLeft => (x - 1, y + 1),
+            Direction::DownRight => (x + 1, y + 1),
+        }
+    }
+}
+
+// Scrambling applied to player input before it reaches the snake
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub struct InputTransform {
+    pub mirror: bool,  // swap left and right
+    pub reverse: bool, // swap every direction with its opposite
+}
+
+impl InputTransform {
+    // Scramble with both, which undo each other where they overlap
+    pub fn combined(self, other: InputTransform) -> Self {
+        Self {
+            mirror: self.mirror != other.mirror,
+            reverse: self.reverse != other.reverse,
+        }
+    }
+
+    pub fn apply(self, mut direction: Direction) -> Direction {
+        if self.mirror {
+            direction = direction.mirrored();
+        }
+        if self.reverse {
+            direction = direction.opposite();
+        }
+        direction
+    }
+}
+
+// Tiles that scramble the controls for a while when crossed
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Trap {
+    Mirror,
+    Reverse,
+}
+
+impl Trap {
+    pub const ALL: [Trap; 2] = [Trap::Mirror, Trap::Reverse];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            Trap::Mirror => "mirror",
+            Trap::Reverse => "reverse",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Trap::ALL.iter().copied().find(|trap| trap.key() == key)
+    }
+
+    fn effect(self) -> PowerUp {
+        match self {
+            Trap::Mirror => PowerUp::Mirrored,
+            Trap::Reverse => PowerUp::Reversed,
+        }
+    }
+}
+
+// Possible states of a tile
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Tile {
+    Empty,
+    Food(FoodKind),
+    Wall,
+    Portal(PortalId),
+    PowerUp(PowerUp),
+    Trap(Trap),
+    Snake(SnakeId, SnakeVal),
+}
+
+// Where new food is allowed to appear
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum FoodPolicy {
+    // Anywhere empty
+    Uniform,
+    // Out of easy reach of the head
+    Far,
+    // Favouring open areas over cramped corners
+    Open,
+}
+
+impl FoodPolicy {
+    pub fn key(self) -> &'static str {
+        match self {
+            FoodPolicy::Uniform => "uniform",
+            FoodPolicy::Far => "far",
+            FoodPolicy::Open => "open",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "uniform" => Some(FoodPolicy::Uniform),
+            "far" => Some(FoodPolicy::Far),
+            "open" => Some(FoodPolicy::Open),
+            _ => None,
+        }
+    }
+}
+
+// How a run speeds up as the first snake eats
+#[derive(Clone, Copy, PartialEq)]
+pub struct SpeedCurve {
+    pub ramp: f32, // how much of the normal speed each food adds
+    pub max: f32,  // the fastest it gets, as a multiple of the normal speed
+}
+
+impl SpeedCurve {
+    // The same speed all the way through
+    pub const FLAT: SpeedCurve = SpeedCurve {
+        ramp: 0.0,
+        max: 1.0,
+    };
+
+    // How many times faster than normal the game runs once this much food
+    // has been eaten
+    pub fn at(self, food_eaten: u32) -> f32 {
+        (1.0 + self.ramp * food_eaten as f32).min(self.max).max(1.0)
+    }
+
+    // The same curve, ramping up a fraction as quickly
+    pub fn slowed(self, by: f32) -> Self {
+        Self {
+            ramp: self.ramp * by,
+            ..self
+        }
+    }
+}
+
+// Kinds of food the snake can eat
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum FoodKind {
+    Normal,
+    Poison,
+    Bonus,
+    Number(u8),
+    Prey,
+    Shrink,
+    Frenzy, // extra food from a food frenzy
+}
+
+impl FoodKind {
+    // One of each kind, standing in for every number
+    pub const ALL: [FoodKind; 7] = [
+        FoodKind::Normal,
+        FoodKind::Poison,
+        FoodKind::Bonus,
+        FoodKind::Number(1),
+        FoodKind::Prey,
+        FoodKind::Shrink,
+        FoodKind::Frenzy,
+    ];
+
+    // The name the food goes by in files, the same for every number
+    pub fn key(self) -> &'static str {
+        match self {
+            FoodKind::Normal => "normal",
+            FoodKind::Poison => "poison",
+            FoodKind::Bonus => "bonus",
+            FoodKind::Number(_) => "number",
+            FoodKind::Prey => "prey",
+            FoodKind::Shrink => "shrink",
+            FoodKind::Frenzy => "frenzy",
+        }
+    }
+
+    // Whether food of this kind is replaced elsewhere once it's gone
+    fn respawns(self) -> bool {
+        match self {
+            FoodKind::Normal | FoodKind::Number(_) | FoodKind::Prey => true,
+            FoodKind::Poison | FoodKind::Bonus | FoodKind::Shrink | FoodKind::Frenzy => false,
+        }
+    }
+}
+
+// Numbered food is drawn with its number
+const NUMBER_GLYPHS: [&str; 9] = [" 1", " 2", " 3", " 4", " 5", " 6", " 7", " 8", " 9"];
+
+// Tiles hidden by fog
+const FOG_GLYPH: &str = "··";
+const FOG_COMPACT: &str = "·";
+
+// Tiles about to be walled off in battle royale
+const ZONE_EDGE_GLYPH: &str = "::";
+const ZONE_EDGE_COMPACT: &str = ":";
+
+// Each player's snake is drawn in its own color
+const SNAKE_COLORS: [Option<Color>; 4] = [
+    None,
+    Some(Color::Green),
+    Some(Color::DarkCyan),
+    Some(Color::DarkMagenta),
+];
+
+// Obstacles are entities rather than tiles, drawn over whatever they cover
+const OBSTACLE_GLYPH: &str = "XX";
+const OBSTACLE_COMPACT: &str = "X";
+
+// Portals are drawn with their pair's letter
+const PORTAL_GLYPHS: [&str; 26] = [
+    "AA", "BB", "CC", "DD", "EE", "FF", "GG", "HH", "II", "JJ", "KK", "LL", "MM", "NN", "OO", "PP",
+    "QQ", "RR", "SS", "TT", "UU", "VV", "WW", "XX", "YY", "ZZ",
+];
+
+impl Tile {
+    // The tile in a game state snapshot, like "food normal" or "snake 0 3"
+    fn to_text(self) -> String {
+        match self {
+            Tile::Empty => ".".to_string(),
+            Tile::Wall => "#".to_string(),
+            Tile::Food(FoodKind::Number(n)) => format!("food number {}", n),
+            Tile::Food(kind) => format!("food {}", kind.key()),
+            Tile::Portal(id) => format!("portal {}", id),
+            Tile::PowerUp(power_up) => format!("power-up {}", power_up.key()),
+            Tile::Trap(trap) => format!("trap {}", trap.key()),
+            Tile::Snake(id, val) => format!("snake {} {}", id, val),
+        }
+    }
+
+    fn parse(text: &str) -> Result<Self, String> {
+        fn number<T: std::str::FromStr>(n: &str) -> Result<T, String> {
+            n.parse()
+                .map_err(|_| format!("{} must be a whole number", n))
+        }
+        let words: Vec<&str> = text.split_whitespace().collect();
+        let tile = match words.as_slice() {
+            ["."] => Tile::Empty,
+            ["#"] => Tile::Wall,
+            ["food", "number", n] => match number(n)? {
+                n @ 1..=9 => Tile::Food(FoodKind::Number(n)),
+                _ => return Err(format!("food number {} must be 1 to 9", n)),
+            },
+            ["food", key] => Tile::Food(
+                FoodKind::ALL
+                    .iter()
+                    .copied()
+                    .find(|kind| !matches!(kind, FoodKind::Number(_)) && kind.key() == *key)
+                    .ok_or_else(|| format!("unknown food {}", key))?,
+            ),
+            ["portal", id] => match number(id)? {
+                id if (id as usize) < PORTAL_GLYPHS.len() => Tile::Portal(id),
+                _ => {
+                    return Err(format!(
+                        "portal {} must be below {}",
+                        id,
+                        PORTAL_GLYPHS.len()
+                    ))
+                }
+            },
+            ["power-up", key] => Tile::PowerUp(
+                PowerUp::from_key(key).ok_or_else(|| format!("unknown power-up {}", key))?,
+            ),
+            ["trap", key] => {
+                Tile::Trap(Trap::from_key(key).ok_or_else(|| format!("unknown trap {}", key))?)
+            }
+            ["snake", id, val] => Tile::Snake(number(id)?, number(val)?),
+            _ => return Err(format!("unknown tile {}", text)),
+        };
+        Ok(tile)
+    }
+
+    // Get a two-character ASCII representation, for tiles themes don't cover
+    fn ascii_rep(self) -> &'static str {
+        match self {
+            Tile::Empty => "  ",
+            Tile::Food(FoodKind::Normal | FoodKind::Frenzy) => "><",
+            Tile::Food(FoodKind::Poison) => "}{",
+            Tile::Food(FoodKind::Bonus) => "$$",
+            Tile::Food(FoodKind::Prey) => "^^",
+            Tile::Food(FoodKind::Shrink) => "<>",
+            Tile::Food(FoodKind::Number(n)) => NUMBER_GLYPHS[n as usize - 1],
+            Tile::Wall => "▒▒",
+            Tile::Portal(id) => PORTAL_GLYPHS[id as usize],
+            Tile::PowerUp(PowerUp::Ghost) => "%%",
+            Tile::PowerUp(_) => "??",
+            Tile::Trap(Trap::Mirror) => "~~",
+            Tile::Trap(Trap::Reverse) => "!!",
+            Tile::Snake(..) => "██",
+        }
+    }
+
+    // Get a one-character representation, for compact boards
+    fn compact_rep(self) -> &'static str {
+        match self {
+            Tile::Empty => " ",
+            Tile::Food(FoodKind::Normal | FoodKind::Frenzy) => "o",
+            Tile::Food(FoodKind::Poison) => "}",
+            Tile::Food(FoodKind::Bonus) => "$",
+            Tile::Food(FoodKind::Prey) => "^",
+            Tile::Food(FoodKind::Shrink) => "-",
+            // Each glyph is a padded number, so the last character is the digit
+            Tile::Food(FoodKind::Number(n)) => &NUMBER_GLYPHS[n as usize - 1][1..],
+            Tile::Wall => "▒",
+            // Each glyph is the letter twice
+            Tile::Portal(id) => &PORTAL_GLYPHS[id as usize][1..],
+            Tile::PowerUp(PowerUp::Ghost) => "%",
+            Tile::PowerUp(_) => "?",
+            Tile::Trap(Trap::Mirror) => "~",
+            Tile::Trap(Trap::Reverse) => "!",
+            Tile::Snake(..) => "█",
+        }
+    }
+
+    // Get the color to draw the tile in, if it isn't drawn plainly
+    fn color(self) -> Option<Color> {
+        match self {
+            Tile::Food(FoodKind::Poison) => Some(Color::Red),
+            Tile::Food(FoodKind::Bonus) => Some(Color::Yellow),
+            Tile::Food(FoodKind::Number(_)) => Some(Color::Green),
+            Tile::Food(FoodKind::Prey) => Some(Color::Cyan),
+            Tile::Food(FoodKind::Shrink) => Some(Color::Blue),
+            Tile::Food(FoodKind::Frenzy) => Some(Color::DarkGreen),
+            Tile::PowerUp(_) => Some(Color::Magenta),
+            Tile::Trap(_) => Some(Color::DarkYellow),
+            Tile::Snake(id, _) => SNAKE_COLORS[id as usize],
+            _ => None,
+        }
+    }
+}
+
+// A board from a drawing for tests in any module, scoring only for food so
+// bonuses for where it's eaten don't get in the way
+#[cfg(test)]
+pub(crate) fn test_board(text: &str) -> Game {
+    let mut game = Game::from_ascii(text).unwrap();
+    game.set_score_rules(ScoreRules {
+        wall_hug: 0,
+        close_call: 0,
+        ..ScoreRules::default()
+    });
+    game
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn head(game: &Game, snake: SnakeId) -> (i32, i32) {
+        let snake = &game.snakes[snake as usize];
+        (snake.head_x, snake.head_y)
+    }
+
+    fn died(game: &Game, snake: SnakeId, cause: DeathCause) -> bool {
+        game.happened().contains(&GameEvent::Died { snake, cause })
+    }
+
+    // Heading right along the middle row, with its head on the right edge
+    const RIGHT_EDGE: &str = "*....\n..oo@\n.....\n";
+
+    // Heading up the middle column, with its head on the top edge
+    const TOP_EDGE: &str = "..@..\n..o..\n*....\n";
+
+    #[test]
+    fn drawings_follow_the_body_from_the_head() {
+        let game = test_board(RIGHT_EDGE);
+        assert_eq!(game.dimensions(), (5, 3));
+        assert!(game.direction(0) == Direction::Right);
+        assert_eq!(game.length(), 3);
+        let segments: Vec<(i32, i32)> = game.segments(0).collect();
+        assert_eq!(segments, vec![(4, 1), (3, 1), (2, 1)]);
+        assert_eq!(game.to_ascii(), RIGHT_EDGE);
+    }
+
+    #[test]
+    fn drawings_that_cant_be_followed_are_rejected() {
+        let error = |text: &str| Game::from_ascii(text).err().unwrap();
+        assert_eq!(error(""), "the board is empty");
+        assert_eq!(error("...\n.o.\n"), "the board needs a head (@)");
+        assert_eq!(
+            error("@.\n.@\n"),
+            "line 2: the board can only have one head"
+        );
+        assert_eq!(
+            error("o@o\n"),
+            "line 1: can't tell which way the body goes from here"
+        );
+        assert_eq!(
+            error("@..\n..o\n"),
+            "line 2: body segment isn't joined to the head"
+        );
+        assert_eq!(error("@x\n"), "line 1: unknown tile 'x'");
+    }
+
+    #[test]
+    fn bounded_edges_kill() {
+        let mut game = test_board(RIGHT_EDGE);
+        game.update();
+        assert!(!game.alive());
+        assert!(died(&game, 0, DeathCause::Edge));
+    }
+
+    #[test]
+    fn torus_edges_wrap_to_the_opposite_side() {
+        let mut game = test_board(RIGHT_EDGE);
+        game.set_topology(topology::from_key("torus", 5, 3).unwrap());
+        game.update();
+        assert!(game.alive());
+        assert_eq!(head(&game, 0), (0, 1));
+
+        let mut game = test_board(TOP_EDGE);
+        game.set_topology(topology::from_key("torus", 5, 3).unwrap());
+        game.update();
+        assert!(game.alive());
+        assert_eq!(head(&game, 0), (2, 2));
+    }
+
+    #[test]
+    fn only_the_listed_edges_wrap() {
+        let mut game = test_board(RIGHT_EDGE);
+        game.set_topology(topology::from_key("wrap:left,right", 5, 3).unwrap());
+        game.update();
+        assert_eq!(head(&game, 0), (0, 1));
+
+        let mut game = test_board(TOP_EDGE);
+        game.set_topology(topology::from_key("wrap:left,right", 5, 3).unwrap());
+        game.update();
+        assert!(died(&game, 0, DeathCause::Edge));
+
+        let mut game = test_board(TOP_EDGE);
+        game.set_topology(topology::from_key("wrap:top", 5, 3).unwrap());
+        game.update();
+        assert_eq!(head(&game, 0), (2, 2));
+    }
+
+    #[test]
+    fn hex_boards_steer_along_the_diagonals() {
+        let mut game = test_board("*....\n.oo@.\n.....\n");
+        game.set_topology(topology::from_key("hex", 5, 3).unwrap());
+        assert!(game.set_direction(0, Direction::Up).is_ok());
+        assert!(game.direction(0) == Direction::UpLeft);
+        game.update();
+        assert_eq!(head(&game, 0), (3, 0));
+
+        // Going back the way it came is still turning round
+        assert!(game.set_direction(0, Direction::DownRight).is_err());
+        assert!(game.set_direction(0, Direction::Down).is_err());
+        assert!(game.set_direction(0, Direction::DownLeft).is_ok());
+        game.update();
+        assert_eq!(head(&game, 0), (2, 1));
+    }
+
+    #[test]
+    fn square_boards_cant_be_steered_diagonally() {
+        let mut game = test_board(RIGHT_EDGE);
+        assert!(game.set_direction(0, Direction::UpLeft).is_err());
+        assert!(game.direction(0) == Direction::Right);
+    }
+
+    // Snake 0 heads up the fourth column into snake 1's body, which runs
+    // right along the second row
+    fn crossing() -> Game {
+        let mut game = test_board("*........\n.........\n.........\n...@.....\n...o.....\n");
+        game.snakes.push(Snake::new((4, 1)));
+        game.place_snake(1, (4, 1), Direction::Right, 4);
+        game
+    }
+
+    #[test]
+    fn invincible_heads_pass_through_bodies() {
+        let mut game = crossing();
+        game.snakes[0].invincible = INVINCIBLE_TICKS;
+        game.update();
+        game.update();
+        assert!(game.snakes[0].alive);
+        assert_eq!(head(&game, 0), (3, 1));
+        // The tile is still snake 1's, to be cleared when its tail gets there
+        assert!(game.tile_at(3, 1) == Some(Tile::Snake(1, 4)));
+
+        game.update();
+        assert!(game.snakes[0].alive);
+        assert!(game.tile_at(3, 1) == Some(Tile::Empty));
+        assert!(game.tile_at(3, 0) == Some(Tile::Snake(0, 1)));
+    }
+
+    #[test]
+    fn bodies_kill_heads_that_arent_invincible() {
+        let mut game = crossing();
+        game.update();
+        game.update();
+        assert!(!game.snakes[0].alive);
+        assert!(died(&game, 0, DeathCause::Body(1)));
+        assert!(game.snakes[1].alive);
+    }
+
+    // Heading up into a wall
+    const WALL_AHEAD: &str = ".#...\n.@...\n.o...\n....*\n";
+
+    #[test]
+    fn shields_take_the_hit_instead_of_a_life() {
+        let mut game = test_board(WALL_AHEAD);
+        game.set_waves(true);
+        game.waves.as_mut().unwrap().shields = 1;
+        game.update();
+        assert!(died(&game, 0, DeathCause::Wall));
+        assert!(game.alive());
+        assert_eq!(game.lives(), 1);
+        assert_eq!(game.waves().unwrap().shields, 0);
+        assert_eq!(head(&game, 0), (1, 1));
+        assert_eq!(game.snakes[0].invincible, INVINCIBLE_TICKS);
+
+        let mut game = test_board(WALL_AHEAD);
+        game.set_waves(true);
+        game.update();
+        assert!(!game.alive());
+    }
+
+    #[test]
+    fn respawns_move_off_a_blocked_spawn() {
+        let mut game = test_board(".#...\n.....\n.@...\n.o...\n....*\n");
+        game.set_lives(2);
+        game.update();
+        game.tiles[1][2] = Tile::Wall;
+        game.update();
+        assert!(died(&game, 0, DeathCause::Wall));
+        assert!(game.alive());
+        assert_eq!(game.lives(), 1);
+        assert_eq!(head(&game, 0), (0, 2));
+        assert!(matches!(game.tile_at(0, 2), Some(Tile::Snake(0, _))));
+        assert!(game.tile_at(1, 2) == Some(Tile::Wall));
+    }
+
+    // Look a field of a snapshot up to change it
+    fn field<'a>(json: &'a mut Json, key: &str) -> &'a mut Json {
+        match json {
+            Json::Object(fields) => &mut fields.iter_mut().find(|(k, _)| k == key).unwrap().1,
+            _ => panic!("{} isn't in an object", key),
+        }
+    }
+
+    fn item(json: &mut Json, i: usize) -> &mut Json {
+        match json {
+            Json::Array(items) => &mut items[i],
+            _ => panic!("{} isn't in an array", i),
+        }
+    }
+
+    fn load_error(json: &Json) -> String {
+        Game::from_json(json).err().unwrap()
+    }
+
+    #[test]
+    fn snapshots_load_back_the_same() {
+        let mut game = test_board("*......\n.......\n..ooo@.\n.......\n");
+        game.set_topology(topology::from_key("torus", 7, 4).unwrap());
+        game.set_seed(7);
+        game.update();
+        game.update();
+
+        let json = game.to_json();
+        let loaded = Game::from_json(&Json::parse(&json.to_text()).unwrap()).unwrap();
+        assert!(loaded.to_json() == json);
+        assert_eq!(loaded.ticks(), 2);
+        assert_eq!(head(&loaded, 0), head(&game, 0));
+        assert_eq!(loaded.to_ascii(), game.to_ascii());
+    }
+
+    #[test]
+    fn loaded_snapshots_play_on_the_same() {
+        let mut game = Game::new(12, 12);
+        game.set_topology(topology::from_key("torus", 12, 12).unwrap());
+        game.set_seed(3);
+        game.set_power_ups(true);
+        game.set_random_events(true);
+        for _ in 0..40 {
+            game.update();
+        }
+
+        let mut loaded = Game::from_json(&Json::parse(&game.to_json().to_text()).unwrap()).unwrap();
+        for _ in 0..200 {
+            game.update();
+            loaded.update();
+        }
+        assert!(loaded.to_json() == game.to_json());
+    }
+
+    #[test]
+    fn snapshots_that_dont_fit_the_board_are_rejected() {
+        let json = test_board(RIGHT_EDGE).to_json();
+
+        let mut changed = json.clone();
+        *item(item(field(&mut changed, "tiles"), 1), 0) = "snake 7 1".into();
+        assert_eq!(load_error(&changed), "tiles: row 2: there's no snake 7");
+
+        let mut changed = json.clone();
+        *item(item(field(&mut changed, "tiles"), 1), 0) = "food number 0".into();
+        assert_eq!(
+            load_error(&changed),
+            "tiles: row 2: food number 0 must be 1 to 9"
+        );
+
+        let mut changed = json.clone();
+        *item(item(field(&mut changed, "tiles"), 1), 0) = "portal 26".into();
+        assert_eq!(
+            load_error(&changed),
+            format!(
+                "tiles: row 2: portal 26 must be below {}",
+                PORTAL_GLYPHS.len()
+            )
+        );
+
+        let mut changed = json.clone();
+        *field(item(field(&mut changed, "snakes"), 0), "head") = Json::pos(5, 1);
+        assert_eq!(load_error(&changed), "snakes: head: 5,1 is off the board");
+
+        let mut changed = json.clone();
+        *field(&mut changed, "tiles") = Json::Array(Vec::new());
+        assert_eq!(load_error(&changed), "tiles: expected 3 rows");
+
+        let mut changed = json;
+        *field(&mut changed, "snakes") = Json::Array(Vec::new());
+        assert_eq!(load_error(&changed), "snakes: there must be 1 to 4");
+    }
+}
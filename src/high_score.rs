@@ -0,0 +1,46 @@
+// The best score of any single-player run, kept in a file of its own next
+// to the profile so every kind of run can tell when it's been beaten
+//
+// The file holds just the score. Runs the player quits don't count, and
+// neither do replays, which only play an old run again.
+
+use crate::profile;
+use std::fs;
+use std::path::PathBuf;
+
+fn path() -> Option<PathBuf> {
+    Some(profile::data_dir()?.join("high-score"))
+}
+
+// The best score so far, if there's been a run to set one
+fn load() -> Result<Option<i32>, String> {
+    let path = match path() {
+        Some(path) if path.exists() => path,
+        _ => return Ok(None),
+    };
+    let text = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let score = text
+        .trim()
+        .parse()
+        .map_err(|_| format!("{}: the high score must be a whole number", path.display()))?;
+    Ok(Some(score))
+}
+
+// Keep a finished run's score if it's the best yet
+// Returns whether it beat a high score that was already there, since the
+// first run to set one hasn't beaten anything
+pub fn record(score: i32) -> Result<bool, String> {
+    let best = load()?;
+    if best.is_some_and(|best| score <= best) {
+        return Ok(false);
+    }
+    let path = match path() {
+        Some(path) => path,
+        None => return Ok(false),
+    };
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    }
+    fs::write(&path, format!("{}\n", score)).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(best.is_some())
+}
@@ -0,0 +1,29 @@
+// Hooks let code driving a game step in around each update without the
+// game knowing anything about it, to log what happens, tweak the rules or
+// keep count of things
+//
+// A hook is handed the game itself, so it can look at or change anything
+// the game lets it. Games made by rewinding share their hooks with the game
+// they were copied from. A hook mustn't update the game it's called from.
+
+use crate::game::{FoodKind, Game};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// Every method does nothing unless it's overridden, so a hook only needs the
+// ones it cares about
+pub trait Hooks {
+    // Before anything moves on an update
+    fn before_tick(&mut self, _game: &mut Game) {}
+
+    // Once everything on an update has been dealt with, when the game's
+    // events say what happened on it
+    fn after_tick(&mut self, _game: &mut Game) {}
+
+    // Whenever food that respawns is put on the board, or shows up from a
+    // level's fixed food
+    fn on_spawn_food(&mut self, _game: &mut Game, _x: i32, _y: i32, _kind: FoodKind) {}
+}
+
+// How games hold on to their hooks, so the caller can keep a handle too
+pub type SharedHooks = Rc<RefCell<dyn Hooks>>;
@@ -0,0 +1,71 @@
+// Hot-seat, where players pass the keyboard and take turns playing full
+// runs on the same seed, then see how they rank against each other
+
+use crate::locale::tr;
+
+// How many players can take turns
+pub const MAX_PLAYERS: usize = 9;
+
+pub struct HotSeat {
+    scores: Vec<i32>, // of the players who have had their turn, in order
+    players: usize,
+}
+
+impl HotSeat {
+    pub fn new(players: usize) -> Self {
+        Self {
+            scores: Vec::new(),
+            players,
+        }
+    }
+
+    // The player whose turn is next, counting from 0, if anyone's left
+    pub fn next_player(&self) -> Option<usize> {
+        Some(self.scores.len()).filter(|&player| player < self.players)
+    }
+
+    pub fn record(&mut self, score: i32) {
+        self.scores.push(score);
+    }
+
+    // The lines of the screen asking for the keyboard to be passed on
+    pub fn handoff_lines(&self, player: usize) -> Vec<String> {
+        let mut lines = vec![format!("  {}", tr("hot-seat.turn", &[&(player + 1)]))];
+        if let Some(&last) = self.scores.last() {
+            lines.push(format!("  {}", tr("hot-seat.last", &[&player, &last])));
+        }
+        lines.push(String::new());
+        lines.push(format!("  {}", tr("hot-seat.start", &[])));
+        lines
+    }
+
+    // The players from best to worst score, with equal scores sharing a place
+    // Each entry is the place, the player and their score
+    pub fn ranking(&self) -> Vec<(usize, usize, i32)> {
+        let mut order: Vec<usize> = (0..self.scores.len()).collect();
+        order.sort_by_key(|&player| -self.scores[player]);
+
+        let mut ranking: Vec<(usize, usize, i32)> = Vec::new();
+        for (i, &player) in order.iter().enumerate() {
+            let score = self.scores[player];
+            let place = match ranking.last() {
+                Some(&(place, _, last)) if last == score => place,
+                _ => i + 1,
+            };
+            ranking.push((place, player, score));
+        }
+        ranking
+    }
+
+    // The lines of the final ranking screen
+    pub fn ranking_lines(&self) -> Vec<String> {
+        let mut lines = vec![format!("  {}", tr("hot-seat.ranking", &[])), String::new()];
+        for (place, player, score) in self.ranking() {
+            let line = tr("hot-seat.place", &[&place, &(player + 1), &score]);
+            lines.push(format!("  {}", line));
+        }
+        lines.push(String::new());
+        lines.push(format!("  {}", tr("hot-seat.finish", &[])));
+        lines
+    }
+}
@@ -0,0 +1,467 @@
+// A small JSON reader and writer, for game state snapshots
+//
+// Snapshots are only ever JSON, or a drawing of the board in .txt files.
+// They're built by hand with to_json and from_json on each type, rather
+// than derived, and there's no RON format.
+//
+// Only what snapshots need is supported: strings can't hold escapes other
+// than \" and \\, and objects keep their keys in the order they were
+// written.
+
+// How far each level of nesting is indented when written out
+const INDENT: usize = 2;
+
+#[derive(Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut parser = Parser {
+            chars: text.chars().collect(),
+            pos: 0,
+        };
+        let value = parser.value()?;
+        parser.skip_space();
+        if parser.pos < parser.chars.len() {
+            return Err(parser.error("unexpected text after the end"));
+        }
+        Ok(value)
+    }
+
+    // The value written out, with each array and object entry on a line of
+    // its own, except for arrays that only hold numbers and strings
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        self.write(&mut text, 0);
+        text.push('\n');
+        text
+    }
+
+    // The value written out on a single line
+    pub fn to_line(&self) -> String {
+        let mut text = String::new();
+        self.write_line(&mut text);
+        text
+    }
+
+    fn write_line(&self, text: &mut String) {
+        match self {
+            Json::Array(items) => {
+                text.push('[');
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        text.push_str(", ");
+                    }
+                    item.write_line(text);
+                }
+                text.push(']');
+            }
+            Json::Object(fields) => {
+                text.push('{');
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        text.push_str(", ");
+                    }
+                    Json::String(key.clone()).write_line(text);
+                    text.push_str(": ");
+                    value.write_line(text);
+                }
+                text.push('}');
+            }
+            value => value.write(text, 0),
+        }
+    }
+
+    fn write(&self, text: &mut String, depth: usize) {
+        let indent = |depth: usize| " ".repeat(depth * INDENT);
+        match self {
+            Json::Null => text.push_str("null"),
+            Json::Bool(b) => text.push_str(&b.to_string()),
+            Json::Number(n) => text.push_str(&n.to_string()),
+            Json::String(s) => {
+                text.push('"');
+                text.push_str(&s.replace('\\', "\\\\").replace('"', "\\\""));
+                text.push('"');
+            }
+            Json::Array(items) if items.iter().all(Json::is_flat) => {
+                text.push('[');
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        text.push_str(", ");
+                    }
+                    item.write(text, depth);
+                }
+                text.push(']');
+            }
+            Json::Array(items) => {
+                text.push_str("[\n");
+                for (i, item) in items.iter().enumerate() {
+                    text.push_str(&indent(depth + 1));
+                    item.write(text, depth + 1);
+                    text.push_str(if i + 1 < items.len() { ",\n" } else { "\n" });
+                }
+                text.push_str(&indent(depth));
+                text.push(']');
+            }
+            Json::Object(fields) if fields.is_empty() => text.push_str("{}"),
+            Json::Object(fields) => {
+                text.push_str("{\n");
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    text.push_str(&indent(depth + 1));
+                    Json::String(key.clone()).write(text, depth + 1);
+                    text.push_str(": ");
+                    value.write(text, depth + 1);
+                    text.push_str(if i + 1 < fields.len() { ",\n" } else { "\n" });
+                }
+                text.push_str(&indent(depth));
+                text.push('}');
+            }
+        }
+    }
+
+    fn is_flat(&self) -> bool {
+        matches!(self, Json::Number(_) | Json::String(_))
+    }
+
+    // Look up a field of an object
+    pub fn get(&self, key: &str) -> Result<&Json, String> {
+        match self {
+            Json::Object(fields) => fields
+                .iter()
+                .find(|(other, _)| other == key)
+                .map(|(_, value)| value)
+                .ok_or_else(|| format!("missing {}", key)),
+            _ => Err(format!("expected an object holding {}", key)),
+        }
+    }
+
+    // Read a field of an object, saying which field it was if it's wrong
+    pub fn at<T>(&self, key: &str, read: impl Fn(&Json) -> Result<T, String>) -> Result<T, String> {
+        read(self.get(key)?).map_err(|e| format!("{}: {}", key, e))
+    }
+
+    // Read a field that can be null
+    pub fn at_option<T>(
+        &self,
+        key: &str,
+        read: impl Fn(&Json) -> Result<T, String>,
+    ) -> Result<Option<T>, String> {
+        self.at(key, |value| value.as_option().map(&read).transpose())
+    }
+
+    pub fn as_i32(&self) -> Result<i32, String> {
+        match self {
+            Json::Number(n)
+                if n.fract() == 0.0 && i32::MIN as f64 <= *n && *n <= i32::MAX as f64 =>
+            {
+                Ok(*n as i32)
+            }
+            _ => Err("expected a whole number".to_string()),
+        }
+    }
+
+    pub fn as_u32(&self) -> Result<u32, String> {
+        match self {
+            Json::Number(n) if n.fract() == 0.0 && 0.0 <= *n && *n <= u32::MAX as f64 => {
+                Ok(*n as u32)
+            }
+            _ => Err("expected a whole number, 0 or more".to_string()),
+        }
+    }
+
+    // A position written as [x, y]
+    pub fn as_pos(&self) -> Result<(i32, i32), String> {
+        match self.as_array()? {
+            [x, y] => Ok((x.as_i32()?, y.as_i32()?)),
+            _ => Err("expected [x, y]".to_string()),
+        }
+    }
+
+    pub fn pos(x: i32, y: i32) -> Self {
+        Json::from(vec![x, y])
+    }
+
+    pub fn as_f32(&self) -> Result<f32, String> {
+        match self {
+            Json::Number(n) => Ok(*n as f32),
+            _ => Err("expected a number".to_string()),
+        }
+    }
+
+    pub fn as_bool(&self) -> Result<bool, String> {
+        match self {
+            Json::Bool(b) => Ok(*b),
+            _ => Err("expected true or false".to_string()),
+        }
+    }
+
+    pub fn as_str(&self) -> Result<&str, String> {
+        match self {
+            Json::String(s) => Ok(s),
+            _ => Err("expected a string".to_string()),
+        }
+    }
+
+    pub fn as_array(&self) -> Result<&[Json], String> {
+        match self {
+            Json::Array(items) => Ok(items),
+            _ => Err("expected an array".to_string()),
+        }
+    }
+
+    pub fn as_string(&self) -> Result<String, String> {
+        self.as_str().map(str::to_string)
+    }
+
+    // The value, or None for null
+    pub fn as_option(&self) -> Option<&Json> {
+        match self {
+            Json::Null => None,
+            value => Some(value),
+        }
+    }
+}
+
+// Written the way it would be printed, so 0.1 doesn't come out with every
+// digit of the nearest double
+impl From<f32> for Json {
+    fn from(n: f32) -> Self {
+        Json::Number(n.to_string().parse().unwrap_or(n.into()))
+    }
+}
+
+impl From<i32> for Json {
+    fn from(n: i32) -> Self {
+        Json::Number(n.into())
+    }
+}
+
+impl From<u32> for Json {
+    fn from(n: u32) -> Self {
+        Json::Number(n.into())
+    }
+}
+
+impl From<bool> for Json {
+    fn from(b: bool) -> Self {
+        Json::Bool(b)
+    }
+}
+
+impl From<&str> for Json {
+    fn from(s: &str) -> Self {
+        Json::String(s.to_string())
+    }
+}
+
+impl From<String> for Json {
+    fn from(s: String) -> Self {
+        Json::String(s)
+    }
+}
+
+impl<T: Into<Json>> From<Option<T>> for Json {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Json::Null, Into::into)
+    }
+}
+
+impl<T: Into<Json>> From<Vec<T>> for Json {
+    fn from(items: Vec<T>) -> Self {
+        Json::Array(items.into_iter().map(Into::into).collect())
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    // Errors say which line they're on, counting from 1
+    fn error(&self, message: &str) -> String {
+        let line = self.chars[..self.pos.min(self.chars.len())]
+            .iter()
+            .filter(|&&c| c == '\n')
+            .count()
+            + 1;
+        format!("line {}: {}", line, message)
+    }
+
+    fn skip_space(&mut self) {
+        while self.chars.get(self.pos).is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn next(&mut self) -> Option<char> {
+        self.skip_space();
+        let c = self.chars.get(self.pos).copied();
+        self.pos += 1;
+        c
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_space();
+        self.chars.get(self.pos).copied()
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.next() {
+            Some(c) if c == expected => Ok(()),
+            _ => Err(self.error(&format!("expected {}", expected))),
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        match self.peek() {
+            Some('{') => self.object(),
+            Some('[') => self.array(),
+            Some('"') => Ok(Json::String(self.string()?)),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.number(),
+            Some(_) => self.word(),
+            None => Err(self.error("unexpected end")),
+        }
+    }
+
+    fn object(&mut self) -> Result<Json, String> {
+        self.expect('{')?;
+        let mut fields = Vec::new();
+        if self.peek() == Some('}') {
+            self.pos += 1;
+            return Ok(Json::Object(fields));
+        }
+        loop {
+            let key = self.string()?;
+            self.expect(':')?;
+            fields.push((key, self.value()?));
+            match self.next() {
+                Some(',') => (),
+                Some('}') => return Ok(Json::Object(fields)),
+                _ => return Err(self.error("expected , or }")),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Json, String> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+        if self.peek() == Some(']') {
+            self.pos += 1;
+            return Ok(Json::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            match self.next() {
+                Some(',') => (),
+                Some(']') => return Ok(Json::Array(items)),
+                _ => return Err(self.error("expected , or ]")),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut s = String::new();
+        loop {
+            match self.chars.get(self.pos).copied() {
+                Some('"') => {
+                    self.pos += 1;
+                    return Ok(s);
+                }
+                Some('\\') => {
+                    match self.chars.get(self.pos + 1) {
+                        Some(&c) if c == '"' || c == '\\' => s.push(c),
+                        _ => return Err(self.error("unsupported escape")),
+                    }
+                    self.pos += 2;
+                }
+                Some(c) => {
+                    s.push(c);
+                    self.pos += 1;
+                }
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Json, String> {
+        let start = self.pos;
+        if self.chars.get(self.pos) == Some(&'-') {
+            self.pos += 1;
+        }
+        while self
+            .chars
+            .get(self.pos)
+            .is_some_and(|&c| c.is_ascii_digit() || "+-.eE".contains(c))
+        {
+            self.pos += 1;
+        }
+        let digits: String = self.chars[start..self.pos].iter().collect();
+        digits
+            .parse()
+            .map(Json::Number)
+            .map_err(|_| self.error("expected a number"))
+    }
+
+    fn word(&mut self) -> Result<Json, String> {
+        let start = self.pos;
+        while self.chars.get(self.pos).is_some_and(|c| c.is_alphabetic()) {
+            self.pos += 1;
+        }
+        let word: String = self.chars[start..self.pos].iter().collect();
+        match word.as_str() {
+            "null" => Ok(Json::Null),
+            "true" => Ok(Json::Bool(true)),
+            "false" => Ok(Json::Bool(false)),
+            _ => Err(self.error("expected a value")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_read_back_what_they_write() {
+        let text = r#"{"a": [1, -2.5, "x \"y\" \\"], "b": {"c": null, "d": true}, "e": []}"#;
+        let json = Json::parse(text).unwrap();
+        assert_eq!(json.at("a", |v| Ok(v.as_array()?.len())), Ok(3));
+        assert_eq!(
+            json.get("b").and_then(|b| b.at("d", Json::as_bool)),
+            Ok(true)
+        );
+        assert!(json
+            .get("b")
+            .unwrap()
+            .get("c")
+            .unwrap()
+            .as_option()
+            .is_none());
+        assert!(Json::parse(&json.to_text()).unwrap() == json);
+        assert!(Json::parse(&json.to_line()).unwrap() == json);
+        assert_eq!(
+            json.to_line(),
+            r#"{"a": [1, -2.5, "x \"y\" \\"], "b": {"c": null, "d": true}, "e": []}"#
+        );
+    }
+
+    #[test]
+    fn bad_values_say_which_line_is_wrong() {
+        assert!(Json::parse("{\n\"a\": 1\n} x")
+            .err()
+            .unwrap()
+            .starts_with("line 3: "));
+        assert!(Json::parse("[1,\n").is_err());
+        assert!(Json::parse("{\"a\" 1}").is_err());
+        assert!(Json::parse("").is_err());
+    }
+}
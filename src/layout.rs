@@ -0,0 +1,153 @@
+// Layout centers each screen in the terminal, inside a dim dotted frame
+// with a little padding, rather than drawing from the top left corner
+//
+// Screens work out a fresh layout every time they're drawn, so they follow
+// the terminal as it's resized. When the frame doesn't fit the screen is
+// drawn without it, and when even that doesn't fit it's drawn from the top
+// left as before.
+
+use crossterm::{
+    cursor,
+    style::{self, Color},
+    terminal, QueueableCommand,
+};
+use std::io::{stdout, Write};
+
+// Blank space between the frame and what's inside it
+const PADDING_COLUMNS: u16 = 2;
+const PADDING_ROWS: u16 = 1;
+
+// What the frame is drawn with
+const FRAME_GLYPH: &str = "·";
+
+// Space between columns drawn side by side
+const COLUMN_GAP: usize = 4;
+
+// Where the next row of a screen goes
+pub struct Layout {
+    left: u16,
+    row: u16,
+}
+
+impl Layout {
+    // Clear the terminal and make room for a screen in the middle of it, as
+    // many columns across and rows down as given
+    pub fn centered(width: usize, height: usize) -> Self {
+        let (columns, rows) = terminal::size().unwrap_or((80, 24));
+        let width = width.min(u16::MAX as usize) as u16;
+        let height = height.min(u16::MAX as usize) as u16;
+
+        stdout()
+            .queue(terminal::Clear(terminal::ClearType::All))
+            .unwrap();
+
+        // The frame goes around the padding, one column or row beyond it
+        let framed_width = width.saturating_add(2 * (PADDING_COLUMNS + 1));
+        let framed_height = height.saturating_add(2 * (PADDING_ROWS + 1));
+        let layout = if framed_width <= columns && framed_height <= rows {
+            let left = (columns - framed_width) / 2;
+            let top = (rows - framed_height) / 2;
+            draw_frame(left, top, framed_width, framed_height);
+            Layout {
+                left: left + PADDING_COLUMNS + 1,
+                row: top + PADDING_ROWS + 1,
+            }
+        } else {
+            Layout {
+                left: columns.saturating_sub(width) / 2,
+                row: rows.saturating_sub(height) / 2,
+            }
+        };
+
+        layout.move_to_row();
+        layout
+    }
+
+    // Move down to the start of the next row
+    pub fn next_row(&mut self) {
+        self.row += 1;
+        self.move_to_row();
+    }
+
+    // Print a whole row of text and move to the next
+    pub fn print_row(&mut self, text: &str) {
+        stdout().write_all(text.as_bytes()).unwrap();
+        self.next_row();
+    }
+
+    fn move_to_row(&self) {
+        stdout().queue(cursor::MoveTo(self.left, self.row)).unwrap();
+    }
+}
+
+// How many columns a row of text takes up
+pub fn text_width(text: &str) -> usize {
+    text.chars().count()
+}
+
+// Draw lines of plain text in the middle of the terminal
+pub fn draw_lines(lines: &[String]) {
+    let width = lines.iter().map(|line| text_width(line)).max().unwrap_or(0);
+    let mut layout = Layout::centered(width, lines.len());
+    for line in lines {
+        layout.print_row(line);
+    }
+    stdout().flush().unwrap();
+}
+
+// Draw columns of rows side by side in the middle of the terminal, with
+// lines of text beneath them all
+// Rows can have colors in them, so each comes with how wide it's drawn
+pub fn draw_columns(columns: &[Vec<(String, usize)>], lines: &[String]) {
+    let widths: Vec<usize> = columns
+        .iter()
+        .map(|column| column.iter().map(|&(_, width)| width).max().unwrap_or(0))
+        .collect();
+    let tall = columns.iter().map(|column| column.len()).max().unwrap_or(0);
+    let width = lines.iter().map(|line| text_width(line)).fold(
+        widths.iter().sum::<usize>() + COLUMN_GAP * widths.len().saturating_sub(1),
+        usize::max,
+    );
+    let mut layout = Layout::centered(width, tall + lines.len());
+
+    for row in 0..tall {
+        let mut text = String::new();
+        for (i, (column, &width)) in columns.iter().zip(&widths).enumerate() {
+            if i > 0 {
+                text += &" ".repeat(COLUMN_GAP);
+            }
+            let (cell, drawn) = column.get(row).map_or(("", 0), |(cell, w)| (cell, *w));
+            text += cell;
+            text += &" ".repeat(width - drawn);
+        }
+        layout.print_row(&text);
+    }
+    for line in lines {
+        layout.print_row(line);
+    }
+    stdout().flush().unwrap();
+}
+
+fn draw_frame(left: u16, top: u16, width: u16, height: u16) {
+    let edge = FRAME_GLYPH.repeat(width as usize);
+    stdout()
+        .queue(style::SetForegroundColor(Color::DarkGrey))
+        .unwrap();
+    for (row, glyph) in [(top, &edge), (top + height - 1, &edge)] {
+        stdout()
+            .queue(cursor::MoveTo(left, row))
+            .unwrap()
+            .write_all(glyph.as_bytes())
+            .unwrap();
+    }
+    for row in top + 1..top + height - 1 {
+        for column in [left, left + width - 1] {
+            stdout()
+                .queue(cursor::MoveTo(column, row))
+                .unwrap()
+                .write_all(FRAME_GLYPH.as_bytes())
+                .unwrap();
+        }
+    }
+    stdout().queue(style::ResetColor).unwrap();
+}
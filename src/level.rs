@@ -0,0 +1,600 @@
+// Level files describe a board layout as plain text
+//
+// The board is drawn row by row, one character per tile:
+//   '.' or ' '  empty
+//   '#'         wall
+//   'A'-'Z'     portal, each letter must appear exactly twice
+//   '~'         trap swapping left and right controls
+//   '!'         trap reversing all controls
+//
+// Lines starting with ';' are comments. Lines starting with a lowercase
+// keyword are settings rather than board rows:
+//   portal <letter> <none|cw|ccw|reverse>
+// sets how a portal pair turns the snake. Entering the first of the pair (in
+// reading order) applies the rotation, entering the second undoes it.
+//   obstacle <bounce|loop> <x,y> <x,y> ...
+// adds a moving obstacle walking between the waypoints, which must line up
+// in straight rows or columns. Coordinates count from 0 at the top left.
+//   snake <x,y> <up|down|left|right> <length>
+// starts the snake with its head at x,y facing that way and its body laid
+// out straight behind it.
+//   food <x,y> <x,y> ...
+// replaces random food with food appearing at each tile in turn.
+//   moves <count>
+// makes the level a puzzle: the snake steps once per key press and has to
+// eat all the food within that many moves.
+//   win <length|score|survive|food> <number>, or win clear
+// wins the level on reaching that length or score, surviving that many
+// seconds, eating that much food, or eating all of the level's food. A level
+// can have several, and is won by meeting any of them. Levels with a food
+// line are always won by eating all of it.
+
+use crate::diagnostic::{did_you_mean, nearest_in};
+use crate::game::{Direction, PortalId, Trap};
+use crate::obstacle::{Obstacle, Patrol};
+use crate::snake::START_LENGTH;
+use crate::win::WinCondition;
+use std::fs;
+
+// The settings a level file can have
+const SETTINGS: [&str; 6] = ["portal", "obstacle", "snake", "food", "moves", "win"];
+
+// Puzzles that ship with the game
+pub const BUILT_IN_PUZZLES: [&str; 3] = [
+    include_str!("../levels/puzzles/1.txt"),
+    include_str!("../levels/puzzles/2.txt"),
+    include_str!("../levels/puzzles/3.txt"),
+];
+
+#[derive(Clone)]
+pub struct Level {
+    pub width: i32,
+    pub height: i32,
+    pub walls: Vec<(i32, i32)>,
+    pub traps: Vec<((i32, i32), Trap)>,
+    pub portals: Vec<PortalPair>,
+    pub obstacles: Vec<Obstacle>,
+    pub snake: Option<SnakeStart>,
+    pub food: Vec<(i32, i32)>,
+    pub moves: Option<u32>,
+    pub win: Vec<WinCondition>,
+}
+
+// Where the snake starts and how long it is
+#[derive(Clone, Copy)]
+pub struct SnakeStart {
+    pub head: (i32, i32),
+    pub direction: Direction,
+    pub length: i32,
+}
+
+impl SnakeStart {
+    // The tiles the snake starts on, head first
+    pub fn body(&self) -> Vec<(i32, i32)> {
+        let mut pos = self.head;
+        let mut body = Vec::new();
+        for _ in 0..self.length {
+            body.push(pos);
+            pos = self.direction.opposite().step(pos.0, pos.1);
+        }
+        body
+    }
+}
+
+#[derive(Clone)]
+pub struct PortalPair {
+    pub id: PortalId,
+    pub a: (i32, i32),
+    pub b: (i32, i32),
+    pub rotation: Rotation,
+}
+
+// How a portal turns the snake passing through it
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Rotation {
+    None,
+    Clockwise,
+    CounterClockwise,
+    Reverse,
+}
+
+impl Rotation {
+    pub fn key(self) -> &'static str {
+        match self {
+            Rotation::None => "none",
+            Rotation::Clockwise => "cw",
+            Rotation::CounterClockwise => "ccw",
+            Rotation::Reverse => "reverse",
+        }
+    }
+
+    pub fn apply(self, direction: Direction) -> Direction {
+        match self {
+            Rotation::None => direction,
+            Rotation::Clockwise => direction.clockwise(),
+            Rotation::CounterClockwise => direction.clockwise().opposite(),
+            Rotation::Reverse => direction.opposite(),
+        }
+    }
+
+    // Get the rotation that undoes this one
+    pub fn inverse(self) -> Self {
+        match self {
+            Rotation::Clockwise => Rotation::CounterClockwise,
+            Rotation::CounterClockwise => Rotation::Clockwise,
+            other => other,
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "none" => Some(Rotation::None),
+            "cw" => Some(Rotation::Clockwise),
+            "ccw" => Some(Rotation::CounterClockwise),
+            "reverse" => Some(Rotation::Reverse),
+            _ => None,
+        }
+    }
+}
+
+impl Level {
+    // Read and parse a level file
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        Self::parse(&text).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    // An empty board of the given size
+    pub fn blank(width: i32, height: i32) -> Self {
+        Self {
+            width,
+            height,
+            walls: Vec::new(),
+            traps: Vec::new(),
+            portals: Vec::new(),
+            obstacles: Vec::new(),
+            snake: None,
+            food: Vec::new(),
+            moves: None,
+            win: Vec::new(),
+        }
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        fs::write(path, self.to_text()).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    // Write the level out in the level file format
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let pos = (x, y);
+                let portal = self.portals.iter().find(|p| p.a == pos || p.b == pos);
+                let trap = self.traps.iter().find(|&&(at, _)| at == pos);
+                text.push(match (portal, trap) {
+                    (Some(pair), _) => (b'A' + pair.id) as char,
+                    (None, Some((_, Trap::Mirror))) => '~',
+                    (None, Some((_, Trap::Reverse))) => '!',
+                    (None, None) if self.walls.contains(&pos) => '#',
+                    (None, None) => '.',
+                });
+            }
+            text.push('\n');
+        }
+
+        for pair in &self.portals {
+            if pair.rotation != Rotation::None {
+                text += &format!(
+                    "portal {} {}\n",
+                    (b'A' + pair.id) as char,
+                    pair.rotation.key()
+                );
+            }
+        }
+        for obstacle in &self.obstacles {
+            let patrol = match obstacle.patrol() {
+                Patrol::Bounce => "bounce",
+                Patrol::Loop => "loop",
+            };
+            text += &format!("obstacle {}", patrol);
+            for (x, y) in obstacle.waypoints() {
+                text += &format!(" {},{}", x, y);
+            }
+            text.push('\n');
+        }
+        if let Some(start) = self.snake {
+            text += &format!(
+                "snake {},{} {} {}\n",
+                start.head.0,
+                start.head.1,
+                start.direction.key(),
+                start.length
+            );
+        }
+        if !self.food.is_empty() {
+            text += "food";
+            for (x, y) in &self.food {
+                text += &format!(" {},{}", x, y);
+            }
+            text.push('\n');
+        }
+        if let Some(moves) = self.moves {
+            text += &format!("moves {}\n", moves);
+        }
+        for condition in &self.win {
+            text += &format!("win {}\n", condition.to_text());
+        }
+        text
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut rows: Vec<(&str, usize)> = Vec::new(); // with their line numbers
+        let mut rotations: Vec<(char, Rotation, usize)> = Vec::new();
+        let mut obstacles: Vec<(Obstacle, usize)> = Vec::new();
+        let mut snake: Option<(SnakeStart, usize)> = None;
+        let mut food: Vec<((i32, i32), usize)> = Vec::new();
+        let mut moves = None;
+        let mut win = Vec::new();
+
+        for (i, line) in text.lines().enumerate() {
+            let line_no = i + 1;
+            if line.starts_with(';') || line.trim().is_empty() {
+                continue;
+            }
+
+            if line.starts_with(|c: char| c.is_ascii_lowercase()) {
+                let words: Vec<&str> = line.split_whitespace().collect();
+                match words.as_slice() {
+                    ["portal", letter, rotation] => {
+                        let letter = parse_portal_letter(letter)
+                            .ok_or_else(|| format!("line {}: bad portal letter", line_no))?;
+                        let rotation = Rotation::parse(rotation).ok_or_else(|| {
+                            format!(
+                                "line {}: rotation must be none, cw, ccw or reverse{}",
+                                line_no,
+                                did_you_mean(rotation, &["none", "cw", "ccw", "reverse"])
+                            )
+                        })?;
+                        rotations.push((letter, rotation, line_no));
+                    }
+                    ["obstacle", patrol, waypoints @ ..] => {
+                        let patrol = match *patrol {
+                            "bounce" => Patrol::Bounce,
+                            "loop" => Patrol::Loop,
+                            _ => {
+                                return Err(format!(
+                                    "line {}: obstacle must bounce or loop{}",
+                                    line_no,
+                                    did_you_mean(patrol, &["bounce", "loop"])
+                                ))
+                            }
+                        };
+                        let waypoints = waypoints
+                            .iter()
+                            .map(|w| parse_coords(w))
+                            .collect::<Option<Vec<_>>>()
+                            .ok_or_else(|| format!("line {}: waypoints look like 3,4", line_no))?;
+                        let obstacle = Obstacle::new(patrol, &waypoints)
+                            .map_err(|e| format!("line {}: {}", line_no, e))?;
+                        obstacles.push((obstacle, line_no));
+                    }
+                    ["snake", head, direction, length] => {
+                        let head = parse_coords(head).ok_or_else(|| {
+                            format!("line {}: snake head looks like 3,4", line_no)
+                        })?;
+                        let keys: Vec<&str> = Direction::ALL.iter().map(|d| d.key()).collect();
+                        let direction = parse_direction(direction)
+                            .filter(|direction| Direction::ALL.contains(direction))
+                            .ok_or_else(|| {
+                                format!(
+                                    "line {}: direction must be up, down, left or right{}",
+                                    line_no,
+                                    did_you_mean(direction, &keys)
+                                )
+                            })?;
+                        let length = length
+                            .parse()
+                            .ok()
+                            .filter(|&length| length > 0)
+                            .ok_or_else(|| {
+                                format!(
+                                    "line {}: snake length must be positive{}",
+                                    line_no,
+                                    nearest_in(length, 1, i64::MAX)
+                                )
+                            })?;
+                        let start = SnakeStart {
+                            head,
+                            direction,
+                            length,
+                        };
+                        snake = Some((start, line_no));
+                    }
+                    ["food", tiles @ ..] => {
+                        for tile in tiles {
+                            let pos = parse_coords(tile)
+                                .ok_or_else(|| format!("line {}: food looks like 3,4", line_no))?;
+                            food.push((pos, line_no));
+                        }
+                    }
+                    ["moves", count] => {
+                        moves = Some(count.parse().ok().filter(|&count| count > 0).ok_or_else(
+                            || {
+                                format!(
+                                    "line {}: moves must be a positive number{}",
+                                    line_no,
+                                    nearest_in(count, 1, i64::MAX)
+                                )
+                            },
+                        )?);
+                    }
+                    ["win", condition @ ..] => {
+                        let condition = WinCondition::parse(condition)
+                            .map_err(|e| format!("line {}: {}", line_no, e))?;
+                        win.push(condition);
+                    }
+                    [setting, ..] if !SETTINGS.contains(setting) => {
+                        return Err(format!(
+                            "line {}: unknown setting {}{}",
+                            line_no,
+                            setting,
+                            did_you_mean(setting, &SETTINGS)
+                        ))
+                    }
+                    _ => return Err(format!("line {}: {} is missing a value", line_no, words[0])),
+                }
+                continue;
+            }
+
+            rows.push((line.trim_end(), line_no));
+        }
+
+        if rows.is_empty() {
+            return Err("level has no board".to_string());
+        }
+
+        let width = rows.iter().map(|(r, _)| r.chars().count()).max().unwrap() as i32;
+        let height = rows.len() as i32;
+
+        let mut walls = Vec::new();
+        let mut traps = Vec::new();
+        let mut portal_ends: Vec<Vec<(i32, i32)>> = vec![Vec::new(); 26];
+
+        for (y, &(row, line_no)) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                let pos = (x as i32, y as i32);
+                match c {
+                    '.' | ' ' => (),
+                    '#' => walls.push(pos),
+                    '~' => traps.push((pos, Trap::Mirror)),
+                    '!' => traps.push((pos, Trap::Reverse)),
+                    'A'..='Z' => portal_ends[(c as u8 - b'A') as usize].push(pos),
+                    _ => return Err(format!("line {}: unknown tile '{}'", line_no, c)),
+                }
+            }
+        }
+
+        let mut portals = Vec::new();
+        for (i, ends) in portal_ends.iter().enumerate() {
+            let letter = (b'A' + i as u8) as char;
+            match ends.len() {
+                0 => continue,
+                2 => portals.push(PortalPair {
+                    id: i as PortalId,
+                    a: ends[0],
+                    b: ends[1],
+                    rotation: Rotation::None,
+                }),
+                _ => return Err(format!("portal {} must appear exactly twice", letter)),
+            }
+        }
+
+        for (letter, rotation, line_no) in rotations {
+            let id = (letter as u8 - b'A') as PortalId;
+            match portals.iter_mut().find(|p| p.id == id) {
+                Some(pair) => pair.rotation = rotation,
+                None => {
+                    return Err(format!(
+                        "line {}: no portal {} on the board",
+                        line_no, letter
+                    ))
+                }
+            }
+        }
+
+        // What's in the way on a tile, if anything
+        let blocked = |(x, y): (i32, i32)| {
+            if x < 0 || x >= width || y < 0 || y >= height {
+                Some("off the board")
+            } else if walls.contains(&(x, y)) {
+                Some("in a wall")
+            } else if portal_ends.iter().flatten().any(|&end| end == (x, y)) {
+                Some("on a portal")
+            } else if traps.iter().any(|&(at, _)| at == (x, y)) {
+                Some("on a trap")
+            } else {
+                None
+            }
+        };
+
+        // Without a snake line the snake starts in the middle, facing up
+        let start = snake.map_or(
+            SnakeStart {
+                head: (width / 2, height / 2),
+                direction: Direction::Up,
+                length: START_LENGTH,
+            },
+            |(start, _)| start,
+        );
+        if let Some((start, line_no)) = snake {
+            if start.length > width * height {
+                return Err(format!(
+                    "line {}: snake length can't be more than the board's {} tiles",
+                    line_no,
+                    width * height
+                ));
+            }
+        }
+        let start_tiles = match snake {
+            Some(_) => start.body(),
+            None => vec![start.head],
+        };
+        for &(x, y) in &start_tiles {
+            if let Some(what) = blocked((x, y)) {
+                return Err(match snake {
+                    Some((_, line_no)) => {
+                        format!("line {}: snake's start is {} at {},{}", line_no, what, x, y)
+                    }
+                    None => format!(
+                        "the snake starts in the middle at {},{} without a snake line, \
+                         but that's {}",
+                        x, y, what
+                    ),
+                });
+            }
+        }
+
+        for (obstacle, line_no) in &obstacles {
+            for &(x, y) in obstacle.route() {
+                let what = match blocked((x, y)) {
+                    Some("off the board") => "leaves the board",
+                    Some("in a wall") => "crosses a wall",
+                    _ if start_tiles.contains(&(x, y)) => "crosses the snake's start",
+                    _ => continue,
+                };
+                return Err(format!(
+                    "line {}: obstacle path {} at {},{}",
+                    line_no, what, x, y
+                ));
+            }
+        }
+
+        for &((x, y), line_no) in &food {
+            if let Some(what) = blocked((x, y)) {
+                return Err(format!("line {}: food is {} at {},{}", line_no, what, x, y));
+            }
+            if start_tiles.contains(&(x, y)) {
+                return Err(format!(
+                    "line {}: food is under the snake's start at {},{}",
+                    line_no, x, y
+                ));
+            }
+        }
+        if win.contains(&WinCondition::ClearFood) && food.is_empty() {
+            return Err("win clear needs a food line".to_string());
+        }
+        if moves.is_some() && food.is_empty() {
+            return Err("puzzles need a food line".to_string());
+        }
+
+        Ok(Self {
+            width,
+            height,
+            walls,
+            traps,
+            portals,
+            obstacles: obstacles.into_iter().map(|(o, _)| o).collect(),
+            snake: snake.map(|(start, _)| start),
+            food: food.into_iter().map(|(pos, _)| pos).collect(),
+            moves,
+            win,
+        })
+    }
+}
+
+fn parse_portal_letter(s: &str) -> Option<char> {
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c @ 'A'..='Z'), None) => Some(c),
+        _ => None,
+    }
+}
+
+pub fn parse_coords(s: &str) -> Option<(i32, i32)> {
+    let mut parts = s.split(',');
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(x), Some(y), None) => Some((x.parse().ok()?, y.parse().ok()?)),
+        _ => None,
+    }
+}
+
+pub fn parse_direction(s: &str) -> Option<Direction> {
+    Direction::ALL
+        .iter()
+        .chain(&Direction::HEX)
+        .copied()
+        .find(|direction| direction.key() == s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn levels_read_back_what_they_write() {
+        let text = "#....A\n.~..!.\nA.....\nportal A cw\nobstacle bounce 1,2 4,2\n\
+                    snake 2,1 left 2\nfood 5,2\nwin score 30\n";
+        let level = Level::parse(text).unwrap();
+        assert_eq!((level.width, level.height), (6, 3));
+        assert_eq!(level.walls, vec![(0, 0)]);
+        assert_eq!(level.traps.len(), 2);
+        assert_eq!(level.portals.len(), 1);
+        assert_eq!((level.portals[0].a, level.portals[0].b), ((5, 0), (0, 2)));
+        assert!(level.portals[0].rotation == Rotation::Clockwise);
+        assert_eq!(level.snake.unwrap().body(), vec![(2, 1), (3, 1)]);
+        assert_eq!(level.food, vec![(5, 2)]);
+        assert_eq!(
+            Level::parse(&level.to_text()).unwrap().to_text(),
+            level.to_text()
+        );
+    }
+
+    #[test]
+    fn built_in_puzzles_parse() {
+        for puzzle in BUILT_IN_PUZZLES {
+            let level = Level::parse(puzzle).unwrap();
+            assert!(level.moves.is_some());
+        }
+    }
+
+    #[test]
+    fn bad_levels_say_which_line_is_wrong() {
+        let error = |text: &str| Level::parse(text).err().unwrap();
+        assert_eq!(error("; nothing\n"), "level has no board");
+        // Line numbers count the settings and comments above the board
+        assert_eq!(
+            error("; a level\nmoves 3\n...\n.x.\n"),
+            "line 4: unknown tile 'x'"
+        );
+        assert_eq!(error("A..\n...\n"), "portal A must appear exactly twice");
+        assert_eq!(
+            error("...\nportal B cw\n"),
+            "line 2: no portal B on the board"
+        );
+        assert_eq!(
+            error("...\nsnake 0,0 right 3\n"),
+            "line 2: snake's start is off the board at -1,0"
+        );
+        assert_eq!(
+            error("...\nsnake 1,0 lfet 1\n"),
+            "line 2: direction must be up, down, left or right (did you mean left?)"
+        );
+        assert_eq!(
+            error("...\nfod 1,0\n"),
+            "line 2: unknown setting fod (did you mean food?)"
+        );
+        assert_eq!(error("...\nwin clear\n"), "win clear needs a food line");
+        assert_eq!(error("...\nmoves 3\n"), "puzzles need a food line");
+    }
+
+    #[test]
+    fn starts_longer_than_the_board_are_rejected() {
+        assert_eq!(
+            Level::parse("...\nsnake 0,0 left 2000000000\n")
+                .err()
+                .unwrap(),
+            "line 2: snake length can't be more than the board's 3 tiles"
+        );
+    }
+}
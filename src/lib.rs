@@ -0,0 +1,51 @@
+// The game without the terminal around it, so other programs can run
+// boards of their own: build one from a RuleSet, steer it with a
+// Controller, ask it what's on the board and hook into what happens on it
+// The ascii-snake binary plays it through the rest of the modules
+
+// Turning, buying and picking only fail one way each, so there's nothing to
+// say about why
+#![allow(clippy::result_unit_err)]
+
+extern crate crossterm;
+extern crate rand;
+extern crate rand_chacha;
+
+pub mod controller;
+pub mod event;
+pub mod game;
+pub mod hooks;
+pub mod rules;
+pub mod topology;
+
+pub mod boss;
+pub mod campaign;
+pub mod co_op;
+pub mod diagnostic;
+pub mod high_score;
+pub mod hot_seat;
+pub mod json;
+pub mod layout;
+pub mod level;
+pub mod locale;
+pub mod mirror;
+pub mod mission;
+pub mod mods;
+pub mod nibbles;
+pub mod notify;
+pub mod obstacle;
+pub mod perk;
+pub mod power_up;
+pub mod profile;
+pub mod random_event;
+pub mod replay;
+pub mod score;
+pub mod script;
+pub mod shop;
+pub mod skin;
+pub mod snake;
+pub mod sync;
+pub mod theme;
+pub mod trace;
+pub mod versus;
+pub mod win;
@@ -0,0 +1,89 @@
+// The text the game shows its players, looked up by key in the catalog of
+// the language picked with --lang
+//
+// Catalogs have one text per line:
+//   <key> <text>
+// where {1}, {2} and so on in the text are filled in with what it's shown
+// with, in that order, so a translation can move them around. Lines
+// starting with ; are comments. Anything a catalog leaves out is shown in
+// English.
+//
+// Messages about files and settings that are wrong stay in English, the
+// same as the files themselves.
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::OnceLock;
+
+// The language used when none is picked, which every other falls back on
+pub const DEFAULT_KEY: &str = "en";
+
+// The catalogs that ship with the game, by language
+const CATALOGS: [(&str, &str); 2] = [
+    ("en", include_str!("../locales/en.txt")),
+    ("es", include_str!("../locales/es.txt")),
+];
+
+// The catalog of the language in use, once it's been picked
+static CATALOG: OnceLock<Catalog> = OnceLock::new();
+
+// The English catalog, for texts missing from the one in use
+static FALLBACK: OnceLock<Catalog> = OnceLock::new();
+
+struct Catalog {
+    texts: HashMap<String, String>,
+}
+
+impl Catalog {
+    fn parse(text: &str) -> Result<Self, String> {
+        let mut texts = HashMap::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(';') {
+                continue;
+            }
+            let (key, text) = line.split_once(' ').unwrap_or((line, ""));
+            if texts
+                .insert(key.to_string(), text.trim().to_string())
+                .is_some()
+            {
+                return Err(format!("line {}: {} is already set", i + 1, key));
+            }
+        }
+        Ok(Self { texts })
+    }
+
+    fn bundled(language: &str) -> Option<Self> {
+        let (_, text) = CATALOGS.iter().find(|&&(key, _)| key == language)?;
+        Some(Self::parse(text).unwrap())
+    }
+}
+
+// The languages there are catalogs for
+pub fn keys() -> Vec<&'static str> {
+    CATALOGS.iter().map(|&(key, _)| key).collect()
+}
+
+// Switch to a language for the rest of the run
+// Only the first language picked is used
+pub fn set(language: &str) -> Result<(), String> {
+    let catalog = Catalog::bundled(language)
+        .ok_or_else(|| format!("--lang must be one of {}", keys().join(", ")))?;
+    let _ = CATALOG.set(catalog);
+    Ok(())
+}
+
+// The text for a key in the language in use, filled in with `args`
+pub fn tr(key: &str, args: &[&dyn Display]) -> String {
+    let fallback = || FALLBACK.get_or_init(|| Catalog::bundled(DEFAULT_KEY).unwrap());
+    let catalog = CATALOG.get().unwrap_or_else(fallback);
+    let text = match catalog.texts.get(key).or_else(|| fallback().texts.get(key)) {
+        Some(text) => text,
+        None => return key.to_string(),
+    };
+    args.iter()
+        .enumerate()
+        .fold(text.clone(), |text, (i, arg)| {
+            text.replace(&format!("{{{}}}", i + 1), &arg.to_string())
+        })
+}
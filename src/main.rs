@@ -1,286 +1,1696 @@
 extern crate crossterm;
 extern crate rand;
 
-use crossterm::{cursor, terminal, QueueableCommand};
-use rand::{prelude::*, thread_rng};
-use std::io::{prelude::*, stdin, stdout};
+mod editor;
+mod viewer;
+
+use ascii_snake::{
+    campaign, co_op, controller, event, game, high_score, hooks, hot_seat, json, layout, level,
+    locale, mirror, mission, mods, notify, perk, profile, replay, rules, score, shop, skin, snake,
+    sync, theme, topology, trace, versus, win,
+};
+use campaign::{Campaign, Checkpoint};
+use co_op::Handoff;
+use controller::Controller;
+use crossterm::{
+    cursor,
+    event::{read as read_event, Event, KeyCode, KeyModifiers},
+    style::{self, Color},
+    terminal, QueueableCommand,
+};
+use event::{DeathCause, GameEvent};
+use game::{Direction, FoodPolicy, Game, Zoom};
+use hooks::SharedHooks;
+use hot_seat::HotSeat;
+use json::Json;
+use layout::Layout;
+use level::{Level, BUILT_IN_PUZZLES};
+use locale::tr;
+use mirror::Mirror;
+use mission::Status;
+use mods::Mods;
+use perk::Perk;
+use profile::Profile;
+use replay::{Recorder, Replay};
+use rules::{Preset, RuleSet};
+use score::ScoreRules;
+use shop::Upgrade;
+use skin::{Milestone, Skin};
+use snake::SnakeId;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fs;
+use std::io::{prelude::*, stdout};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::{channel, Receiver};
 use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use theme::Theme;
+use trace::Trace;
+use versus::Versus;
+use win::{WinCondition, TICKS_PER_SECOND};
 
-type SnakeVal = i32;
+// The rewind perk winds the game back this many ticks
+const REWIND_TICKS: usize = 30;
 
-fn main() {
-    use std::time::{Duration, Instant};
+// Keys that end the game early: raw mode swallows Ctrl-C, so it arrives as a key
+const QUIT_KEYS: [char; 2] = ['q', '\x03'];
 
-    // Create game
-    let mut game = Game::new(16, 16);
+// Quiet games that go on for this many updates are stopped and counted as
+// lost, and the replay viewer stops following replays there too
+const QUIET_TICK_LIMIT: u32 = 100_000;
 
-    // Start alternate terminal view and disable cursor to prepare for drawing
-    stdout()
-        .queue(terminal::EnterAlternateScreen)
-        .unwrap()
-        .queue(cursor::Hide)
-        .unwrap()
-        .flush()
-        .unwrap();
+// The daily challenge is the same board for everyone on the same day, won
+// by scoring this much on it
+const DAILY_SCORE: i32 = 1000;
 
-    terminal::enable_raw_mode().unwrap();
+// What the process exits with when a quiet game is lost, apart from 0 for a
+// win, 1 for errors and 2 for bad settings
+const EXIT_LOST: i32 = 3;
 
-    // Game loop timing information
-    let tick_rate: f32 = 10.0;
-    let mut last_game_update = Instant::now();
+// xterm's escapes for saving the window title and putting it back, so it
+// can show the score during play. Terminals without them ignore them.
+// Windows consoles can't put the title back, so it isn't changed there.
+#[cfg(not(windows))]
+const PUSH_TITLE: &str = "\x1b[22;0t";
+#[cfg(not(windows))]
+const POP_TITLE: &str = "\x1b[23;0t";
 
-    // Spawn control input channel
-    let input_channel = spawn_input_channel();
-    let mut direction_input = Direction::Up;
+// State files ending in this hold a drawing of the board instead of JSON
+const ASCII_EXTENSION: &str = ".txt";
 
-    // Game loop
-    loop {
-        // Process input
-        if let Ok(direction) = input_channel.try_recv() {
-            direction_input = direction;
+const USAGE: &str = "usage: ascii-snake edit [FILE]\n       ascii-snake mods\n       ascii-snake themes\n       ascii-snake sync\n       ascii-snake [--level FILE | --puzzle N | --campaign nibbles]\n                   [--portals N] [--obstacles 0.0..1.0]\n                   [--topology bounded|torus|hex|wrap:EDGES]\n                   [--poison 0.0..1.0] [--prey 0.0..1.0] [--food-ttl TICKS]\n                   [--nibbles] [--food-policy uniform|far|open]\n                   [--power-ups] [--lives N] [--traps N]\n                   [--fog RADIUS] [--players 1|2] [--battle-royale]\n                   [--boss] [--events] [--missions] [--meta]\n                   [--waves] [--scoring FILE] [--start X,Y]\n                   [--heading up|down|left|right] [--length N]\n                   [--theme FILE] [--zoom WxH] [--compact]\n                   [--versus SCORE | --mirror] [--co-op SECONDS|food]\n                   [--hot-seat PLAYERS] [--log FILE]\n                   [--load-state FILE] [--dump-state FILE]\n                   [--record FILE] [--daily]\n       ascii-snake --replay FILE [SETTINGS]\n       ascii-snake --quiet (--controller greedy | --replay FILE) [SETTINGS]\n       ascii-snake --preset classic|arcade|maze|nibbles|battle-royale|boss|survival\n                   [SETTINGS]\n--lang en|es goes with any of these\nOn hex boards, w e z x and i o m , steer along the diagonals, with w and i\nleaning left and s and k leaning right\nBuilds with the notify feature send notifications through notify-send, or\nosascript on macOS, and builds with the sync feature sync through curl, so\nthose need to be installed";
+
+// Command line settings
+struct Options {
+    rules: RuleSet, // the preset, changed by any other settings
+    level: Option<String>,
+    puzzle: Option<usize>,
+    campaign: Option<Campaign>,
+    meta: bool,
+    daily: bool, // play today's challenge
+    scoring: Option<String>,
+    theme: Option<String>,
+    zoom: Zoom,
+    versus: Option<i32>, // the score to race to
+    mirror: bool,
+    hot_seat: Option<usize>, // how many players take turns
+    log: Option<String>,
+    load_state: Option<String>, // a snapshot to play on from
+    dump_state: Option<String>, // where to write a snapshot when the game ends
+    quiet: bool,                // play without showing anything, printing the result
+    controller: Option<Box<dyn Controller>>,
+    replay: Option<String>, // a replay to play back
+    record: Option<String>, // where to write a replay of the game
+}
+
+impl Options {
+    fn parse(args: Vec<String>) -> Result<Self, String> {
+        let mut options = Options {
+            rules: RuleSet::default(),
+            level: None,
+            puzzle: None,
+            campaign: None,
+            meta: false,
+            daily: false,
+            scoring: None,
+            theme: None,
+            zoom: Zoom::DEFAULT,
+            versus: None,
+            mirror: false,
+            hot_seat: None,
+            log: None,
+            load_state: None,
+            dump_state: None,
+            quiet: false,
+            controller: None,
+            replay: None,
+            record: None,
+        };
+
+        // A preset replaces every rule, so it has to come before the
+        // settings that change it
+        let mut args = args.into_iter().peekable();
+        if args.peek().map(String::as_str) == Some("--preset") {
+            args.next();
+            let preset = args
+                .next()
+                .ok_or_else(|| "--preset needs a value".to_string())?;
+            options.rules = Preset::from_key(&preset)
+                .ok_or_else(|| {
+                    let keys: Vec<&str> = Preset::ALL.iter().map(|preset| preset.key()).collect();
+                    format!("--preset must be one of {}", keys.join(", "))
+                })?
+                .rules();
+        }
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| format!("{} needs a value", arg));
+            match arg.as_str() {
+                "--preset" => return Err("--preset has to come first".to_string()),
+                "--level" => options.level = Some(value()?),
+                "--puzzle" => {
+                    options.puzzle = Some(
+                        value()?
+                            .parse()
+                            .ok()
+                            .filter(|n| (1..=BUILT_IN_PUZZLES.len()).contains(n))
+                            .ok_or_else(|| {
+                                format!("--puzzle must be 1 to {}", BUILT_IN_PUZZLES.len())
+                            })?,
+                    )
+                }
+                "--campaign" => {
+                    options.campaign = Some(
+                        Campaign::from_key(&value()?)
+                            .ok_or_else(|| "--campaign must be nibbles".to_string())?,
+                    )
+                }
+                "--scoring" => options.scoring = Some(value()?),
+                "--theme" => options.theme = Some(value()?),
+                "--log" => options.log = Some(value()?),
+                "--load-state" => options.load_state = Some(value()?),
+                "--dump-state" => options.dump_state = Some(value()?),
+                "--quiet" => options.quiet = true,
+                "--controller" => {
+                    options.controller = Some(
+                        controller::from_key(&value()?)
+                            .ok_or_else(|| "--controller must be greedy".to_string())?,
+                    )
+                }
+                "--replay" => options.replay = Some(value()?),
+                "--record" => options.record = Some(value()?),
+                "--zoom" => {
+                    options.zoom = Zoom::parse(&value()?).ok_or_else(|| {
+                        "--zoom looks like 4x2, with an even width and up to 4 rows, or 1x1"
+                            .to_string()
+                    })?
+                }
+                "--compact" => options.zoom = Zoom::COMPACT,
+                "--mirror" => options.mirror = true,
+                "--hot-seat" => {
+                    options.hot_seat = Some(
+                        value()?
+                            .parse()
+                            .ok()
+                            .filter(|players| (2..=hot_seat::MAX_PLAYERS).contains(players))
+                            .ok_or_else(|| {
+                                format!("--hot-seat must be 2 to {} players", hot_seat::MAX_PLAYERS)
+                            })?,
+                    )
+                }
+                "--co-op" => {
+                    options.rules.co_op =
+                        Some(Handoff::parse(&value()?).ok_or_else(|| {
+                            "--co-op must be a number of seconds or food".to_string()
+                        })?)
+                }
+                "--versus" => {
+                    options.versus = Some(
+                        value()?
+                            .parse()
+                            .ok()
+                            .filter(|&score| score > 0)
+                            .ok_or_else(|| "--versus must be a positive score".to_string())?,
+                    )
+                }
+                "--start" => {
+                    options.rules.start = Some(
+                        level::parse_coords(&value()?)
+                            .ok_or_else(|| "--start looks like 3,4".to_string())?,
+                    )
+                }
+                "--heading" => {
+                    options.rules.heading =
+                        Some(level::parse_direction(&value()?).ok_or_else(|| {
+                            "--heading must be up, down, left or right, or a diagonal like \
+                             up-left on hex boards"
+                                .to_string()
+                        })?)
+                }
+                "--length" => {
+                    options.rules.length = Some(
+                        value()?
+                            .parse()
+                            .ok()
+                            .filter(|&length| length > 0)
+                            .ok_or_else(|| "--length must be a positive number".to_string())?,
+                    )
+                }
+                "--portals" => {
+                    options.rules.portals = value()?
+                        .parse()
+                        .map_err(|_| "--portals must be a whole number".to_string())?
+                }
+                "--obstacles" => options.rules.wall_density = parse_fraction(&arg, &value()?)?,
+                "--poison" => options.rules.poison_chance = parse_fraction(&arg, &value()?)?,
+                "--prey" => options.rules.prey_chance = parse_fraction(&arg, &value()?)?,
+                "--lives" => {
+                    options.rules.lives = value()?
+                        .parse()
+                        .ok()
+                        .filter(|&lives| lives > 0)
+                        .ok_or_else(|| "--lives must be a positive number".to_string())?
+                }
+                "--traps" => {
+                    options.rules.traps = value()?
+                        .parse()
+                        .map_err(|_| "--traps must be a whole number".to_string())?
+                }
+                "--fog" => {
+                    options.rules.fog = Some(
+                        value()?
+                            .parse()
+                            .ok()
+                            .filter(|&radius| radius > 0)
+                            .ok_or_else(|| "--fog must be a positive number".to_string())?,
+                    )
+                }
+                "--players" => {
+                    options.rules.players = value()?
+                        .parse()
+                        .ok()
+                        .filter(|&players| players == 1 || players == 2)
+                        .ok_or_else(|| "--players must be 1 or 2".to_string())?
+                }
+                "--battle-royale" => options.rules.battle_royale = true,
+                "--boss" => options.rules.boss = true,
+                "--events" => options.rules.events = true,
+                "--missions" => options.rules.missions = true,
+                "--meta" => options.meta = true,
+                "--daily" => {
+                    options.daily = true;
+                    options.rules.seed = Some(daily_seed());
+                    options
+                        .rules
+                        .win_conditions
+                        .push(WinCondition::Score(DAILY_SCORE));
+                }
+                "--waves" => options.rules.waves = true,
+                "--power-ups" => options.rules.power_ups = true,
+                "--nibbles" => options.rules.nibbles = true,
+                "--topology" => {
+                    let key = value()?;
+                    if topology::from_key(&key, 1, 1).is_none() {
+                        return Err("--topology must be bounded, torus, hex or wrap: \
+                                    and a list of left, right, top and bottom"
+                            .to_string());
+                    }
+                    options.rules.topology = key;
+                }
+                "--food-policy" => {
+                    options.rules.food_policy = FoodPolicy::parse(&value()?)
+                        .ok_or_else(|| "--food-policy must be uniform, far or open".to_string())?
+                }
+                "--food-ttl" => {
+                    options.rules.food_ttl = Some(
+                        value()?
+                            .parse()
+                            .ok()
+                            .filter(|&ttl| ttl > 0)
+                            .ok_or_else(|| "--food-ttl must be a positive number".to_string())?,
+                    )
+                }
+                _ => return Err(format!("unknown argument {}", arg)),
+            }
         }
 
-        // If the fixed time step has passed, perform the next update
-        let now = Instant::now();
-        if now - last_game_update > Duration::from_secs_f32(1.0 / tick_rate) {
-            last_game_update = now;
+        let boards = [
+            options.level.is_some(),
+            options.puzzle.is_some(),
+            options.campaign.is_some(),
+        ];
+        if boards.iter().filter(|&&set| set).count() > 1 {
+            return Err("only one of --level, --puzzle and --campaign can be used".to_string());
+        }
+        if options.campaign.is_some() && options.rules.players > 1 {
+            return Err("--campaign is single player only".to_string());
+        }
+        if options.rules.boss && options.rules.players > 1 {
+            return Err("--boss is single player only".to_string());
+        }
 
-            // Set the direction to the latest input
-            let _ = game.set_direction(direction_input);
+        let modes = [
+            options.versus.is_some(),
+            options.mirror,
+            options.rules.co_op.is_some(),
+            options.hot_seat.is_some(),
+        ];
+        if modes.iter().filter(|&&set| set).count() > 1 {
+            return Err(
+                "only one of --versus, --mirror, --co-op and --hot-seat can be used".to_string(),
+            );
+        }
+        if options.rules.co_op.is_some() && options.rules.players > 1 {
+            return Err("--co-op shares one snake, so it can't have more --players".to_string());
+        }
 
-            // Update
-            game.update();
+        // Modes that share a seed have to play the same board, with one snake each
+        let unfair = options.rules.players > 1
+            || options.puzzle.is_some()
+            || options.campaign.is_some()
+            || options.rules.boss
+            || options.rules.waves
+            || options.meta;
+        let seeded = match (options.versus, options.mirror, options.hot_seat) {
+            (Some(_), _, _) => Some("--versus"),
+            (None, true, _) => Some("--mirror"),
+            (None, false, Some(_)) => Some("--hot-seat"),
+            (None, false, None) => None,
+        };
+        if let Some(seeded) = seeded.filter(|_| unfair) {
+            return Err(format!(
+                "{} can't be used with --players, --puzzle, --campaign, --boss, --waves or --meta",
+                seeded
+            ));
+        }
+        // A snapshot already has its board and rules
+        let board = match (&options.level, options.puzzle, options.campaign, seeded) {
+            (Some(_), _, _, _) => Some("--level"),
+            (_, Some(_), _, _) => Some("--puzzle"),
+            (_, _, Some(_), _) => Some("--campaign"),
+            (_, _, _, seeded) => seeded,
+        };
+        if let Some(board) = board.filter(|_| options.load_state.is_some()) {
+            return Err(format!("--load-state can't be used with {}", board));
+        }
+        // Snapshots leave out the modes that keep state of their own
+        let unsaved = match &options.rules {
+            rules if rules.boss => Some("--boss"),
+            rules if rules.events => Some("--events"),
+            rules if rules.missions => Some("--missions"),
+            rules if rules.waves => Some("--waves"),
+            rules if rules.co_op.is_some() => Some("--co-op"),
+            _ => None,
+        };
+        let snapshot = match (&options.load_state, &options.dump_state) {
+            (Some(_), _) => Some("--load-state"),
+            (None, Some(_)) => Some("--dump-state"),
+            (None, None) => None,
+        };
+        if let Some((snapshot, unsaved)) = snapshot.zip(unsaved) {
+            return Err(format!(
+                "{} can't be used with {}, which snapshots don't keep",
+                snapshot, unsaved
+            ));
+        }
+        if let Some(seeded) = seeded.filter(|_| options.dump_state.is_some()) {
+            return Err(format!(
+                "--dump-state saves a single board, so it can't be used with {}",
+                seeded
+            ));
+        }
+        if let Some(seeded) = seeded.filter(|_| options.log.is_some()) {
+            return Err(format!(
+                "--log follows a single board, so it can't be used with {}",
+                seeded
+            ));
+        }
 
-            // Clear terminal and render
-            stdout()
-                .queue(terminal::Clear(terminal::ClearType::All))
-                .unwrap()
-                .queue(cursor::MoveTo(0, 0))
-                .unwrap();
-            game.render_ascii();
-
-            // Stop running the game loop if the player died
-            if !game.alive() {
-                println!("You died!");
-                std::thread::sleep(Duration::from_secs(1));
-                break;
+        // Quiet games are played by a controller or a replay instead
+        match (options.quiet, &options.controller, &options.replay) {
+            (true, None, None) => return Err("--quiet needs --controller or --replay".to_string()),
+            (true, Some(_), Some(_)) => {
+                return Err("only one of --controller and --replay can be used".to_string())
             }
+            (false, Some(_), _) => return Err("--controller only plays --quiet games".to_string()),
+            _ => (),
+        }
+        let several = options.rules.players > 1
+            || options.campaign.is_some()
+            || options.meta
+            || seeded.is_some();
+        if options.quiet && several {
+            return Err("--quiet plays one snake on one board, so it can't be used with --players, --campaign, --meta, --versus, --mirror or --hot-seat".to_string());
+        }
+        // Replays only keep the seed and the turns, so everything else has
+        // to come out the same from them
+        let unrepeatable = several || options.rules.waves || options.load_state.is_some();
+        let replayed = match (&options.replay, &options.record) {
+            (Some(_), _) => Some("--replay"),
+            (None, Some(_)) => Some("--record"),
+            (None, None) => None,
+        };
+        if let Some(replayed) = replayed.filter(|_| unrepeatable) {
+            return Err(format!("{} can't be used with --players, --campaign, --meta, --waves, --load-state, --versus, --mirror or --hot-seat", replayed));
         }
+
+        // The viewer goes back over a replay, so it can't follow it with
+        // anything that only goes forwards
+        let viewed = options.replay.is_some() && !options.quiet;
+        if viewed
+            && (options.log.is_some() || options.record.is_some() || options.dump_state.is_some())
+        {
+            return Err("--replay without --quiet opens the viewer, so it can't be used with --log, --record or --dump-state".to_string());
+        }
+
+        Ok(options)
     }
+}
 
-    // Reset terminal to original state
-    stdout()
-        .queue(terminal::LeaveAlternateScreen)
-        .unwrap()
-        .queue(cursor::Show)
-        .unwrap()
-        .flush()
-        .unwrap();
+// Switch to the language picked with --lang, which can go anywhere since
+// every command shows text
+// Returns the rest of the arguments
+fn pick_language(mut args: Vec<String>) -> Result<Vec<String>, String> {
+    if let Some(i) = args.iter().position(|arg| arg == "--lang") {
+        let language = args.get(i + 1).ok_or("--lang needs a value")?;
+        locale::set(language)?;
+        args.drain(i..i + 2);
+    }
+    Ok(args)
+}
 
-    terminal::disable_raw_mode().unwrap();
+// Parse a value that must lie between 0.0 and 1.0
+fn parse_fraction(arg: &str, value: &str) -> Result<f32, String> {
+    match value.parse::<f32>() {
+        Ok(v) if (0.0..=1.0).contains(&v) => Ok(v),
+        Ok(v) => Err(format!(
+            "{} must be between 0.0 and 1.0 (the nearest is {:.1})",
+            arg,
+            v.clamp(0.0, 1.0)
+        )),
+        Err(_) => Err(format!("{} must be between 0.0 and 1.0", arg)),
+    }
 }
 
-fn spawn_input_channel() -> Receiver<Direction> {
-    let (tx, rx) = channel::<Direction>();
+fn main() {
+    let args = pick_language(std::env::args().skip(1).collect()).unwrap_or_else(|e| {
+        eprintln!("{}\n{}", e, USAGE);
+        std::process::exit(2);
+    });
 
-    thread::spawn(move || loop {
-        let mut buf = [0u8; 1];
-        stdin().read_exact(&mut buf).unwrap();
-        tx.send(match buf[0] as char {
-            'w' => Direction::Up,
-            's' => Direction::Down,
-            'a' => Direction::Left,
-            'd' => Direction::Right,
-            _ => continue,
+    // The level editor, the mods menu, the theme picker and syncing are
+    // separate commands
+    match args.first().map(String::as_str) {
+        Some("edit") => {
+            enter_screen();
+            let result = editor::run(args.get(1).cloned(), &spawn_input_channel());
+            leave_screen();
+            if let Err(e) = result {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+            return;
+        }
+        Some("mods") => {
+            let mut mods = Mods::discover().unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            });
+            enter_screen();
+            let result = mods_screen(&mut mods);
+            leave_screen();
+            if let Err(e) = result {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+            return;
+        }
+        Some("themes") => {
+            let mut profile = Profile::load().unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            });
+            enter_screen();
+            themes_screen(&mut profile);
+            leave_screen();
+            return;
+        }
+        Some("sync") => {
+            match sync::now() {
+                Ok(changes) if changes.is_empty() => println!("{}", tr("sync.up-to-date", &[])),
+                Ok(changes) => changes.iter().for_each(|change| println!("{}", change)),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                }
+            }
+            return;
+        }
+        _ => (),
+    }
+
+    let mut options = Options::parse(args).unwrap_or_else(|e| {
+        eprintln!("{}\n{}", e, USAGE);
+        std::process::exit(2);
+    });
+
+    // Progress from other machines has to be fetched before any of it's
+    // read, but quiet games are left to run without a server
+    if !options.quiet {
+        sync_progress();
+    }
+
+    let mut mods = Mods::discover().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
+
+    // A campaign plays its levels in turn, anything else is a single game
+    let levels: Vec<Option<Level>> = match (&options.level, options.puzzle, options.campaign) {
+        (Some(path), _, _) => vec![Some(mods.level(path).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }))],
+        (None, Some(n), _) => vec![Some(Level::parse(BUILT_IN_PUZZLES[n - 1]).unwrap())],
+        (None, None, Some(campaign)) => campaign.levels().into_iter().map(Some).collect(),
+        (None, None, None) => vec![None],
+    };
+    let moving = levels
+        .iter()
+        .flatten()
+        .any(|level| !level.obstacles.is_empty());
+    if options.dump_state.is_some() && moving {
+        eprintln!("--dump-state can't be used with levels that have moving obstacles, which snapshots don't keep\n{}", USAGE);
+        std::process::exit(2);
+    }
+
+    // Scoring rules and themes are used as far as they can be read, with
+    // the lines that couldn't be warned about
+    if let Some(path) = &options.scoring {
+        let (scoring, warnings) = ScoreRules::load(path).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        });
+        options.rules.scoring = scoring;
+        warnings.iter().for_each(|warning| eprintln!("{}", warning));
+    }
+
+    let (theme, warnings) = match &options.theme {
+        Some(path) => Theme::load(path).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }),
+        None => mods
+            .theme()
+            .unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            })
+            .unwrap_or_default(),
+    };
+    warnings.iter().for_each(|warning| eprintln!("{}", warning));
+
+    // Scripts from mods change the rules of single games
+    let script = Some(mods.script().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }))
+    .filter(|script| !script.is_empty())
+    .map(|script| Rc::new(RefCell::new(script)));
+
+    // Every level of a run goes into the same log
+    let trace = options.log.as_ref().map(|path| {
+        let trace = Trace::create(path).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        });
+        Rc::new(RefCell::new(trace))
+    });
+
+    // A replay plays the board it was recorded on again, and a recording
+    // needs a seed to lay its board out from
+    let replay = options.replay.as_ref().map(|path| {
+        Replay::load(path).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
         })
-        .unwrap();
     });
+    if let Some(replay) = &replay {
+        options.rules.seed = Some(replay.seed);
+    }
+    let recorder = if options.record.is_some() {
+        let seed = options.rules.seed.unwrap_or_else(rand::random);
+        options.rules.seed = Some(seed);
+        Some(Rc::new(RefCell::new(Recorder::new(seed))))
+    } else {
+        None
+    };
 
-    rx
-}
+    // Everything hooked into single games
+    let mut hooks: Vec<SharedHooks> = Vec::new();
+    if let Some(script) = &script {
+        hooks.push(script.clone());
+    }
+    if let Some(trace) = &trace {
+        hooks.push(trace.clone());
+    }
+    if let Some(recorder) = &recorder {
+        hooks.push(recorder.clone());
+    }
+
+    // A snapshot replaces the first game's board and rules
+    let mut snapshot = options.load_state.as_ref().map(|path| {
+        load_state(path).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        })
+    });
+
+    // Pick a campaign back up where it was left
+    let checkpoint = match options.campaign {
+        Some(campaign) => Checkpoint::load(campaign).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }),
+        None => None,
+    };
 
-// The board containing the snake and food
-struct Game {
-    width: i32,
-    height: i32,
-    tiles: Vec<Vec<Tile>>, // tiles[x][y]
-    direction: Direction,
-    alive: bool,
-    length: i32,
-    head_x: i32,
-    head_y: i32,
-}
-
-impl Game {
-    // Create a world with the specified size
-    fn new(width: i32, height: i32) -> Self {
-        let mut new = Self {
-            width,
-            height,
-            tiles: vec![vec![Tile::Empty; height as usize]; width as usize],
-            direction: Direction::Up,
-            alive: true,
-            length: 3,
-            head_x: width / 2,
-            head_y: height / 2,
+    if options.quiet {
+        let mut game = match snapshot.take() {
+            Some(game) => game,
+            None => build(&level_rules(&options, &levels[0])),
         };
+        for hooks in &hooks {
+            game.add_hooks(hooks.clone());
+        }
+        let mut controller = match (replay, options.controller.take()) {
+            (Some(replay), _) => Box::new(replay),
+            (None, controller) => controller.unwrap(),
+        };
+        let cause = play_quiet(&mut game, controller.as_mut());
+        let won = game.solved() || game.winner() == Some(0);
+
+        let result = Json::Object(vec![
+            (
+                "result".to_string(),
+                (if won { "won" } else { "lost" }).into(),
+            ),
+            ("score".to_string(), game.score().into()),
+            ("length".to_string(), game.length().into()),
+            ("ticks".to_string(), game.ticks().into()),
+            (
+                "cause".to_string(),
+                cause.filter(|_| !won).map(DeathCause::key).into(),
+            ),
+        ]);
+        println!("{}", result.to_line());
+
+        // Bots can set high scores too, but replays only play one again
+        if options.replay.is_none() && game.players() == 1 {
+            match high_score::record(game.score()) {
+                Ok(true) => send_high_score(game.score()),
+                Ok(false) => (),
+                Err(e) => eprintln!("{}", e),
+            }
+        }
 
-        new.spawn_food();
+        let dumped = options
+            .dump_state
+            .as_ref()
+            .map(|path| dump_state(&game, path));
+        let recorded = options
+            .record
+            .as_ref()
+            .zip(recorder.as_ref())
+            .map(|(path, recorder)| recorder.borrow().replay.save(path));
+        if let Some(Err(e)) = dumped.into_iter().chain(recorded).find(Result::is_err) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        std::process::exit(if won { 0 } else { EXIT_LOST });
+    }
+
+    enter_screen();
 
-        new
+    // Without --quiet, a replay is watched in the viewer, on boards that
+    // each have scripts of their own starting from scratch
+    if let Some(replay) = &replay {
+        let rules = level_rules(&options, &levels[0]);
+        let script = script.as_ref().map(|script| script.borrow().clone());
+        let board = || {
+            let mut game = seeded_board(&rules, &theme, options.zoom, replay.seed);
+            if let Some(script) = &script {
+                game.add_hooks(Rc::new(RefCell::new(script.clone())));
+            }
+            game
+        };
+        viewer::run(board, replay, &spawn_input_channel(), QUIET_TICK_LIMIT);
+        leave_screen();
+        return;
     }
 
-    // Set the snake's direction
-    // Returns an error if direction is opposite to current direction
-    fn set_direction(&mut self, direction: Direction) -> Result<(), ()> {
-        if direction == self.direction.opposite() {
-            Err(())
-        } else {
-            self.direction = direction;
-            Ok(())
+    if let Some(target) = options.versus {
+        let rules = level_rules(&options, &levels[0]);
+        race(twin_boards(&rules, &theme, options.zoom), target);
+        leave_screen();
+        return;
+    }
+    if options.mirror {
+        let rules = level_rules(&options, &levels[0]);
+        let [plain, mirrored] = twin_boards(&rules, &theme, options.zoom);
+        play_mirror(Mirror::new(plain, mirrored));
+        leave_screen();
+        return;
+    }
+    if let Some(players) = options.hot_seat {
+        let rules = level_rules(&options, &levels[0]);
+        let seed = rand::random();
+        play_hot_seat(HotSeat::new(players), || {
+            seeded_board(&rules, &theme, options.zoom, seed)
+        });
+        leave_screen();
+        return;
+    }
+
+    // Coins and perks carry over between runs in meta-progression
+    let mut profile = if options.meta {
+        let mut profile = Profile::load().unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        });
+        progress_screen(&mut profile);
+        Some(profile)
+    } else {
+        None
+    };
+    let has_perk = |perk| profile.as_ref().is_some_and(|p: &Profile| p.has(perk));
+    let mut rewind_left = has_perk(Perk::Rewind);
+
+    // Spawn control input channel
+    let input_channel = spawn_input_channel();
+
+    // Zooming during play carries on to the next level
+    let mut zoom = options.zoom;
+
+    // A new high score is announced once the terminal is back to normal
+    let mut high_score = None;
+
+    let first = checkpoint.as_ref().map_or(0, |checkpoint| checkpoint.level);
+    let mut carried = checkpoint.map(|checkpoint| (checkpoint.score, checkpoint.lives));
+    for (i, level) in levels.iter().enumerate().skip(first) {
+        let mut game = match snapshot.take() {
+            Some(game) => game,
+            None => build(&level_rules(&options, level)),
+        };
+        game.set_theme(theme.clone());
+        game.set_zoom(zoom);
+        if let Some((score, lives)) = carried {
+            game.carry_over(score, lives);
+        }
+        if has_perk(Perk::LongerStart) {
+            game.extend_start(1);
+        }
+        if has_perk(Perk::SlowerRamp) {
+            game.set_speed_curve(game.speed_curve().slowed(perk::SLOWER_RAMP));
+        }
+        if let Some(profile) = &profile {
+            game.set_skin(profile.skin);
+        }
+        for hooks in &hooks {
+            game.add_hooks(hooks.clone());
+        }
+
+        // Quitting keeps any checkpoint for next time
+        let players = game.players();
+        let finished = play(&mut game, players, &input_channel, &mut rewind_left);
+        zoom = game.zoom();
+        let dumped = options
+            .dump_state
+            .as_ref()
+            .map(|path| dump_state(&game, path));
+        let recorded = options
+            .record
+            .as_ref()
+            .zip(recorder.as_ref())
+            .map(|(path, recorder)| recorder.borrow().replay.save(path));
+        if !finished {
+            break;
+        }
+
+        // Move on to the next level of a campaign
+        if game.solved() && i + 1 < levels.len() {
+            let mut lines = vec![
+                String::new(),
+                format!("  {}", tr("result.level-done", &[&(i + 1), &game.score()])),
+            ];
+            if let Some(campaign) = options.campaign {
+                let checkpoint = Checkpoint {
+                    level: i + 1,
+                    lives: game.lives(),
+                    score: game.score(),
+                };
+                if let Err(e) = checkpoint.save(campaign) {
+                    lines.push(format!("  {}", tr("result.no-checkpoint", &[&e])));
+                }
+            }
+            game.render_ascii(&lines);
+            thread::sleep(Duration::from_secs(1));
+            while input_channel.try_recv().is_ok() {}
+            carried = Some((game.score(), game.lives()));
+            continue;
+        }
+
+        // The game over screen shows the final board with how it went beneath
+        let mut lines = report(&game, &options, profile.as_mut());
+        if let Some(Err(e)) = dumped {
+            lines.push(format!("  {}", tr("result.no-state", &[&e])));
+        }
+        if let Some(Err(e)) = recorded {
+            lines.push(format!("  {}", tr("result.no-replay", &[&e])));
+        }
+        match mods.reach(game.score()) {
+            Ok(reached) => {
+                for achievement in reached {
+                    lines.push(format!("  {}", tr("result.achievement", &[&achievement])));
+                }
+            }
+            Err(e) => lines.push(format!("  {}", tr("result.no-achievements", &[&e]))),
         }
+        if let Some(campaign) = options.campaign {
+            if let Err(e) = Checkpoint::clear(campaign) {
+                lines.push(format!("  {}", tr("result.no-clear-checkpoint", &[&e])));
+            }
+        }
+        if game.players() == 1 && options.replay.is_none() {
+            match high_score::record(game.score()) {
+                Ok(true) => {
+                    lines.push(format!("  {}", tr("result.high-score", &[])));
+                    high_score = Some(game.score());
+                }
+                Ok(false) => (),
+                Err(e) => lines.push(format!("  {}", tr("result.no-high-score", &[&e]))),
+            }
+        }
+        set_title(&tr("title.game-over", &[&game.score()]));
+        game.render_ascii(&lines);
+        thread::sleep(Duration::from_secs(1));
+        break;
     }
 
-    fn alive(&self) -> bool {
-        self.alive
+    leave_screen();
+    if let Some(score) = high_score {
+        send_high_score(score);
     }
+    sync_progress();
+}
 
-    fn update(&mut self) {
-        // Move head
-        match self.direction {
-            Direction::Up => self.head_y -= 1,
-            Direction::Down => self.head_y += 1,
-            Direction::Left => self.head_x -= 1,
-            Direction::Right => self.head_x += 1,
+// Sync progress with the server, if syncing's been set up
+fn sync_progress() {
+    if sync::set_up() {
+        if let Err(e) = sync::now() {
+            eprintln!("{}", tr("sync.failed", &[&e]));
         }
+    }
+}
 
-        // Check for out of bounds
-        if self.head_x < 0
-            || self.head_x >= self.width
-            || self.head_y < 0
-            || self.head_y >= self.height
-        {
-            // Die if out of bounds
-            self.alive = false;
+// Announce a new high score on the desktop, in builds that can
+fn send_high_score(score: i32) {
+    if let Err(e) = notify::high_score(score) {
+        eprintln!("{}", tr("notify.failed", &[&e]));
+    }
+}
+
+// Read a game state snapshot, or a drawing of the board from a .txt file
+fn load_state(path: &str) -> Result<Game, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    let game = if path.ends_with(ASCII_EXTENSION) {
+        Game::from_ascii(&text)
+    } else {
+        Json::parse(&text).and_then(|json| Game::from_json(&json))
+    };
+    game.map_err(|e| format!("{}: {}", path, e))
+}
+
+// Write a game state snapshot, or a drawing of the board to a .txt file
+fn dump_state(game: &Game, path: &str) -> Result<(), String> {
+    let text = if path.ends_with(ASCII_EXTENSION) {
+        game.to_ascii()
+    } else {
+        game.to_json().to_text()
+    };
+    fs::write(path, text).map_err(|e| format!("{}: {}", path, e))
+}
+
+// The rules for playing a level, from the command line settings
+fn level_rules(options: &Options, level: &Option<Level>) -> RuleSet {
+    let mut rules = options.rules.clone();
+    rules.level = level.clone();
+    if let Some(campaign) = options.campaign {
+        rules.nibbles |= campaign.numbered_food();
+        rules.win_conditions.push(campaign.win_condition());
+    }
+    rules
+}
+
+// Make a game playing by the rules, stopping if they don't fit its board
+fn build(rules: &RuleSet) -> Game {
+    rules.build().unwrap_or_else(|e| {
+        leave_screen();
+        eprintln!("{}", e);
+        std::process::exit(1);
+    })
+}
+
+// Set up two boards the same way, with the same seed
+fn twin_boards(rules: &RuleSet, theme: &Theme, zoom: Zoom) -> [Game; 2] {
+    let seed = rand::random();
+    let board = || seeded_board(rules, theme, zoom, seed);
+    [board(), board()]
+}
+
+// Set up a board by the rules, laid out from `seed`
+fn seeded_board(rules: &RuleSet, theme: &Theme, zoom: Zoom, seed: u64) -> Game {
+    let mut game = build(&RuleSet {
+        seed: Some(seed),
+        ..rules.clone()
+    });
+    game.set_theme(theme.clone());
+    game.set_zoom(zoom);
+    game
+}
+
+// Give each player a full run on the same board in turn, then rank them
+// Quitting any run ends the whole session
+fn play_hot_seat(mut hot_seat: HotSeat, board: impl Fn() -> Game) {
+    let input_channel = spawn_input_channel();
+    let wait_for_key = || {
+        while input_channel.try_recv().is_ok() {}
+        !matches!(input_channel.recv(), Ok(key) if QUIT_KEYS.contains(&key))
+    };
+
+    let mut zoom = None;
+    while let Some(player) = hot_seat.next_player() {
+        set_title(&tr("title.hot-seat", &[&(player + 1)]));
+        layout::draw_lines(&hot_seat.handoff_lines(player));
+        if !wait_for_key() {
             return;
         }
 
-        // Check for collision
-        match self.tiles[self.head_x as usize][self.head_y as usize] {
-            Tile::Snake(_) => {
-                // Die if collided
-                self.alive = false;
-                return;
+        // Zooming carries on from one turn to the next
+        let mut game = board();
+        if let Some(zoom) = zoom {
+            game.set_zoom(zoom);
+        }
+        if !play(&mut game, 1, &input_channel, &mut false) {
+            return;
+        }
+        zoom = Some(game.zoom());
+        set_title(&tr("title.game-over", &[&game.score()]));
+        game.render_ascii(&[
+            String::new(),
+            format!("  {}", tr("result.game-over", &[&game.score()])),
+        ]);
+        thread::sleep(Duration::from_secs(1));
+        hot_seat.record(game.score());
+    }
+
+    set_title(&tr("title.ranking", &[]));
+    layout::draw_lines(&hot_seat.ranking_lines());
+    wait_for_key();
+}
+
+// Play a split-screen race between two players
+fn race(boards: [Game; 2], target: i32) {
+    let mut versus = Versus::new(boards.into(), target);
+
+    let input_channel = spawn_input_channel();
+    if let Some(outcome) = play_versus(&mut versus, &input_channel) {
+        draw_versus(
+            &versus,
+            &[
+                String::new(),
+                format!("  {}", outcome.describe(versus.target())),
+            ],
+        );
+        thread::sleep(Duration::from_secs(1));
+    }
+}
+
+// Run a race until it's decided, steering each board with its player's keys
+// Returns how it went, or `None` if the players quit first
+fn play_versus(versus: &mut Versus, input_channel: &Receiver<char>) -> Option<versus::Outcome> {
+    let tick = Duration::from_secs_f32(1.0 / TICKS_PER_SECOND as f32);
+    let mut last_update = Instant::now();
+    let mut direction_inputs: Vec<Direction> = versus
+        .boards()
+        .iter()
+        .map(|board| board.direction(0))
+        .collect();
+    let mut size = terminal::size().ok();
+
+    loop {
+        if terminal::size().ok() != size {
+            size = terminal::size().ok();
+            draw_versus(versus, &[]);
+        }
+
+        while let Ok(key) = input_channel.try_recv() {
+            if QUIT_KEYS.contains(&key) {
+                return None;
             }
-            Tile::Food => {
-                // Eat
-                self.length += 1;
-                self.spawn_food();
-                self.tiles[self.head_x as usize][self.head_y as usize] = Tile::Snake(0);
+
+            // Both boards zoom together so they stay side by side
+            let zoom = match key {
+                '+' | '=' => Some(versus.boards()[0].zoom().zoomed_in()),
+                '-' => Some(versus.boards()[0].zoom().zoomed_out()),
+                _ => None,
+            };
+            if let Some(zoom) = zoom {
+                for player in 0..direction_inputs.len() {
+                    versus.board_mut(player).set_zoom(zoom);
+                }
+                draw_versus(versus, &[]);
+                continue;
             }
-            Tile::Empty => {
-                // Set head position to snake tile
-                self.tiles[self.head_x as usize][self.head_y as usize] = Tile::Snake(0);
+
+            // Each player steers the only snake on their own board
+            if let Some((player, direction)) = steer(key) {
+                direction_inputs[player as usize] = direction;
             }
         }
 
-        // Update the grid's snake values
-        for x in 0..self.width {
-            for y in 0..self.height {
-                match self.tiles[x as usize][y as usize] {
-                    Tile::Snake(val) => {
-                        self.tiles[x as usize][y as usize] = if val >= self.length {
-                            Tile::Empty
-                        } else {
-                            Tile::Snake(val + 1)
-                        }
-                    }
-                    _ => (),
+        let now = Instant::now();
+        if now - last_update > tick {
+            last_update = now;
+            for (player, &input) in direction_inputs.iter().enumerate() {
+                let board = versus.board_mut(player);
+                let _ = board.set_direction(0, board.input_transform(0).apply(input));
+            }
+            versus.update();
+            draw_versus(versus, &[]);
+
+            if let Some(outcome) = versus.outcome() {
+                return Some(outcome);
+            }
+        }
+    }
+}
+
+// Run mirror mode until either snake dies, steering both with wasd
+fn play_mirror(mut mirror: Mirror) {
+    let input_channel = spawn_input_channel();
+    let tick = Duration::from_secs_f32(1.0 / TICKS_PER_SECOND as f32);
+    let mut last_update = Instant::now();
+    let mut direction_input = mirror.boards()[0].direction(0);
+    let mut size = terminal::size().ok();
+
+    while !mirror.finished() {
+        if terminal::size().ok() != size {
+            size = terminal::size().ok();
+            draw_mirror(&mirror, &[]);
+        }
+
+        while let Ok(key) = input_channel.try_recv() {
+            if QUIT_KEYS.contains(&key) {
+                return;
+            }
+            let zoom = match key {
+                '+' | '=' => Some(mirror.boards()[0].zoom().zoomed_in()),
+                '-' => Some(mirror.boards()[0].zoom().zoomed_out()),
+                _ => None,
+            };
+            if let Some(zoom) = zoom {
+                for board in mirror.boards_mut() {
+                    board.set_zoom(zoom);
                 }
+                draw_mirror(&mirror, &[]);
+                continue;
+            }
+            if let Some((0, direction)) = steer(key) {
+                direction_input = direction;
             }
         }
+
+        let now = Instant::now();
+        if now - last_update > tick {
+            last_update = now;
+            mirror.steer(direction_input);
+            mirror.update();
+            draw_mirror(&mirror, &[]);
+        }
     }
 
-    fn spawn_food(&mut self) {
-        loop {
-            let tile = &mut self.tiles[thread_rng().gen_range(0, self.width) as usize]
-            [thread_rng().gen_range(0, self.height) as usize];
-            if *tile == Tile::Empty {
-                *tile = Tile::Food;  
+    let result = if mirror.won() {
+        tr("result.mirror-won", &[&mirror.score()])
+    } else {
+        tr("result.died", &[&mirror.score()])
+    };
+    draw_mirror(&mirror, &[String::new(), format!("  {}", result)]);
+    thread::sleep(Duration::from_secs(1));
+}
+
+// Run the game loop until the game is decided
+// Returns false if the player quit first
+fn play(
+    game: &mut Game,
+    players: usize,
+    input_channel: &Receiver<char>,
+    rewind_left: &mut bool,
+) -> bool {
+    let mut history: VecDeque<Game> = VecDeque::new();
+
+    // Game loop timing information
+    let tick_rate = TICKS_PER_SECOND as f32;
+    let mut last_game_update = Instant::now();
+
+    let mut direction_inputs: Vec<Direction> = (0..players)
+        .map(|player| game.direction(player as SnakeId))
+        .collect();
+
+    // Puzzles only move when the player does, so show the board straight away
+    let turn_based = game.turn_based();
+    if turn_based {
+        draw(game);
+    }
+
+    // Redraw when the terminal is resized, for when the game is waiting on
+    // the player
+    let mut size = terminal::size().ok();
+
+    // Game loop
+    loop {
+        if terminal::size().ok() != size {
+            size = terminal::size().ok();
+            draw(game);
+        }
+
+        // Process input
+        let mut stepped = false;
+        while let Ok(key) = input_channel.try_recv() {
+            if QUIT_KEYS.contains(&key) {
+                return false;
+            }
+
+            // Zooming works any time, even in the shop
+            let zoom = match key {
+                '+' | '=' => Some(game.zoom().zoomed_in()),
+                '-' => Some(game.zoom().zoomed_out()),
+                _ => None,
+            };
+            if let Some(zoom) = zoom {
+                game.set_zoom(zoom);
+                draw(game);
+                continue;
+            }
+
+            // The game is paused while the shop is open
+            if game.shop_open() {
+                shop_key(game, key);
+                continue;
+            }
+
+            // Co-op players take turns on one snake, so only some keys count
+            let (snake, direction) = match steer(key) {
+                Some((player, direction)) => match game.controller(player as usize) {
+                    Some(snake) => (snake, direction),
+                    None => continue,
+                },
+                None => continue,
+            };
+            if let Some(input) = direction_inputs.get_mut(snake as usize) {
+                *input = direction;
+            }
+            if turn_based && snake == 0 {
+                // Keys that would turn the snake back on itself don't use up a move
+                stepped = game
+                    .set_direction(0, game.input_transform(0).apply(direction))
+                    .is_ok();
                 break;
-            } 
+            }
+        }
+
+        // If the fixed time step has passed, or the player stepped in a
+        // puzzle, perform the next update
+        let now = Instant::now();
+        let due = if game.shop_open() {
+            false
+        } else if turn_based {
+            stepped
+        } else {
+            now - last_game_update > Duration::from_secs_f32(1.0 / (tick_rate * game.speed()))
+        };
+        if due {
+            last_game_update = now;
+
+            // Set each direction to the latest input, scrambled by any active traps
+            for (player, &input) in direction_inputs.iter().enumerate() {
+                let player = player as SnakeId;
+                let _ = game.set_direction(player, game.input_transform(player).apply(input));
+            }
+
+            // Update, remembering recent states to rewind to
+            if *rewind_left {
+                history.push_back(game.clone());
+                if history.len() > REWIND_TICKS {
+                    history.pop_front();
+                }
+            }
+            game.update();
+
+            draw(game);
+
+            // Spend the rewind on a lost run instead of ending it
+            let lost = !game.solved() && game.winner() != Some(0);
+            if game.finished() && lost && *rewind_left && players == 1 {
+                if let Some(earlier) = history.pop_front() {
+                    let zoom = game.zoom();
+                    *game = earlier;
+                    game.set_zoom(zoom);
+                }
+                *rewind_left = false;
+                direction_inputs[0] = game.direction(0);
+                draw(game);
+                continue;
+            }
+
+            // Stop running the game loop once the game is decided
+            if game.finished() {
+                return true;
+            }
+        }
+    }
+}
+
+// Let a controller play the first snake until the game is decided, without
+// showing anything or waiting between updates
+// Returns what the first snake last died of, if it did
+fn play_quiet(game: &mut Game, controller: &mut dyn Controller) -> Option<DeathCause> {
+    let mut cause = None;
+    while !game.finished() && game.ticks() < QUIET_TICK_LIMIT {
+        // Nobody is there to shop
+        if game.shop_open() {
+            game.close_shop();
+        }
+        if let Some(direction) = controller.steer(game) {
+            let _ = game.set_direction(0, direction);
+        }
+        game.update();
+        for &event in game.happened() {
+            if let GameEvent::Died {
+                snake: 0,
+                cause: died,
+            } = event
+            {
+                cause = Some(died);
+            }
         }
     }
+    cause
+}
 
-    fn render_ascii(&self) {
-        // Top border
-        stdout().write("  ".as_bytes()).unwrap();
-        for _x in 0..self.width {
-            stdout().write("--".as_bytes()).unwrap();
+// Say how a finished game went, paying out into the profile in meta-progression
+fn report(game: &Game, options: &Options, profile: Option<&mut Profile>) -> Vec<String> {
+    let mut lines = vec![String::new()];
+    let score = game.score();
+    if let (Some(condition), Some(campaign)) = (game.won(), options.campaign) {
+        let (name, won) = (campaign.name(), condition.describe());
+        lines.push(format!(
+            "  {}",
+            tr("result.campaign-won", &[&name, &won, &score])
+        ));
+    } else if let Some(condition) = game.won() {
+        lines.push(format!(
+            "  {}",
+            tr("result.level-won", &[&condition.describe(), &score])
+        ));
+    } else if game.turn_based() && game.alive() {
+        lines.push(format!("  {}", tr("result.out-of-moves", &[&score])));
+    } else if options.rules.boss {
+        match game.winner() {
+            Some(0) => lines.push(format!("  {}", tr("result.beat-boss", &[&score]))),
+            _ => lines.push(format!("  {}", tr("result.boss-won", &[&score]))),
+        }
+    } else if game.players() == 1 {
+        // The update that ended the game is the one the snake died on
+        let cause = game.happened().iter().find_map(|event| match *event {
+            GameEvent::Died { snake: 0, cause } => Some(cause),
+            _ => None,
+        });
+        match cause {
+            Some(cause) => lines.push(format!(
+                "  {}",
+                tr("result.died-of", &[&cause.describe(0), &score])
+            )),
+            None => lines.push(format!("  {}", tr("result.died", &[&score]))),
         }
-        stdout().write("\n".as_bytes()).unwrap();
+    } else {
+        match game.winner() {
+            Some(player) => lines.push(format!("  {}", tr("result.player-wins", &[&(player + 1)]))),
+            None => lines.push(format!("  {}", tr("draw", &[]))),
+        }
+    }
+    if !game.missions().is_empty() {
+        let complete = game
+            .missions()
+            .iter()
+            .filter(|mission| mission.status == Status::Complete)
+            .count();
+        lines.push(format!(
+            "  {}",
+            tr("result.missions", &[&complete, &game.missions().len()],)
+        ));
+    }
+    if let Some(profile) = profile {
+        let earned = profile.reward(game.score());
+        lines.push(format!(
+            "  {}",
+            tr("result.coins", &[&earned, &profile.coins])
+        ));
 
-        for y in 0..self.height {
-            // Left border
-            stdout().write("| ".as_bytes()).unwrap();
+        let mut reached = Vec::new();
+        if game.score() >= 500 {
+            reached.push(Milestone::Score500);
+        }
+        if options.rules.boss && game.winner() == Some(0) {
+            reached.push(Milestone::BeatBoss);
+        }
+        if game.turn_based() && game.solved() {
+            reached.push(Milestone::SolvePuzzle);
+        }
+        if options.daily && game.winner() == Some(0) {
+            reached.push(Milestone::WinDaily);
+        }
+        for milestone in reached {
+            if let Some(skin) = profile.reach(milestone) {
+                lines.push(format!("  {}", tr("result.skin", &[&skin.name()])));
+            }
+        }
 
-            // Tiles
-            for x in 0..self.width {
-                stdout()
-                    .write(self.tiles[x as usize][y as usize].ascii_rep().as_bytes())
-                    .unwrap();
+        if let Err(e) = profile.save() {
+            lines.push(format!("  {}", tr("result.no-profile", &[&e])));
+        }
+    }
+    lines
+}
+
+// Whether the alternate screen is up, so it's only left once it's been
+// entered, and --quiet output isn't mixed with terminal codes
+static ON_SCREEN: AtomicBool = AtomicBool::new(false);
+
+// Start alternate terminal view and disable cursor to prepare for drawing
+fn enter_screen() {
+    ON_SCREEN.store(true, Ordering::SeqCst);
+    let mut out = stdout();
+    out.queue(terminal::EnterAlternateScreen)
+        .unwrap()
+        .queue(cursor::Hide)
+        .unwrap();
+    #[cfg(not(windows))]
+    out.queue(style::Print(PUSH_TITLE)).unwrap();
+    out.flush().unwrap();
+
+    terminal::enable_raw_mode().unwrap();
+}
+
+// Reset terminal to original state, if it was changed
+fn leave_screen() {
+    if !ON_SCREEN.swap(false, Ordering::SeqCst) {
+        return;
+    }
+    let mut out = stdout();
+    out.queue(terminal::LeaveAlternateScreen)
+        .unwrap()
+        .queue(cursor::Show)
+        .unwrap();
+    #[cfg(not(windows))]
+    out.queue(style::Print(POP_TITLE)).unwrap();
+    out.flush().unwrap();
+
+    terminal::disable_raw_mode().unwrap();
+}
+
+// Let the player spend coins on perks before a run
+// Number keys choose a perk and anything else starts the game
+fn progress_screen(profile: &mut Profile) {
+    loop {
+        let mut lines = vec![
+            (
+                format!("  {}", tr("progress.perks", &[&profile.coins])),
+                None,
+            ),
+            (String::new(), None),
+        ];
+        for (i, perk) in Perk::ALL.iter().enumerate() {
+            let status = if profile.has(*perk) {
+                tr("progress.owned", &[])
+            } else {
+                tr("coins", &[&perk.cost()])
+            };
+            let line = format!(
+                "  {}. {}: {} ({})",
+                i + 1,
+                perk.name(),
+                perk.description(),
+                status
+            );
+            lines.push((line, None));
+        }
+        lines.push((String::new(), None));
+        lines.push((format!("  {}", tr("progress.prompt", &[])), None));
+        print_menu(&lines);
+
+        let perk = match read_key().and_then(|key| key.to_digit(10)) {
+            Some(n) if n > 0 => Perk::ALL.get(n as usize - 1),
+            _ => None,
+        };
+        match perk {
+            Some(&perk) if profile.buy(perk).is_ok() => {
+                let _ = profile.save();
             }
+            Some(_) => (),
+            None => return,
+        }
+    }
+}
 
-            // Right border
-            stdout().write(" |\n".as_bytes()).unwrap();
+// Let the player pick a skin for their snake, with the ones still locked
+// greyed out beside what it takes to unlock them
+// Number keys pick a skin and anything else leaves
+fn themes_screen(profile: &mut Profile) {
+    loop {
+        let mut lines = vec![
+            (format!("  {}", tr("themes.title", &[])), None),
+            (String::new(), None),
+        ];
+        for (i, skin) in Skin::ALL.iter().enumerate() {
+            if profile.unlocked(*skin) {
+                let mark = if profile.skin == *skin {
+                    format!(" {}", tr("themes.in-use", &[]))
+                } else {
+                    String::new()
+                };
+                lines.push((format!("  {}. {}{}", i + 1, skin.name(), mark), None));
+            } else {
+                let locked = tr(
+                    "themes.locked",
+                    &[&skin.unlocked_by().unwrap().description()],
+                );
+                let line = format!("  {}. {} ({})", i + 1, skin.name(), locked);
+                lines.push((line, Some(Color::DarkGrey)));
+            }
         }
+        lines.push((String::new(), None));
+        lines.push((format!("  {}", tr("themes.prompt", &[])), None));
+        print_menu(&lines);
 
-        // Bottom border
-        stdout().write("  ".as_bytes()).unwrap();
-        for _x in 0..self.width {
-            stdout().write("--".as_bytes()).unwrap();
+        let skin = match read_key().and_then(|key| key.to_digit(10)) {
+            Some(n) if n > 0 => Skin::ALL.get(n as usize - 1),
+            _ => None,
+        };
+        match skin {
+            Some(&skin) if profile.pick(skin).is_ok() => {
+                let _ = profile.save();
+            }
+            Some(_) => (),
+            None => return,
+        }
+    }
+}
+
+// Draw a menu's lines in the middle of the screen, each in its own color if
+// it has one
+fn print_menu(lines: &[(String, Option<Color>)]) {
+    let width = lines
+        .iter()
+        .map(|(line, _)| layout::text_width(line))
+        .max()
+        .unwrap_or(0);
+    let mut layout = Layout::centered(width, lines.len());
+    for (line, color) in lines {
+        if let Some(color) = color {
+            stdout().queue(style::SetForegroundColor(*color)).unwrap();
+            layout.print_row(line);
+            stdout().queue(style::ResetColor).unwrap();
+        } else {
+            layout.print_row(line);
         }
-        stdout().write("\n".as_bytes()).unwrap();
     }
+    stdout().flush().unwrap();
 }
 
-// Snake direction controls
-#[derive(Clone, Copy, PartialEq, Eq)]
-enum Direction {
-    Up,
-    Down,
-    Left,
-    Right,
+// Today's daily challenge seed: the number of days since 1970
+fn daily_seed() -> u64 {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    now.as_secs() / (24 * 60 * 60)
 }
 
-impl Direction {
-    // Get the opposite direction
-    fn opposite(self) -> Self {
-        match self {
-            Direction::Up => Direction::Down,
-            Direction::Down => Direction::Up,
-            Direction::Left => Direction::Right,
-            Direction::Right => Direction::Left,
+// List the mods that were found and let the player switch them on and off
+fn mods_screen(mods: &mut Mods) -> Result<(), String> {
+    loop {
+        let mut lines = vec![
+            (format!("  {}", tr("mods.title", &[])), None),
+            (String::new(), None),
+        ];
+        if mods.mods.is_empty() {
+            lines.push((format!("  {}", tr("mods.none", &[])), Some(Color::DarkGrey)));
+        }
+
+        // Mods that are switched off are greyed out
+        for (i, m) in mods.mods.iter().enumerate() {
+            let (status, color) = if m.enabled {
+                (tr("mods.on", &[]), None)
+            } else {
+                (tr("mods.off", &[]), Some(Color::DarkGrey))
+            };
+            lines.push((format!("  {}. {} ({})", i + 1, m.name, status), color));
+            if !m.description.is_empty() {
+                lines.push((format!("     {}", m.description), color));
+            }
+            if !m.achievements.is_empty() {
+                let achieved = m.achieved.len().min(m.achievements.len());
+                let line = format!(
+                    "     {}",
+                    tr("mods.achievements", &[&achieved, &m.achievements.len()])
+                );
+                lines.push((line, color));
+            }
+        }
+        lines.push((String::new(), None));
+        lines.push((format!("  {}", tr("mods.prompt", &[])), None));
+        print_menu(&lines);
+
+        match read_key().and_then(|key| key.to_digit(10)) {
+            Some(n) if n > 0 && n as usize <= mods.mods.len() => mods.toggle(n as usize - 1)?,
+            _ => return Ok(()),
         }
     }
 }
 
-// Possible states of a tile
-#[derive(Clone, Copy, PartialEq, Eq)]
-enum Tile {
-    Empty,
-    Food,
-    Snake(SnakeVal),
+// Clear terminal and render, with the shop beneath the board between waves
+// and the score in the window title
+fn draw(game: &Game) {
+    set_title(&tr("title.playing", &[&game.score(), &game.length()]));
+    game.render_ascii(&shop_lines(game));
+}
+
+// The title is sent with whatever's drawn next
+fn set_title(title: &str) {
+    if cfg!(not(windows)) {
+        stdout().queue(terminal::SetTitle(title)).unwrap();
+    }
+}
+
+// Render a race, with both scores in the window title
+fn draw_versus(versus: &Versus, footer: &[String]) {
+    let scores: Vec<i32> = versus.boards().iter().map(Game::score).collect();
+    set_title(&tr("title.versus", &[&scores[0], &scores[1]]));
+    versus.render_ascii(footer);
+}
+
+// Render mirror mode, with the total score in the window title
+fn draw_mirror(mirror: &Mirror, footer: &[String]) {
+    set_title(&tr("title.mirror", &[&mirror.score()]));
+    mirror.render_ascii(footer);
+}
+
+// The shop's lines, if it's open
+fn shop_lines(game: &Game) -> Vec<String> {
+    let waves = match game.waves().filter(|_| game.shop_open()) {
+        Some(waves) => waves,
+        None => return Vec::new(),
+    };
+    let mut lines = vec![
+        String::new(),
+        format!("  {}", tr("shop.cleared", &[&waves.wave, &waves.coins])),
+    ];
+    for (i, upgrade) in Upgrade::ALL.iter().enumerate() {
+        let cost = tr("coins", &[&upgrade.cost()]);
+        lines.push(format!("  {}. {} ({})", i + 1, upgrade.name(), cost));
+    }
+    lines.push(format!("  {}", tr("shop.prompt", &[])));
+    lines
 }
 
-impl Tile {
-    // Get a two-character ASCII representation
-    fn ascii_rep(self) -> &'static str {
-        match self {
-            Tile::Empty => "  ",
-            Tile::Food => "><",
-            Tile::Snake(_) => "██",
+// Number keys buy upgrades in the shop and space leaves it
+fn shop_key(game: &mut Game, key: char) {
+    if key == ' ' {
+        game.close_shop();
+        draw(game);
+        return;
+    }
+    let upgrade = key
+        .to_digit(10)
+        .and_then(|n| Upgrade::ALL.get((n as usize).checked_sub(1)?));
+    if let Some(&upgrade) = upgrade {
+        if game.buy(upgrade).is_ok() {
+            draw(game);
         }
     }
 }
+
+// Player 1 steers with wasd and player 2 with ijkl, with e z x and o m , for
+// the diagonals on hex boards
+fn steer(key: char) -> Option<(SnakeId, Direction)> {
+    match key {
+        'w' => Some((0, Direction::Up)),
+        's' => Some((0, Direction::Down)),
+        'a' => Some((0, Direction::Left)),
+        'd' => Some((0, Direction::Right)),
+        'e' => Some((0, Direction::UpRight)),
+        'z' => Some((0, Direction::DownLeft)),
+        'x' => Some((0, Direction::DownRight)),
+        'i' => Some((1, Direction::Up)),
+        'k' => Some((1, Direction::Down)),
+        'j' => Some((1, Direction::Left)),
+        'l' => Some((1, Direction::Right)),
+        'o' => Some((1, Direction::UpRight)),
+        'm' => Some((1, Direction::DownLeft)),
+        ',' => Some((1, Direction::DownRight)),
+        _ => None,
+    }
+}
+
+// Wait for a key, as the character it types, or None once there's no more
+// input
+// Keys are read as crossterm events rather than straight from stdin, since
+// the Windows console doesn't send them as text. Keys that don't type
+// anything are given as what a Unix terminal in raw mode sends for them, and
+// the rest, like the arrow keys, are skipped.
+fn read_key() -> Option<char> {
+    loop {
+        let key = match read_event().ok()? {
+            Event::Key(key) => key,
+            _ => continue,
+        };
+        return Some(match key.code {
+            KeyCode::Char(c)
+                if key.modifiers.contains(KeyModifiers::CONTROL) && c.is_ascii_alphabetic() =>
+            {
+                (c.to_ascii_lowercase() as u8 - b'a' + 1) as char
+            }
+            KeyCode::Char(c) => c,
+            KeyCode::Enter => '\r',
+            KeyCode::Tab => '\t',
+            KeyCode::Backspace => '\x7f',
+            KeyCode::Esc => '\x1b',
+            _ => continue,
+        });
+    }
+}
+
+fn spawn_input_channel() -> Receiver<char> {
+    let (tx, rx) = channel::<char>();
+
+    thread::spawn(move || {
+        while let Some(key) = read_key() {
+            if tx.send(key).is_err() {
+                return;
+            }
+        }
+    });
+
+    rx
+}
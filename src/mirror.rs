@@ -0,0 +1,80 @@
+// Mirror mode, where one player steers snakes on two boards at once
+//
+// Both boards are set up the same way and both snakes follow the same keys,
+// but left and right are swapped on the second board. The run ends when
+// either snake dies, and scores on both boards count.
+
+use crate::game::{Direction, Game, InputTransform};
+use crate::layout;
+use crate::locale::tr;
+
+pub struct Mirror {
+    boards: Vec<Game>, // the plain board, then the mirrored one
+}
+
+impl Mirror {
+    // Play on two boards set up the same way
+    pub fn new(plain: Game, mut mirrored: Game) -> Self {
+        mirrored.set_controls(InputTransform {
+            mirror: true,
+            reverse: false,
+        });
+        Self {
+            boards: vec![plain, mirrored],
+        }
+    }
+
+    pub fn boards(&self) -> &[Game] {
+        &self.boards
+    }
+
+    pub fn boards_mut(&mut self) -> &mut [Game] {
+        &mut self.boards
+    }
+
+    // Pass the player's direction to both snakes, each scrambled by its board
+    pub fn steer(&mut self, direction: Direction) {
+        for board in &mut self.boards {
+            let _ = board.set_direction(0, board.input_transform(0).apply(direction));
+        }
+    }
+
+    pub fn update(&mut self) {
+        for board in &mut self.boards {
+            board.update();
+        }
+    }
+
+    // Whether a snake has died on either board, or both have met their
+    // level's win conditions
+    pub fn finished(&self) -> bool {
+        let lost = self
+            .boards
+            .iter()
+            .any(|board| board.finished() && !board.solved());
+        lost || self.won()
+    }
+
+    pub fn won(&self) -> bool {
+        self.boards.iter().all(|board| board.solved())
+    }
+
+    pub fn score(&self) -> i32 {
+        self.boards.iter().map(|board| board.score()).sum()
+    }
+
+    // Draw the boards side by side in the middle of the terminal, with the
+    // total score and any footer lines beneath
+    pub fn render_ascii(&self, footer: &[String]) {
+        let columns = [
+            self.boards[0].column(&tr("mirror.plain", &[])),
+            self.boards[1].column(&tr("mirror.mirrored", &[])),
+        ];
+        let mut lines = vec![
+            String::new(),
+            format!("  {}", tr("mirror.total", &[&self.score()])),
+        ];
+        lines.extend_from_slice(footer);
+        layout::draw_columns(&columns, &lines);
+    }
+}
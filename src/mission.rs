@@ -0,0 +1,138 @@
+// Missions are optional goals for a run that pay out bonus points
+// The game reports what the player does and each mission keeps its own score
+
+use crate::game::FoodKind;
+use crate::locale::tr;
+use rand::prelude::*;
+
+// How many missions a run starts with
+pub const MISSIONS_PER_RUN: usize = 2;
+
+// The game loop runs at this many ticks a second, for describing time limits
+const TICKS_PER_SECOND: u32 = 10;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Goal {
+    // Eat this many foods in a row without turning left
+    NoLeftTurns(u32),
+    // Grow to this length within this many ticks
+    ReachLength(i32, u32),
+    // Eat this many bonus foods
+    EatBonus(u32),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Active,
+    Complete,
+    Failed,
+}
+
+#[derive(Clone)]
+pub struct Mission {
+    pub goal: Goal,
+    pub status: Status,
+    progress: u32,
+    ticks: u32,
+}
+
+impl Mission {
+    pub fn new(goal: Goal) -> Self {
+        Self {
+            goal,
+            status: Status::Active,
+            progress: 0,
+            ticks: 0,
+        }
+    }
+
+    // Pick a few different missions at random
+    pub fn random_set(rng: &mut impl Rng) -> Vec<Mission> {
+        let goals = [
+            Goal::NoLeftTurns(3),
+            Goal::ReachLength(15, 60 * TICKS_PER_SECOND),
+            Goal::EatBonus(2),
+        ];
+        goals
+            .choose_multiple(rng, MISSIONS_PER_RUN)
+            .map(|&goal| Mission::new(goal))
+            .collect()
+    }
+
+    // Points for completing the mission
+    pub fn reward(&self) -> i32 {
+        match self.goal {
+            Goal::NoLeftTurns(_) => 100,
+            Goal::ReachLength(..) => 150,
+            Goal::EatBonus(_) => 200,
+        }
+    }
+
+    // What the HUD shows for the mission
+    pub fn describe(&self) -> String {
+        let text = match self.goal {
+            Goal::NoLeftTurns(foods) => tr("mission.no-left", &[&foods, &self.progress, &foods]),
+            Goal::ReachLength(length, ticks) => tr(
+                "mission.reach-length",
+                &[
+                    &length,
+                    &(ticks / TICKS_PER_SECOND),
+                    &(ticks.saturating_sub(self.ticks) / TICKS_PER_SECOND),
+                ],
+            ),
+            Goal::EatBonus(count) => tr("mission.eat-bonus", &[&count, &self.progress, &count]),
+        };
+        match self.status {
+            Status::Active => text,
+            Status::Complete => tr("mission.done", &[&text]),
+            Status::Failed => tr("mission.failed", &[&text]),
+        }
+    }
+
+    // The player ate something
+    // Returns the reward if that completed the mission
+    pub fn ate(&mut self, kind: FoodKind) -> Option<i32> {
+        match self.goal {
+            Goal::NoLeftTurns(foods) => self.advance(foods),
+            Goal::EatBonus(count) if kind == FoodKind::Bonus => self.advance(count),
+            _ => None,
+        }
+    }
+
+    // The player turned left, breaking any streak going
+    pub fn turned_left(&mut self) {
+        if let (Goal::NoLeftTurns(_), Status::Active) = (self.goal, self.status) {
+            self.progress = 0;
+        }
+    }
+
+    // Another tick went by with the player this long
+    // Returns the reward if that completed the mission
+    pub fn ticked(&mut self, length: i32) -> Option<i32> {
+        self.ticks += 1;
+        match (self.goal, self.status) {
+            (Goal::ReachLength(target, _), Status::Active) if length >= target => {
+                self.status = Status::Complete;
+                Some(self.reward())
+            }
+            (Goal::ReachLength(_, limit), Status::Active) if self.ticks >= limit => {
+                self.status = Status::Failed;
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn advance(&mut self, target: u32) -> Option<i32> {
+        if self.status != Status::Active {
+            return None;
+        }
+        self.progress += 1;
+        if self.progress >= target {
+            self.status = Status::Complete;
+            Some(self.reward())
+        } else {
+            None
+        }
+    }
+}
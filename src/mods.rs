@@ -0,0 +1,302 @@
+// Mods are content packs, each a directory of its own in the mods directory
+// holding any of:
+//   manifest        what the mod is called and what it adds
+//   theme           a theme file, used when no --theme is given
+//   levels/         level files, played with --level <mod>/<level>
+//   *.script        rule scripts, as described in script.rs
+//
+// The manifest has one setting per line:
+//   name <text>
+//   description <text>
+//   achievement <score> <text>
+// where each achievement is reached by scoring that much in a run. Lines
+// starting with ; are comments.
+//
+// Every mod is switched on when it's found. The ones switched off in the mods
+// menu are listed by directory name in the mods directory's disabled file,
+// and each mod keeps the achievements it's had reached in its achieved file.
+
+use crate::diagnostic::did_you_mean;
+use crate::level::Level;
+use crate::profile;
+use crate::script::Script;
+use crate::theme::Theme;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Files in the mods directory that aren't mods themselves
+const DISABLED_FILE: &str = "disabled";
+
+// Files inside a mod
+const MANIFEST_FILE: &str = "manifest";
+const THEME_FILE: &str = "theme";
+const LEVELS_DIR: &str = "levels";
+const ACHIEVED_FILE: &str = "achieved";
+
+pub struct Achievement {
+    pub score: i32,
+    pub text: String,
+}
+
+pub struct Mod {
+    pub key: String, // the directory it's in
+    pub name: String,
+    pub description: String,
+    pub achievements: Vec<Achievement>,
+    pub achieved: Vec<String>, // the text of each achievement reached
+    pub enabled: bool,
+    path: PathBuf,
+}
+
+impl Mod {
+    fn load(path: PathBuf) -> Result<Self, String> {
+        let key = path
+            .file_name()
+            .map_or(String::new(), |name| name.to_string_lossy().into_owned());
+        let mut new = Self {
+            name: key.clone(),
+            key,
+            description: String::new(),
+            achievements: Vec::new(),
+            achieved: Vec::new(),
+            enabled: true,
+            path,
+        };
+
+        let manifest = new.path.join(MANIFEST_FILE);
+        if manifest.exists() {
+            let text = fs::read_to_string(&manifest)
+                .map_err(|e| format!("{}: {}", manifest.display(), e))?;
+            new.parse_manifest(&text)
+                .map_err(|e| format!("{}: {}", manifest.display(), e))?;
+        }
+
+        let achieved = new.path.join(ACHIEVED_FILE);
+        if achieved.exists() {
+            let text = fs::read_to_string(&achieved)
+                .map_err(|e| format!("{}: {}", achieved.display(), e))?;
+            new.achieved = text
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(|line| line.trim().to_string())
+                .collect();
+        }
+        Ok(new)
+    }
+
+    fn parse_manifest(&mut self, text: &str) -> Result<(), String> {
+        for (i, line) in text.lines().enumerate() {
+            let line_no = i + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(';') {
+                continue;
+            }
+
+            let (setting, rest) = line.split_once(' ').unwrap_or((line, ""));
+            let rest = rest.trim();
+            match setting {
+                "name" if !rest.is_empty() => self.name = rest.to_string(),
+                "description" => self.description = rest.to_string(),
+                "achievement" => {
+                    let (score, text) = rest.split_once(' ').unwrap_or((rest, ""));
+                    let score = score.parse().ok().filter(|&n| n > 0).ok_or_else(|| {
+                        format!("line {}: achievement score must be positive", line_no)
+                    })?;
+                    let text = text.trim();
+                    if text.is_empty() {
+                        return Err(format!("line {}: achievement needs a description", line_no));
+                    }
+                    self.achievements.push(Achievement {
+                        score,
+                        text: text.to_string(),
+                    });
+                }
+                _ => {
+                    return Err(format!(
+                        "line {}: unknown setting {}{}",
+                        line_no,
+                        setting,
+                        did_you_mean(setting, &["name", "description", "achievement"])
+                    ))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn save_achieved(&self) -> Result<(), String> {
+        let path = self.path.join(ACHIEVED_FILE);
+        let text: String = self
+            .achieved
+            .iter()
+            .map(|text| text.clone() + "\n")
+            .collect();
+        fs::write(&path, text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
+
+pub struct Mods {
+    dir: Option<PathBuf>, // where they're kept, if there's a home directory
+    pub mods: Vec<Mod>,   // in order of their directory names
+}
+
+impl Mods {
+    // Find every mod in the mods directory
+    // There being no directory just means there are no mods
+    pub fn discover() -> Result<Self, String> {
+        Self::load_dir(profile::data_dir().map(|dir| dir.join("mods")))
+    }
+
+    fn load_dir(dir: Option<PathBuf>) -> Result<Self, String> {
+        let mut mods = Self {
+            dir: dir.clone(),
+            mods: Vec::new(),
+        };
+        let entries = match dir.as_ref().map(fs::read_dir) {
+            Some(Ok(entries)) => entries,
+            _ => return Ok(mods),
+        };
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| Some(entry.ok()?.path()))
+            .filter(|path| path.is_dir())
+            .collect();
+        paths.sort();
+        for path in paths {
+            mods.mods.push(Mod::load(path)?);
+        }
+
+        let disabled = mods.disabled_path().filter(|path| path.exists());
+        if let Some(path) = disabled {
+            let text =
+                fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            for key in text.lines().map(str::trim) {
+                for m in mods.mods.iter_mut().filter(|m| m.key == key) {
+                    m.enabled = false;
+                }
+            }
+        }
+        Ok(mods)
+    }
+
+    fn disabled_path(&self) -> Option<PathBuf> {
+        Some(self.dir.as_ref()?.join(DISABLED_FILE))
+    }
+
+    fn enabled(&self) -> impl Iterator<Item = &Mod> {
+        self.mods.iter().filter(|m| m.enabled)
+    }
+
+    // Switch a mod on or off, remembering it for next time
+    pub fn toggle(&mut self, index: usize) -> Result<(), String> {
+        let m = match self.mods.get_mut(index) {
+            Some(m) => m,
+            None => return Ok(()),
+        };
+        m.enabled = !m.enabled;
+
+        let path = match self.disabled_path() {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let text: String = self
+            .mods
+            .iter()
+            .filter(|m| !m.enabled)
+            .map(|m| m.key.clone() + "\n")
+            .collect();
+        fs::write(&path, text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    // The theme of the last mod switched on that has one, with warnings
+    // about any lines of it that were left out
+    pub fn theme(&self) -> Result<Option<(Theme, Vec<String>)>, String> {
+        match self
+            .enabled()
+            .map(|m| m.path.join(THEME_FILE))
+            .filter(|path| path.exists())
+            .last()
+        {
+            Some(path) => Theme::load(&path.to_string_lossy()).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    // Load a level by its path, or as <mod>/<level> from a mod switched on
+    pub fn level(&self, path: &str) -> Result<Level, String> {
+        if Path::new(path).exists() {
+            return Level::load(path);
+        }
+        let found = path.split_once('/').and_then(|(key, name)| {
+            let m = self.enabled().find(|m| m.key == key)?;
+            Some(m.path.join(LEVELS_DIR).join(name)).filter(|path| path.exists())
+        });
+        match found {
+            Some(found) => Level::load(&found.to_string_lossy()),
+            None => Level::load(path),
+        }
+    }
+
+    // Every rule from scripts loose in the mods directory and in the mods
+    // switched on
+    pub fn script(&self) -> Result<Script, String> {
+        let mut script = match &self.dir {
+            Some(dir) => Script::load_dir(dir)?,
+            None => Script::default(),
+        };
+        for m in self.enabled() {
+            script.extend(Script::load_dir(&m.path)?);
+        }
+        Ok(script)
+    }
+
+    // Mark the achievements a run's score reached for the first time, saving
+    // them, and return what each was for
+    pub fn reach(&mut self, score: i32) -> Result<Vec<String>, String> {
+        let mut reached = Vec::new();
+        for m in self.mods.iter_mut().filter(|m| m.enabled) {
+            let new: Vec<String> = m
+                .achievements
+                .iter()
+                .filter(|a| a.score <= score && !m.achieved.contains(&a.text))
+                .map(|a| a.text.clone())
+                .collect();
+            if new.is_empty() {
+                continue;
+            }
+            m.achieved.extend(new.iter().cloned());
+            m.save_achieved()?;
+            reached.extend(new);
+        }
+        Ok(reached)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scripts_come_from_the_mods_directory_and_mods_switched_on() {
+        let dir = std::env::temp_dir().join(format!("ascii-snake-mods-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for (key, points) in [("a-on", 2), ("b-off", 3)] {
+            fs::create_dir_all(dir.join(key)).unwrap();
+            let manifest = format!("name {}\n", key);
+            fs::write(dir.join(key).join("manifest"), manifest).unwrap();
+            let rule = format!("on eat points {}\n", points);
+            fs::write(dir.join(key).join("rules.script"), rule).unwrap();
+        }
+        fs::write(dir.join("loose.script"), "on eat points 1\n").unwrap();
+        fs::write(dir.join(DISABLED_FILE), "b-off\n").unwrap();
+
+        let mut mods = Mods::load_dir(Some(dir.clone())).unwrap();
+        assert_eq!(mods.mods.len(), 2);
+        assert!(mods.mods[0].enabled && !mods.mods[1].enabled);
+        assert_eq!(mods.script().unwrap().len(), 2);
+
+        mods.toggle(1).unwrap();
+        let mods = Mods::load_dir(Some(dir.clone())).unwrap();
+        assert_eq!(mods.script().unwrap().len(), 3);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
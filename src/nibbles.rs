@@ -0,0 +1,140 @@
+// Importer for the arenas of the classic QBasic Nibbles
+//
+// The layouts are described in levels/nibbles.txt as the straight runs of
+// wall the original drew, in its own screen coordinates. Nibbles' border
+// becomes the edge of the board.
+
+use crate::game::Direction;
+use crate::level::{parse_coords, parse_direction, Level, SnakeStart};
+use crate::snake::START_LENGTH;
+
+// The description of the original nine arenas
+const ARENAS: &str = include_str!("../levels/nibbles.txt");
+
+// The border in Nibbles' screen coordinates, inclusive
+const LEFT: i32 = 1;
+const RIGHT: i32 = 80;
+const TOP: i32 = 3;
+const BOTTOM: i32 = 50;
+
+// The original arenas, in order
+pub fn levels() -> Vec<Level> {
+    import(ARENAS).expect("built-in Nibbles arenas are valid")
+}
+
+// Parse an arena description into one level per arena
+pub fn import(text: &str) -> Result<Vec<Level>, String> {
+    let mut levels = Vec::new();
+
+    for (i, line) in text.lines().enumerate() {
+        let line_no = i + 1;
+        let words: Vec<&str> = line.split_whitespace().collect();
+        if words.is_empty() || line.starts_with(';') {
+            continue;
+        }
+
+        if words == ["level"] {
+            levels.push(Level::blank(RIGHT - LEFT - 1, BOTTOM - TOP - 1));
+            continue;
+        }
+        let level = levels
+            .last_mut()
+            .ok_or_else(|| format!("line {}: expected a level line first", line_no))?;
+
+        let walls: Vec<(i32, i32)> = match words.as_slice() {
+            ["snake", head, direction] => {
+                let head = parse_coords(head)
+                    .ok_or_else(|| format!("line {}: snake head looks like 40,25", line_no))?;
+                let head = to_board(head)
+                    .ok_or_else(|| format!("line {}: snake outside the arena", line_no))?;
+                let direction = parse_direction(direction)
+                    .filter(|direction| Direction::ALL.contains(direction))
+                    .ok_or_else(|| {
+                        format!(
+                            "line {}: direction must be up, down, left or right",
+                            line_no
+                        )
+                    })?;
+                level.snake = Some(SnakeStart {
+                    head,
+                    direction,
+                    length: START_LENGTH,
+                });
+                vec![]
+            }
+            ["hline", row, cols, step @ ..] => {
+                let row = parse_number(row, line_no)?;
+                let step = parse_step(step, line_no)?;
+                parse_range(cols, line_no)?
+                    .step_by(step)
+                    .map(|col| (col, row))
+                    .collect()
+            }
+            ["vline", col, rows, step @ ..] => {
+                let col = parse_number(col, line_no)?;
+                let step = parse_step(step, line_no)?;
+                parse_range(rows, line_no)?
+                    .step_by(step)
+                    .map(|row| (col, row))
+                    .collect()
+            }
+            ["slope", rows, offset] => {
+                let offset = parse_number(offset, line_no)?;
+                parse_range(rows, line_no)?
+                    .map(|row| (row + offset, row))
+                    .collect()
+            }
+            _ => return Err(format!("line {}: unknown setting", line_no)),
+        };
+
+        for wall in walls {
+            let wall = to_board(wall)
+                .ok_or_else(|| format!("line {}: wall outside the arena", line_no))?;
+            if !level.walls.contains(&wall) {
+                level.walls.push(wall);
+            }
+        }
+    }
+
+    if levels.is_empty() {
+        return Err("no arenas".to_string());
+    }
+    Ok(levels)
+}
+
+// Move a screen position inside the border to the board, where the top left
+// is 0,0
+fn to_board((col, row): (i32, i32)) -> Option<(i32, i32)> {
+    if col > LEFT && col < RIGHT && row > TOP && row < BOTTOM {
+        Some((col - LEFT - 1, row - TOP - 1))
+    } else {
+        None
+    }
+}
+
+fn parse_number(s: &str, line_no: usize) -> Result<i32, String> {
+    s.parse()
+        .map_err(|_| format!("line {}: {} isn't a number", line_no, s))
+}
+
+// An inclusive run of positions like 4-30
+fn parse_range(s: &str, line_no: usize) -> Result<std::ops::RangeInclusive<i32>, String> {
+    s.split_once('-')
+        .and_then(|(from, to)| Some((from.parse().ok()?, to.parse().ok()?)))
+        .filter(|(from, to)| from <= to)
+        .map(|(from, to)| from..=to)
+        .ok_or_else(|| format!("line {}: runs look like 4-30", line_no))
+}
+
+// How far apart the walls of a run are, one after another if not given
+fn parse_step(step: &[&str], line_no: usize) -> Result<usize, String> {
+    match step {
+        [] => Ok(1),
+        [step] => step
+            .parse()
+            .ok()
+            .filter(|&step| step > 0)
+            .ok_or_else(|| format!("line {}: step must be a positive number", line_no)),
+        _ => Err(format!("line {}: unknown setting", line_no)),
+    }
+}
@@ -0,0 +1,66 @@
+// Desktop notifications, sent once the game is over and the terminal is
+// back to normal so they aren't drawn over
+//
+// They're only sent in builds with the notify feature, through whatever
+// the system already uses for them: notify-send on Linux and the BSDs, and
+// osascript on macOS. Those have to be installed, and other systems can't
+// be sent them at all.
+
+#[cfg(feature = "notify")]
+use crate::locale::tr;
+
+// Let the player know a run beat the high score
+#[cfg(feature = "notify")]
+pub fn high_score(score: i32) -> Result<(), String> {
+    send(
+        &tr("notify.title", &[]),
+        &tr("notify.high-score", &[&score]),
+    )
+}
+
+#[cfg(not(feature = "notify"))]
+pub fn high_score(_score: i32) -> Result<(), String> {
+    Ok(())
+}
+
+#[cfg(all(feature = "notify", target_os = "macos"))]
+fn send(title: &str, text: &str) -> Result<(), String> {
+    // AppleScript strings are quoted the same way as JSON ones
+    let quote = |s: &str| crate::json::Json::from(s).to_line();
+    let script = format!(
+        "display notification {} with title {}",
+        quote(text),
+        quote(title)
+    );
+    run(std::process::Command::new("osascript")
+        .arg("-e")
+        .arg(script))
+}
+
+#[cfg(all(feature = "notify", unix, not(target_os = "macos")))]
+fn send(title: &str, text: &str) -> Result<(), String> {
+    run(std::process::Command::new("notify-send")
+        .arg(title)
+        .arg(text))
+}
+
+#[cfg(all(feature = "notify", not(unix)))]
+fn send(_title: &str, _text: &str) -> Result<(), String> {
+    Err("notifications need notify-send or osascript, and this system has neither".to_string())
+}
+
+#[cfg(all(feature = "notify", unix))]
+fn run(command: &mut std::process::Command) -> Result<(), String> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    let status = command.status().map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => {
+            format!("{} isn't installed, notifications need it", program)
+        }
+        _ => format!("{}: {}", program, e),
+    })?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("{} failed with {}", program, status))
+    }
+}
@@ -0,0 +1,108 @@
+// Obstacles patrol a fixed path one tile per tick
+
+// How an obstacle walks its path
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Patrol {
+    // Walk to the last waypoint, then turn around
+    Bounce,
+    // Walk to the last waypoint, then back to the first
+    Loop,
+}
+
+#[derive(Clone)]
+pub struct Obstacle {
+    patrol: Patrol,
+    waypoints: Vec<(i32, i32)>,
+    path: Vec<(i32, i32)>, // every tile on the route, in walking order
+    index: usize,
+    forward: bool,
+}
+
+impl Obstacle {
+    // Build an obstacle walking between waypoints
+    // Consecutive waypoints must share a row or column
+    pub fn new(patrol: Patrol, waypoints: &[(i32, i32)]) -> Result<Self, String> {
+        if waypoints.len() < 2 {
+            return Err("an obstacle needs at least two waypoints".to_string());
+        }
+
+        let mut route = waypoints.to_vec();
+        if patrol == Patrol::Loop {
+            route.push(waypoints[0]);
+        }
+
+        let mut path = vec![route[0]];
+        for pair in route.windows(2) {
+            let (from, to) = (pair[0], pair[1]);
+            if from.0 != to.0 && from.1 != to.1 {
+                return Err(format!(
+                    "waypoints {},{} and {},{} are not in a straight line",
+                    from.0, from.1, to.0, to.1
+                ));
+            }
+
+            let dx = (to.0 - from.0).signum();
+            let dy = (to.1 - from.1).signum();
+            let mut pos = from;
+            while pos != to {
+                pos = (pos.0 + dx, pos.1 + dy);
+                path.push(pos);
+            }
+        }
+
+        // The loop's closing step lands back on the start
+        if patrol == Patrol::Loop {
+            path.pop();
+        }
+
+        Ok(Self {
+            patrol,
+            waypoints: waypoints.to_vec(),
+            path,
+            index: 0,
+            forward: true,
+        })
+    }
+
+    pub fn position(&self) -> (i32, i32) {
+        self.path[self.index]
+    }
+
+    pub fn patrol(&self) -> Patrol {
+        self.patrol
+    }
+
+    // The waypoints the obstacle was built from
+    pub fn waypoints(&self) -> &[(i32, i32)] {
+        &self.waypoints
+    }
+
+    // Every tile this obstacle will ever visit
+    pub fn route(&self) -> &[(i32, i32)] {
+        &self.path
+    }
+
+    // Move one tile along the path
+    pub fn advance(&mut self) {
+        if self.path.len() < 2 {
+            return;
+        }
+
+        match self.patrol {
+            Patrol::Loop => self.index = (self.index + 1) % self.path.len(),
+            Patrol::Bounce => {
+                if self.forward && self.index == self.path.len() - 1 {
+                    self.forward = false;
+                } else if !self.forward && self.index == 0 {
+                    self.forward = true;
+                }
+
+                if self.forward {
+                    self.index += 1;
+                } else {
+                    self.index -= 1;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,68 @@
+// Perks are permanent upgrades bought with coins earned over many runs
+
+use crate::locale::tr;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Perk {
+    // Start every life one segment longer
+    LongerStart,
+    // Once per run, dying winds the game back a few seconds instead
+    Rewind,
+    // The game speeds up half as quickly
+    SlowerRamp,
+}
+
+// How much of the speed ramp is left with the slower ramp perk
+pub const SLOWER_RAMP: f32 = 0.5;
+
+impl Perk {
+    // Every perk, in the order the perk screen lists them
+    pub const ALL: [Perk; 3] = [Perk::LongerStart, Perk::Rewind, Perk::SlowerRamp];
+
+    // Coins needed to unlock the perk
+    pub fn cost(self) -> u32 {
+        match self {
+            Perk::LongerStart => 20,
+            Perk::Rewind => 50,
+            Perk::SlowerRamp => 35,
+        }
+    }
+
+    pub fn name(self) -> String {
+        tr(&format!("perk.{}", self.key()), &[])
+    }
+
+    pub fn description(self) -> String {
+        tr(&format!("perk.{}.about", self.key()), &[])
+    }
+
+    // The name the perk is saved under in the profile
+    pub fn key(self) -> &'static str {
+        match self {
+            Perk::LongerStart => "longer-start",
+            Perk::Rewind => "rewind",
+            Perk::SlowerRamp => "slower-ramp",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Perk::ALL.iter().copied().find(|perk| perk.key() == key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::SpeedCurve;
+
+    #[test]
+    fn slower_ramps_take_twice_the_food_to_speed_up() {
+        let curve = SpeedCurve {
+            ramp: 0.1,
+            max: 2.0,
+        };
+        let slowed = curve.slowed(SLOWER_RAMP);
+        assert_eq!(slowed.at(10), curve.at(5));
+        assert_eq!(slowed.at(100), 2.0);
+    }
+}
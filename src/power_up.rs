@@ -0,0 +1,77 @@
+// Power-ups are picked up from the board and give the snake a timed effect
+// Traps reuse the same effects to hand out debuffs
+
+use crate::locale::tr;
+
+// Kinds of power-up
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum PowerUp {
+    // Pass through your own body
+    Ghost,
+    // Left and right controls are swapped
+    Mirrored,
+    // Every control points the opposite way
+    Reversed,
+}
+
+impl PowerUp {
+    pub const ALL: [PowerUp; 3] = [PowerUp::Ghost, PowerUp::Mirrored, PowerUp::Reversed];
+
+    // Every kind of power-up that can be picked up, for choosing one at random
+    pub const PICKUPS: [PowerUp; 1] = [PowerUp::Ghost];
+
+    // How many ticks the effect lasts
+    pub fn duration(self) -> u32 {
+        match self {
+            PowerUp::Ghost => 40,
+            PowerUp::Mirrored | PowerUp::Reversed => 50,
+        }
+    }
+
+    // How many ticks before running out the HUD starts warning
+    pub fn warning_ticks(self) -> u32 {
+        match self {
+            PowerUp::Ghost | PowerUp::Mirrored | PowerUp::Reversed => 10,
+        }
+    }
+
+    // Name shown in the HUD
+    pub fn name(self) -> String {
+        tr(&format!("power-up.{}", self.key()), &[])
+    }
+
+    pub fn key(self) -> &'static str {
+        match self {
+            PowerUp::Ghost => "ghost",
+            PowerUp::Mirrored => "mirrored",
+            PowerUp::Reversed => "reversed",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        PowerUp::ALL
+            .iter()
+            .copied()
+            .find(|power_up| power_up.key() == key)
+    }
+}
+
+// A power-up currently affecting the snake
+#[derive(Clone, Copy)]
+pub struct Effect {
+    pub power_up: PowerUp,
+    pub ticks_left: u32,
+}
+
+impl Effect {
+    pub fn new(power_up: PowerUp) -> Self {
+        Self {
+            power_up,
+            ticks_left: power_up.duration(),
+        }
+    }
+
+    pub fn expiring(self) -> bool {
+        self.ticks_left <= self.power_up.warning_ticks()
+    }
+}
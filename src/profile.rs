@@ -0,0 +1,191 @@
+// The profile keeps progress between runs in a small text file
+//
+// Each line is a setting:
+//   coins <count>
+//   perk <name>
+//   milestone <name>
+//   skin <name>
+// with one perk or milestone line for every one reached, and the skin line
+// naming the skin in use.
+
+use crate::perk::Perk;
+use crate::skin::{Milestone, Skin};
+use std::fs;
+use std::path::PathBuf;
+
+// Every COIN_POINTS points scored in a run earns a coin
+const COIN_POINTS: i32 = 10;
+
+// The directory progress is kept in, if there's a home directory for it
+pub fn data_dir() -> Option<PathBuf> {
+    let home = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"))?;
+    Some(PathBuf::from(home).join(".ascii-snake"))
+}
+
+pub struct Profile {
+    pub coins: u32,
+    pub perks: Vec<Perk>,
+    pub milestones: Vec<Milestone>,
+    pub skin: Skin,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            coins: 0,
+            perks: Vec::new(),
+            milestones: Vec::new(),
+            skin: Skin::Classic,
+        }
+    }
+}
+
+impl Profile {
+    // Where the profile lives, if there's a home directory to keep it in
+    fn path() -> Option<PathBuf> {
+        Some(data_dir()?.join("profile"))
+    }
+
+    // Read the profile, starting a fresh one if there isn't one yet
+    pub fn load() -> Result<Self, String> {
+        let path = match Self::path() {
+            Some(path) if path.exists() => path,
+            _ => return Ok(Self::default()),
+        };
+        let text = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    fn parse(text: &str) -> Result<Self, String> {
+        let mut profile = Self::default();
+        for (i, line) in text.lines().enumerate() {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words.as_slice() {
+                [] => (),
+                ["coins", count] => {
+                    profile.coins = count
+                        .parse()
+                        .map_err(|_| format!("line {}: coins must be a whole number", i + 1))?
+                }
+                ["perk", key] => {
+                    let perk = Perk::from_key(key)
+                        .ok_or_else(|| format!("line {}: unknown perk {}", i + 1, key))?;
+                    if !profile.perks.contains(&perk) {
+                        profile.perks.push(perk);
+                    }
+                }
+                ["milestone", key] => {
+                    let milestone = Milestone::from_key(key)
+                        .ok_or_else(|| format!("line {}: unknown milestone {}", i + 1, key))?;
+                    if !profile.milestones.contains(&milestone) {
+                        profile.milestones.push(milestone);
+                    }
+                }
+                ["skin", key] => {
+                    profile.skin = Skin::from_key(key)
+                        .ok_or_else(|| format!("line {}: unknown skin {}", i + 1, key))?
+                }
+                _ => return Err(format!("line {}: unknown setting", i + 1)),
+            }
+        }
+        Ok(profile)
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let path = match Self::path() {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+
+        let mut text = format!("coins {}\n", self.coins);
+        for perk in &self.perks {
+            text += &format!("perk {}\n", perk.key());
+        }
+        for milestone in &self.milestones {
+            text += &format!("milestone {}\n", milestone.key());
+        }
+        text += &format!("skin {}\n", self.skin.key());
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+        }
+        fs::write(&path, text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn has(&self, perk: Perk) -> bool {
+        self.perks.contains(&perk)
+    }
+
+    // Unlock a perk if there are enough coins for it
+    // Returns an error if it's already unlocked or can't be afforded
+    pub fn buy(&mut self, perk: Perk) -> Result<(), ()> {
+        if self.has(perk) || self.coins < perk.cost() {
+            return Err(());
+        }
+        self.coins -= perk.cost();
+        self.perks.push(perk);
+        Ok(())
+    }
+
+    pub fn unlocked(&self, skin: Skin) -> bool {
+        skin.unlocked_by()
+            .is_none_or(|milestone| self.milestones.contains(&milestone))
+    }
+
+    // Switch skins
+    // Returns an error if the skin is still locked
+    pub fn pick(&mut self, skin: Skin) -> Result<(), ()> {
+        if !self.unlocked(skin) {
+            return Err(());
+        }
+        self.skin = skin;
+        Ok(())
+    }
+
+    // Record a milestone, returning the skin it unlocks if it's new
+    pub fn reach(&mut self, milestone: Milestone) -> Option<Skin> {
+        if self.milestones.contains(&milestone) {
+            return None;
+        }
+        self.milestones.push(milestone);
+        Skin::ALL
+            .iter()
+            .copied()
+            .find(|skin| skin.unlocked_by() == Some(milestone))
+    }
+
+    // Pay out the coins for a finished run, returning how many were earned
+    pub fn reward(&mut self, score: i32) -> u32 {
+        let earned = (score / COIN_POINTS).max(0) as u32;
+        self.coins += earned;
+        earned
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn milestones_unlock_their_skins() {
+        let mut profile = Profile::default();
+        assert!(!profile.unlocked(Skin::Frost));
+        assert!(profile.pick(Skin::Frost).is_err());
+
+        assert!(profile.reach(Milestone::WinDaily) == Some(Skin::Frost));
+        assert!(profile.reach(Milestone::WinDaily).is_none());
+        assert!(profile.pick(Skin::Frost).is_ok());
+        assert!(profile.skin == Skin::Frost);
+    }
+
+    #[test]
+    fn profiles_keep_their_milestones() {
+        let profile = Profile::parse("coins 4\nmilestone win-daily\nskin frost\n").unwrap();
+        assert!(profile.milestones == vec![Milestone::WinDaily]);
+        assert!(profile.unlocked(Skin::Frost) && !profile.unlocked(Skin::Gold));
+        assert_eq!(
+            Profile::parse("milestone win-weekly\n").err().unwrap(),
+            "line 1: unknown milestone win-weekly"
+        );
+    }
+}
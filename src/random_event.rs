@@ -0,0 +1,91 @@
+// Random events shake up a run every so often for a short while
+// The scheduler only decides what happens when; the game applies the effects
+
+use crate::locale::tr;
+use rand::prelude::*;
+
+// Quiet spells between events last somewhere in this range of ticks
+const MIN_QUIET_TICKS: u32 = 150;
+const MAX_QUIET_TICKS: u32 = 300;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum RandomEvent {
+    // Walls drop onto the board and crumble away again
+    MeteorShower,
+    // Extra food appears all at once
+    FoodFrenzy,
+    // The game speeds up
+    SpeedSurge,
+}
+
+impl RandomEvent {
+    const ALL: [RandomEvent; 3] = [
+        RandomEvent::MeteorShower,
+        RandomEvent::FoodFrenzy,
+        RandomEvent::SpeedSurge,
+    ];
+
+    // How many ticks the event lasts
+    pub fn duration(self) -> u32 {
+        match self {
+            RandomEvent::MeteorShower => 40,
+            RandomEvent::FoodFrenzy => 60,
+            RandomEvent::SpeedSurge => 50,
+        }
+    }
+
+    // Name shown in the HUD
+    pub fn name(self) -> String {
+        let key = match self {
+            RandomEvent::MeteorShower => "event.meteor-shower",
+            RandomEvent::FoodFrenzy => "event.food-frenzy",
+            RandomEvent::SpeedSurge => "event.speed-surge",
+        };
+        tr(key, &[])
+    }
+}
+
+#[derive(Clone)]
+pub struct EventScheduler {
+    pub active: Option<(RandomEvent, u32)>, // the running event and its ticks left
+    ticks_until_next: u32,
+}
+
+impl EventScheduler {
+    pub fn new(rng: &mut impl Rng) -> Self {
+        Self {
+            active: None,
+            ticks_until_next: quiet_spell(rng),
+        }
+    }
+
+    // Move the clock on, returning an event if one starts this tick
+    // Events are drawn from the game's generator, so seeded games get the
+    // same ones
+    pub fn tick(&mut self, rng: &mut impl Rng) -> Option<RandomEvent> {
+        if let Some((_, ticks_left)) = &mut self.active {
+            *ticks_left -= 1;
+            if *ticks_left == 0 {
+                self.active = None;
+                self.ticks_until_next = quiet_spell(rng);
+            }
+            return None;
+        }
+
+        self.ticks_until_next = self.ticks_until_next.saturating_sub(1);
+        if self.ticks_until_next > 0 {
+            return None;
+        }
+        let event = *RandomEvent::ALL.choose(rng).unwrap();
+        self.active = Some((event, event.duration()));
+        Some(event)
+    }
+
+    pub fn running(&self, event: RandomEvent) -> bool {
+        self.active.map(|(active, _)| active) == Some(event)
+    }
+}
+
+fn quiet_spell(rng: &mut impl Rng) -> u32 {
+    rng.gen_range(MIN_QUIET_TICKS, MAX_QUIET_TICKS + 1)
+}
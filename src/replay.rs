@@ -0,0 +1,174 @@
+// A replay is the seed a game was laid out from and every turn its first
+// snake took, so the game can be played again without anyone at the keys
+//
+// Replay files have one entry per line:
+//   seed <number>
+//   <tick> <up|down|left|right>
+// where each turn is the way the snake was heading going into that tick's
+// update, counting updates from 0. Lines starting with ; are comments.
+//
+// Only the seed and the turns are kept, so a replay has to be played back
+// with the same settings it was recorded with. Any point in one can be
+// reached by playing it again from the start.
+
+use crate::game::{Direction, Game};
+use crate::hooks::Hooks;
+use crate::level::parse_direction;
+use std::fs;
+
+pub struct Replay {
+    pub seed: u64,
+    turns: Vec<(u32, Direction)>, // in the order of their ticks
+}
+
+impl Replay {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            turns: Vec::new(),
+        }
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        Self::parse(&text).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        fs::write(path, self.to_text()).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut seed = None;
+        let mut turns: Vec<(u32, Direction)> = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line_no = i + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(';') {
+                continue;
+            }
+
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words.as_slice() {
+                ["seed", n] => {
+                    seed =
+                        Some(n.parse().map_err(|_| {
+                            format!("line {}: seed must be a whole number", line_no)
+                        })?)
+                }
+                [tick, direction] => {
+                    let tick = tick
+                        .parse()
+                        .map_err(|_| format!("line {}: turns start with a tick number", line_no))?;
+                    let direction = parse_direction(direction).ok_or_else(|| {
+                        format!(
+                            "line {}: direction must be up, down, left or right",
+                            line_no
+                        )
+                    })?;
+                    if turns.last().is_some_and(|&(last, _)| last >= tick) {
+                        return Err(format!("line {}: turns must be in tick order", line_no));
+                    }
+                    turns.push((tick, direction));
+                }
+                _ => return Err(format!("line {}: expected seed or a turn", line_no)),
+            }
+        }
+        let seed = seed.ok_or("missing seed")?;
+        Ok(Self { seed, turns })
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = format!("seed {}\n", self.seed);
+        for &(tick, direction) in &self.turns {
+            text += &format!("{} {}\n", tick, direction.key());
+        }
+        text
+    }
+
+    // The way the snake turned going into an update, if it did
+    pub fn turn(&self, tick: u32) -> Option<Direction> {
+        let i = self.turns.binary_search_by_key(&tick, |&(t, _)| t).ok()?;
+        Some(self.turns[i].1)
+    }
+
+    // Play the update a game laid out from the seed is up to
+    pub fn step(&self, game: &mut Game) {
+        if let Some(direction) = self.turn(game.ticks()) {
+            let _ = game.set_direction(0, direction);
+        }
+        game.update();
+    }
+
+    // Play on until a game is `tick` updates in, or it's over
+    // Games only go forwards, so seeking back has to start again on a new
+    // board laid out from the seed
+    pub fn seek(&self, game: &mut Game, tick: u32) {
+        while game.ticks() < tick && !game.finished() {
+            self.step(game);
+        }
+    }
+}
+
+// A hook recording a replay of the game it's added to
+pub struct Recorder {
+    pub replay: Replay,
+}
+
+impl Recorder {
+    // Record a game laid out from `seed`
+    pub fn new(seed: u64) -> Self {
+        Self {
+            replay: Replay::new(seed),
+        }
+    }
+}
+
+impl Hooks for Recorder {
+    fn before_tick(&mut self, game: &mut Game) {
+        let direction = game.direction(0);
+        let turns = &mut self.replay.turns;
+        if turns.last().map(|&(_, last)| last) != Some(direction) {
+            turns.push((game.ticks(), direction));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replays_read_back_what_they_write() {
+        let replay = Replay::parse("; a comment\nseed 42\n\n3 left\n10 up\n").unwrap();
+        assert_eq!(replay.seed, 42);
+        assert!(replay.turn(3) == Some(Direction::Left));
+        assert!(replay.turn(10) == Some(Direction::Up));
+        assert!(replay.turn(4).is_none());
+        assert_eq!(replay.to_text(), "seed 42\n3 left\n10 up\n");
+        assert_eq!(
+            Replay::parse(&replay.to_text()).unwrap().to_text(),
+            replay.to_text()
+        );
+    }
+
+    #[test]
+    fn bad_replays_say_which_line_is_wrong() {
+        let error = |text: &str| Replay::parse(text).err().unwrap();
+        assert_eq!(error("3 left\n"), "missing seed");
+        assert_eq!(error("seed -1\n"), "line 1: seed must be a whole number");
+        assert_eq!(
+            error("seed 1\nsoon left\n"),
+            "line 2: turns start with a tick number"
+        );
+        assert_eq!(
+            error("seed 1\n3 sideways\n"),
+            "line 2: direction must be up, down, left or right"
+        );
+        assert_eq!(
+            error("seed 1\n3 left\n3 up\n"),
+            "line 3: turns must be in tick order"
+        );
+        assert_eq!(error("seed 1 2\n"), "line 1: expected seed or a turn");
+    }
+}
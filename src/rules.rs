@@ -0,0 +1,306 @@
+// A rule set is everything that decides how a game plays, gathered in one
+// place so a variant can be described all at once and then built into a game
+//
+// Presets are rule sets with a name. The command line starts from one, the
+// classic game unless --preset picks another, and its settings change it
+// from there.
+
+use crate::co_op::Handoff;
+use crate::game::{Direction, FoodPolicy, Game, SpeedCurve};
+use crate::level::Level;
+use crate::score::ScoreRules;
+use crate::topology;
+use crate::win::WinCondition;
+
+// The size of the board when there's no level to play
+const BOARD_SIZE: (i32, i32) = (16, 16);
+
+// Games get a little faster with every food, up to twice the normal speed
+const SPEED_CURVE: SpeedCurve = SpeedCurve {
+    ramp: 0.02,
+    max: 2.0,
+};
+
+#[derive(Clone)]
+pub struct RuleSet {
+    pub level: Option<Level>, // the board to play on, instead of an empty one
+    pub size: (i32, i32),     // of the empty board
+    pub topology: String,     // the key of how the board's tiles join up
+    pub players: usize,
+    pub portals: usize,
+    pub traps: usize,
+    pub wall_density: f32,
+    pub poison_chance: f32,
+    pub prey_chance: f32,
+    pub food_policy: FoodPolicy,
+    pub food_ttl: Option<u32>,
+    pub speed: SpeedCurve,
+    pub nibbles: bool,
+    pub power_ups: bool,
+    pub lives: u32,
+    pub fog: Option<i32>,
+    pub battle_royale: bool,
+    pub boss: bool,
+    pub events: bool,
+    pub missions: bool,
+    pub waves: bool,
+    pub co_op: Option<Handoff>,
+    pub win_conditions: Vec<WinCondition>, // on top of any the level has
+    pub scoring: ScoreRules,
+    pub start: Option<(i32, i32)>, // the first snake's head
+    pub heading: Option<Direction>,
+    pub length: Option<i32>,
+    pub seed: Option<u64>, // to lay the board out the same way every time
+}
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        Self {
+            level: None,
+            size: BOARD_SIZE,
+            topology: topology::DEFAULT_KEY.to_string(),
+            players: 1,
+            portals: 0,
+            traps: 0,
+            wall_density: 0.0,
+            poison_chance: 0.0,
+            prey_chance: 0.0,
+            food_policy: FoodPolicy::Uniform,
+            food_ttl: None,
+            speed: SPEED_CURVE,
+            nibbles: false,
+            power_ups: false,
+            lives: 1,
+            fog: None,
+            battle_royale: false,
+            boss: false,
+            events: false,
+            missions: false,
+            waves: false,
+            co_op: None,
+            win_conditions: Vec::new(),
+            scoring: ScoreRules::default(),
+            start: None,
+            heading: None,
+            length: None,
+            seed: None,
+        }
+    }
+}
+
+impl RuleSet {
+    // Make a game playing by these rules
+    // Returns an error if the snake's start doesn't fit on the board, or
+    // there's no topology by that key
+    pub fn build(&self) -> Result<Game, String> {
+        let mut game = match &self.level {
+            Some(level) => Game::from_level(level),
+            None => Game::new(self.size.0, self.size.1),
+        };
+        if let Some(seed) = self.seed {
+            game.set_seed(seed);
+        }
+        let (width, height) = game.dimensions();
+        let topology = topology::from_key(&self.topology, width, height)
+            .ok_or_else(|| format!("unknown topology {}", self.topology))?;
+        game.set_topology(topology);
+
+        // Levels can place the first snake themselves, so only replace it
+        // for more players
+        if self.players > 1 {
+            game.set_players(self.players);
+        }
+
+        // The start goes down before anything random does, which keeps
+        // clear of where the snakes are
+        if self.start.is_some() || self.heading.is_some() || self.length.is_some() {
+            game.set_start(self.start, self.heading, self.length)?;
+        }
+        game.set_boss(self.boss);
+        game.spawn_random_portals(self.portals);
+        game.spawn_random_traps(self.traps);
+        game.scatter_walls(self.wall_density);
+        game.set_poison_chance(self.poison_chance);
+        game.set_food_policy(self.food_policy);
+        game.set_power_ups(self.power_ups);
+        game.set_lives(self.lives);
+        game.set_fog(self.fog);
+        game.set_battle_royale(self.battle_royale);
+        game.set_random_events(self.events);
+        game.set_missions(self.missions);
+        game.set_waves(self.waves);
+        game.set_co_op(self.co_op);
+        game.set_prey_chance(self.prey_chance);
+        game.set_nibbles(self.nibbles);
+        for &condition in &self.win_conditions {
+            game.add_win_condition(condition);
+        }
+        game.set_food_ttl(self.food_ttl);
+        game.set_speed_curve(self.speed);
+        game.set_score_rules(self.scoring.clone());
+        Ok(game)
+    }
+}
+
+// Named rule sets for ways of playing that need several settings together
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Preset {
+    Classic,
+    Arcade,
+    Maze,
+    Nibbles,
+    BattleRoyale,
+    Boss,
+    Survival,
+}
+
+impl Preset {
+    pub const ALL: [Preset; 7] = [
+        Preset::Classic,
+        Preset::Arcade,
+        Preset::Maze,
+        Preset::Nibbles,
+        Preset::BattleRoyale,
+        Preset::Boss,
+        Preset::Survival,
+    ];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            Preset::Classic => "classic",
+            Preset::Arcade => "arcade",
+            Preset::Maze => "maze",
+            Preset::Nibbles => "nibbles",
+            Preset::BattleRoyale => "battle-royale",
+            Preset::Boss => "boss",
+            Preset::Survival => "survival",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Preset::ALL
+            .iter()
+            .copied()
+            .find(|preset| preset.key() == key)
+    }
+
+    pub fn rules(self) -> RuleSet {
+        let classic = RuleSet::default();
+        match self {
+            Preset::Classic => classic,
+            // Everything that turns up on its own, speeding up faster, and a
+            // couple of lives to make up for it
+            Preset::Arcade => RuleSet {
+                poison_chance: 0.2,
+                prey_chance: 0.2,
+                power_ups: true,
+                events: true,
+                lives: 3,
+                speed: SpeedCurve {
+                    ramp: 0.04,
+                    max: 2.5,
+                },
+                ..classic
+            },
+            Preset::Maze => RuleSet {
+                wall_density: 0.05,
+                portals: 2,
+                traps: 2,
+                food_policy: FoodPolicy::Open,
+                ..classic
+            },
+            Preset::Nibbles => RuleSet {
+                nibbles: true,
+                ..classic
+            },
+            Preset::BattleRoyale => RuleSet {
+                players: 2,
+                battle_royale: true,
+                ..classic
+            },
+            Preset::Boss => RuleSet {
+                boss: true,
+                ..classic
+            },
+            Preset::Survival => RuleSet {
+                waves: true,
+                lives: 3,
+                ..classic
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn random_walls_keep_clear_of_the_start() {
+        for seed in 0..20 {
+            let rules = RuleSet {
+                start: Some((2, 2)),
+                wall_density: 0.5,
+                seed: Some(seed),
+                ..RuleSet::default()
+            };
+            let game = rules.build().unwrap();
+            let segments: Vec<(i32, i32)> = game.segments(0).collect();
+            assert_eq!(segments, vec![(2, 2), (2, 3), (2, 4)]);
+            assert!(game.is_cell_safe(2, 1));
+        }
+    }
+
+    #[test]
+    fn starts_longer_than_the_board_are_rejected() {
+        let rules = RuleSet {
+            length: Some(2_000_000_000),
+            ..RuleSet::default()
+        };
+        assert_eq!(
+            rules.build().err().unwrap(),
+            "the snake can't be longer than the board's 256 tiles"
+        );
+    }
+
+    #[test]
+    fn hex_starts_head_along_the_diagonals() {
+        let rules = RuleSet {
+            start: Some((8, 8)),
+            heading: Some(Direction::Up),
+            topology: "hex".to_string(),
+            ..RuleSet::default()
+        };
+        let game = rules.build().unwrap();
+        assert!(game.direction(0) == Direction::UpLeft);
+        let segments: Vec<(i32, i32)> = game.segments(0).collect();
+        assert_eq!(segments, vec![(8, 8), (8, 9), (9, 10)]);
+
+        let rules = RuleSet {
+            heading: Some(Direction::UpLeft),
+            ..RuleSet::default()
+        };
+        assert_eq!(
+            rules.build().err().unwrap(),
+            "the snake can't head up-left on this board"
+        );
+    }
+
+    #[test]
+    fn games_speed_up_along_the_rule_sets_curve() {
+        let speed = SpeedCurve {
+            ramp: 0.5,
+            max: 2.0,
+        };
+        let rules = RuleSet {
+            speed,
+            ..RuleSet::default()
+        };
+        let game = rules.build().unwrap();
+        assert!(game.speed_curve() == speed);
+        assert_eq!(game.speed(), 1.0);
+        assert_eq!(speed.at(1), 1.5);
+        assert_eq!(speed.at(5), 2.0);
+        assert_eq!(SpeedCurve::FLAT.at(100), 1.0);
+    }
+}
@@ -0,0 +1,262 @@
+// Scoring rules say how many points everything is worth, so tournaments can
+// agree on one scoring without changing the game
+//
+// Rules can be read from a text file with one setting per line, leaving out
+// any that should keep their usual value:
+//   food <points>       for each normal food, times its number in Nibbles mode
+//   bonus <points>      for bonus food
+//   prey <points>       for catching prey
+//   poison <points>     for eating poison, usually negative
+//   tick <points>       for every tick survived
+//   length <points>     with every food, for each segment the snake has grown
+//   wall-hug <points>   extra for food eaten with the head beside a wall
+//   close-call <points> extra for food eaten with the head beside its own body
+//   combo <ticks>       how soon after the last food the next has to be eaten
+//                       to raise the combo multiplier, or 0 for no combos
+//   multiplier <mode> <times>
+// where the multiplier scales all points gained while playing in that mode,
+// one of nibbles, battle-royale, boss, waves or puzzle.
+//
+// Lines that are wrong are warned about and leave their setting as it was.
+
+use crate::diagnostic::{self, did_you_mean, nearest_in};
+use crate::json::Json;
+use std::fs;
+
+// The settings a rules file can have
+const SETTINGS: [&str; 10] = [
+    "food",
+    "bonus",
+    "prey",
+    "poison",
+    "tick",
+    "length",
+    "wall-hug",
+    "close-call",
+    "combo",
+    "multiplier",
+];
+
+// Points for eating each kind of food
+const FOOD_POINTS: i32 = 10;
+const POISON_POINTS: i32 = -20;
+const BONUS_POINTS: i32 = FOOD_POINTS * 5;
+const PREY_POINTS: i32 = FOOD_POINTS * 3;
+
+// Extra points for eating with the head beside a wall or the snake's own body
+const WALL_HUG_POINTS: i32 = 5;
+const CLOSE_CALL_POINTS: i32 = 10;
+
+// Food eaten within COMBO_TICKS ticks of the last raises the multiplier on
+// food points by one, up to COMBO_MAX
+const COMBO_TICKS: u32 = 20;
+pub const COMBO_MAX: i32 = 5;
+
+// Ways of playing that can have their own multiplier
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Nibbles,
+    BattleRoyale,
+    Boss,
+    Waves,
+    Puzzle,
+}
+
+impl Mode {
+    pub const ALL: [Mode; 5] = [
+        Mode::Nibbles,
+        Mode::BattleRoyale,
+        Mode::Boss,
+        Mode::Waves,
+        Mode::Puzzle,
+    ];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            Mode::Nibbles => "nibbles",
+            Mode::BattleRoyale => "battle-royale",
+            Mode::Boss => "boss",
+            Mode::Waves => "waves",
+            Mode::Puzzle => "puzzle",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Mode::ALL.iter().copied().find(|mode| mode.key() == key)
+    }
+}
+
+#[derive(Clone)]
+pub struct ScoreRules {
+    pub food: i32,
+    pub bonus: i32,
+    pub prey: i32,
+    pub poison: i32,
+    pub tick: i32,
+    pub length: i32,
+    pub wall_hug: i32,
+    pub close_call: i32,
+    pub combo: u32,
+    pub multipliers: Vec<(Mode, i32)>,
+}
+
+impl Default for ScoreRules {
+    fn default() -> Self {
+        Self {
+            food: FOOD_POINTS,
+            bonus: BONUS_POINTS,
+            prey: PREY_POINTS,
+            poison: POISON_POINTS,
+            tick: 0,
+            length: 0,
+            wall_hug: WALL_HUG_POINTS,
+            close_call: CLOSE_CALL_POINTS,
+            combo: COMBO_TICKS,
+            multipliers: Vec::new(),
+        }
+    }
+}
+
+impl ScoreRules {
+    // Read and parse a rules file
+    // Returns the rules with a warning for every line that was left out
+    pub fn load(path: &str) -> Result<(Self, Vec<String>), String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        let (rules, problems) = Self::parse(&text);
+        let warnings = problems
+            .iter()
+            .map(|problem| diagnostic::warning(path, problem))
+            .collect();
+        Ok((rules, warnings))
+    }
+
+    // Parse rules, saying what's wrong with each line that can't be
+    pub fn parse(text: &str) -> (Self, Vec<String>) {
+        let mut rules = Self::default();
+        let mut problems = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            if line.starts_with(';') {
+                continue;
+            }
+            let words: Vec<&str> = line.split_whitespace().collect();
+            if let Err(e) = rules.parse_line(&words) {
+                problems.push(format!("line {}: {}", i + 1, e));
+            }
+        }
+        (rules, problems)
+    }
+
+    fn parse_line(&mut self, words: &[&str]) -> Result<(), String> {
+        let points = |s: &str| {
+            s.parse()
+                .map_err(|_| "points must be a whole number".to_string())
+        };
+        match words {
+            [] => (),
+            ["food", n] => self.food = points(n)?,
+            ["bonus", n] => self.bonus = points(n)?,
+            ["prey", n] => self.prey = points(n)?,
+            ["poison", n] => self.poison = points(n)?,
+            ["tick", n] => self.tick = points(n)?,
+            ["length", n] => self.length = points(n)?,
+            ["wall-hug", n] => self.wall_hug = points(n)?,
+            ["close-call", n] => self.close_call = points(n)?,
+            ["combo", n] => {
+                self.combo = n.parse().map_err(|_| {
+                    format!(
+                        "combo must be a whole number of ticks{}",
+                        nearest_in(n, 0, u32::MAX.into())
+                    )
+                })?
+            }
+            ["multiplier", mode, times] => {
+                let keys: Vec<&str> = Mode::ALL.iter().map(|mode| mode.key()).collect();
+                let mode = Mode::from_key(mode)
+                    .ok_or_else(|| format!("unknown mode {}{}", mode, did_you_mean(mode, &keys)))?;
+                let times = times.parse().ok().filter(|&n| n > 0).ok_or_else(|| {
+                    format!(
+                        "multiplier must be a positive number{}",
+                        nearest_in(times, 1, i32::MAX.into())
+                    )
+                })?;
+                self.multipliers.retain(|&(other, _)| other != mode);
+                self.multipliers.push((mode, times));
+            }
+            [setting, ..] if !SETTINGS.contains(setting) => {
+                return Err(format!(
+                    "unknown setting {}{}",
+                    setting,
+                    did_you_mean(setting, &SETTINGS)
+                ))
+            }
+            [setting, ..] => {
+                let value = if *setting == "multiplier" {
+                    "a mode and a number"
+                } else {
+                    "a number"
+                };
+                return Err(format!("{} needs {}", setting, value));
+            }
+        }
+        Ok(())
+    }
+
+    // The rules under the same names as in a rules file, for a game state
+    // snapshot
+    pub fn to_json(&self) -> Json {
+        let multipliers = self
+            .multipliers
+            .iter()
+            .map(|&(mode, times)| (mode.key().to_string(), times.into()))
+            .collect();
+        Json::Object(vec![
+            ("food".to_string(), self.food.into()),
+            ("bonus".to_string(), self.bonus.into()),
+            ("prey".to_string(), self.prey.into()),
+            ("poison".to_string(), self.poison.into()),
+            ("tick".to_string(), self.tick.into()),
+            ("length".to_string(), self.length.into()),
+            ("wall-hug".to_string(), self.wall_hug.into()),
+            ("close-call".to_string(), self.close_call.into()),
+            ("combo".to_string(), self.combo.into()),
+            ("multipliers".to_string(), Json::Object(multipliers)),
+        ])
+    }
+
+    pub fn from_json(json: &Json) -> Result<Self, String> {
+        let multipliers = match json.get("multipliers")? {
+            Json::Object(fields) => fields
+                .iter()
+                .map(|(key, times)| {
+                    let mode = Mode::from_key(key)
+                        .ok_or_else(|| format!("multipliers: unknown mode {}", key))?;
+                    let times = times
+                        .as_i32()
+                        .map_err(|e| format!("multipliers: {}: {}", key, e))?;
+                    Ok((mode, times))
+                })
+                .collect::<Result<_, String>>()?,
+            _ => return Err("multipliers: expected an object".to_string()),
+        };
+        Ok(Self {
+            food: json.at("food", Json::as_i32)?,
+            bonus: json.at("bonus", Json::as_i32)?,
+            prey: json.at("prey", Json::as_i32)?,
+            poison: json.at("poison", Json::as_i32)?,
+            tick: json.at("tick", Json::as_i32)?,
+            length: json.at("length", Json::as_i32)?,
+            wall_hug: json.at("wall-hug", Json::as_i32)?,
+            close_call: json.at("close-call", Json::as_i32)?,
+            combo: json.at("combo", Json::as_u32)?,
+            multipliers,
+        })
+    }
+
+    // How much points gained are scaled by while playing in this mode
+    pub fn multiplier(&self, mode: Mode) -> i32 {
+        self.multipliers
+            .iter()
+            .find(|&&(other, _)| other == mode)
+            .map_or(1, |&(_, times)| times)
+    }
+}
@@ -0,0 +1,338 @@
+// Scripts are small files of rules that change how a game plays, so new
+// variants can be written without touching the game itself
+//
+// Every file ending in .script in the mods directory, or in a mod that's
+// switched on, is read at startup.
+// Each line is a rule saying what to do when something happens:
+//   on <trigger> <action>
+// where the trigger is one of
+//   eat            a snake eats anything
+//   eat <food>     a snake eats normal, poison, bonus, number, prey, shrink
+//                  or frenzy food
+//   turn           a snake turns
+//   die            a snake dies, even if it has lives left
+//   power-up       a power-up or trap starts on a snake
+//   win            the first snake meets a win condition
+//   every <ticks>  every so many ticks
+// and the action is one of
+//   points <n>     give the snake that set it off points, or take them away
+//                  if negative, counting the first snake for every
+//   spawn bonus    put bonus food somewhere risky
+//   spawn frenzy   put down one food of the kind a food frenzy brings
+//   spawn wall     put a wall somewhere away from every head
+//   say <text>     show the first player a message
+// Lines starting with ; are comments.
+//
+// Scripts only run on single games, since boards that share a seed would
+// drift apart as they changed them.
+
+use crate::diagnostic::did_you_mean;
+use crate::event::GameEvent;
+use crate::game::{FoodKind, Game};
+use crate::hooks::Hooks;
+use crate::snake::SnakeId;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// The extension script files need in the mods directory
+const EXTENSION: &str = "script";
+
+// The first word of each trigger and action
+const TRIGGERS: [&str; 6] = ["eat", "turn", "die", "power-up", "win", "every"];
+const ACTIONS: [&str; 3] = ["points", "spawn", "say"];
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Trigger {
+    Eat(Option<&'static str>), // the food's key, if it's only one kind
+    Turn,
+    Die,
+    PowerUp,
+    Win,
+    Every(u32),
+}
+
+#[derive(Clone, PartialEq, Eq)]
+enum Action {
+    Points(i32),
+    SpawnBonus,
+    SpawnFrenzy,
+    SpawnWall,
+    Say(String),
+}
+
+#[derive(Clone)]
+struct Rule {
+    trigger: Trigger,
+    action: Action,
+}
+
+#[derive(Clone, Default)]
+pub struct Script {
+    rules: Vec<Rule>,
+    ticks: u32, // updates seen, across every level played
+}
+
+impl Script {
+    // Read every script in a directory, in order of their names, as one
+    // script with all their rules
+    // There being no directory just means there are no scripts
+    pub fn load_dir(dir: &Path) -> Result<Self, String> {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(_) => return Ok(Self::default()),
+        };
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| Some(entry.ok()?.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == EXTENSION))
+            .collect();
+        paths.sort();
+
+        let mut script = Self::default();
+        for path in paths {
+            script.rules.extend(Self::load(&path)?.rules);
+        }
+        Ok(script)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let name = path.display();
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", name, e))?;
+        Self::parse(&text).map_err(|e| format!("{}: {}", name, e))
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut script = Self::default();
+        for (i, line) in text.lines().enumerate() {
+            let line_no = i + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(';') {
+                continue;
+            }
+
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let (trigger, rest) = match words.as_slice() {
+                ["on", "eat", food, rest @ ..] if food_key(food).is_some() => {
+                    (Trigger::Eat(food_key(food)), rest)
+                }
+                ["on", "eat", rest @ ..] => (Trigger::Eat(None), rest),
+                ["on", "turn", rest @ ..] => (Trigger::Turn, rest),
+                ["on", "die", rest @ ..] => (Trigger::Die, rest),
+                ["on", "power-up", rest @ ..] => (Trigger::PowerUp, rest),
+                ["on", "win", rest @ ..] => (Trigger::Win, rest),
+                ["on", "every", ticks, rest @ ..] => {
+                    let ticks = ticks.parse().ok().filter(|&n| n > 0).ok_or_else(|| {
+                        format!("line {}: every must be a positive number of ticks", line_no)
+                    })?;
+                    (Trigger::Every(ticks), rest)
+                }
+                ["on", trigger, ..] => {
+                    return Err(format!(
+                        "line {}: unknown trigger {}{}",
+                        line_no,
+                        trigger,
+                        did_you_mean(trigger, &TRIGGERS)
+                    ))
+                }
+                ["on"] => return Err(format!("line {}: missing trigger", line_no)),
+                _ => return Err(format!("line {}: rules start with on", line_no)),
+            };
+            let action = match rest {
+                ["points", n] => Action::Points(
+                    n.parse()
+                        .map_err(|_| format!("line {}: points must be a whole number", line_no))?,
+                ),
+                ["spawn", "bonus"] => Action::SpawnBonus,
+                ["spawn", "frenzy"] => Action::SpawnFrenzy,
+                ["spawn", "wall"] => Action::SpawnWall,
+                ["say", text @ ..] if !text.is_empty() => Action::Say(text.join(" ")),
+                [] => return Err(format!("line {}: missing action", line_no)),
+                [action, ..] => {
+                    return Err(format!(
+                        "line {}: unknown action {}{}",
+                        line_no,
+                        action,
+                        did_you_mean(action, &ACTIONS)
+                    ))
+                }
+            };
+            script.rules.push(Rule { trigger, action });
+        }
+        Ok(script)
+    }
+
+    // Add another script's rules after this one's
+    pub fn extend(&mut self, other: Script) {
+        self.rules.extend(other.rules);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    // How many rules there are, across every file read
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    // The snakes that set off a trigger on the last update, once for each
+    // time they did
+    fn fired(&self, trigger: Trigger, game: &Game) -> Vec<SnakeId> {
+        if let Trigger::Every(ticks) = trigger {
+            return if self.ticks.is_multiple_of(ticks) {
+                vec![0]
+            } else {
+                Vec::new()
+            };
+        }
+        game.happened()
+            .iter()
+            .filter_map(|event| match (trigger, *event) {
+                (Trigger::Eat(food), GameEvent::FoodEaten { snake, kind, .. })
+                    if food.is_none_or(|food| food == kind.key()) =>
+                {
+                    Some(snake)
+                }
+                (Trigger::Turn, GameEvent::Turned { snake, .. }) => Some(snake),
+                (Trigger::Die, GameEvent::Died { snake, .. }) => Some(snake),
+                (Trigger::PowerUp, GameEvent::PowerUpStarted { snake, .. }) => Some(snake),
+                (Trigger::Win, GameEvent::Won(_)) => Some(0),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+impl Hooks for Script {
+    fn before_tick(&mut self, _game: &mut Game) {
+        self.ticks += 1;
+    }
+
+    // Work out everything the rules do before doing any of it, so one rule
+    // can't set off another on the same update
+    fn after_tick(&mut self, game: &mut Game) {
+        let mut actions = Vec::new();
+        for rule in &self.rules {
+            for snake in self.fired(rule.trigger, game) {
+                actions.push((snake, rule.action.clone()));
+            }
+        }
+
+        for (snake, action) in actions {
+            match action {
+                Action::Points(points) => game.add_points(snake, points),
+                Action::SpawnBonus => game.add_bonus(),
+                Action::SpawnFrenzy => game.add_frenzy_food(),
+                Action::SpawnWall => game.add_wall(),
+                Action::Say(text) => game.show_popup(text),
+            }
+        }
+    }
+}
+
+// The key of a kind of food named in a script
+fn food_key(name: &str) -> Option<&'static str> {
+    FoodKind::ALL
+        .iter()
+        .map(|kind| kind.key())
+        .find(|&key| key == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::test_board;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    // Where a test's scripts go, emptied first
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ascii-snake-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn rules_read_every_trigger_and_action() {
+        let script = Script::parse(
+            "; a comment\n\non eat poison points -5\non eat spawn bonus\non turn spawn wall\n\
+             on die say ouch\non power-up spawn frenzy\non win points 100\non every 10 say hi there\n",
+        )
+        .unwrap();
+        let triggers: Vec<Trigger> = script.rules.iter().map(|rule| rule.trigger).collect();
+        assert!(
+            triggers
+                == vec![
+                    Trigger::Eat(Some("poison")),
+                    Trigger::Eat(None),
+                    Trigger::Turn,
+                    Trigger::Die,
+                    Trigger::PowerUp,
+                    Trigger::Win,
+                    Trigger::Every(10),
+                ]
+        );
+        assert!(script.rules[6].action == Action::Say("hi there".to_string()));
+        assert!(script.rules[0].action == Action::Points(-5));
+    }
+
+    #[test]
+    fn bad_rules_say_which_line_is_wrong() {
+        let error = |text: &str| Script::parse(text).err().unwrap();
+        assert_eq!(error("eat points 1"), "line 1: rules start with on");
+        assert_eq!(error("; fine\non"), "line 2: missing trigger");
+        assert_eq!(
+            error("on eats points 1"),
+            "line 1: unknown trigger eats (did you mean eat?)"
+        );
+        assert_eq!(error("on turn"), "line 1: missing action");
+        assert_eq!(
+            error("on turn spwn wall"),
+            "line 1: unknown action spwn (did you mean spawn?)"
+        );
+        assert_eq!(
+            error("on turn points lots"),
+            "line 1: points must be a whole number"
+        );
+        assert_eq!(
+            error("on every 0 say hi"),
+            "line 1: every must be a positive number of ticks"
+        );
+    }
+
+    #[test]
+    fn directories_read_only_their_scripts_in_name_order() {
+        let dir = scratch_dir("scripts");
+        fs::write(dir.join("b.script"), "on win points 2\n").unwrap();
+        fs::write(dir.join("a.script"), "on win points 1\n").unwrap();
+        fs::write(dir.join("notes.txt"), "not a script\n").unwrap();
+        let script = Script::load_dir(&dir).unwrap();
+        let actions: Vec<Action> = script.rules.iter().map(|r| r.action.clone()).collect();
+        assert!(actions == vec![Action::Points(1), Action::Points(2)]);
+
+        fs::write(dir.join("c.script"), "on win jump\n").unwrap();
+        let error = Script::load_dir(&dir).err().unwrap();
+        assert!(error.ends_with("c.script: line 1: unknown action jump"));
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(Script::load_dir(&dir).unwrap().is_empty());
+    }
+
+    #[test]
+    fn eating_sets_off_the_rules_for_that_food() {
+        let play = |script: &str| {
+            let mut game = test_board("oo@*.\n.....\n");
+            let script = Script::parse(script).unwrap();
+            game.add_hooks(Rc::new(RefCell::new(script)));
+            game.update();
+            game.score()
+        };
+        let plain = play("");
+        assert_eq!(play("on eat points 7"), plain + 7);
+        assert_eq!(
+            play("on eat normal points 7\non eat poison points 100"),
+            plain + 7
+        );
+        assert_eq!(play("on turn points 7"), plain);
+    }
+}
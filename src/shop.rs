@@ -0,0 +1,101 @@
+// Upgrades bought between waves with coins earned during the run
+// They only last for the run, unlike perks
+
+use crate::locale::tr;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Upgrade {
+    // Takes the next fatal hit without losing a life
+    Shield,
+    // Doubles points scored during the next wave
+    DoubleScore,
+}
+
+impl Upgrade {
+    // Everything on sale, in the order the shop lists it
+    pub const ALL: [Upgrade; 2] = [Upgrade::Shield, Upgrade::DoubleScore];
+
+    pub fn cost(self) -> u32 {
+        match self {
+            Upgrade::Shield => 5,
+            Upgrade::DoubleScore => 8,
+        }
+    }
+
+    pub fn name(self) -> String {
+        let key = match self {
+            Upgrade::Shield => "upgrade.shield",
+            Upgrade::DoubleScore => "upgrade.double-score",
+        };
+        tr(key, &[])
+    }
+}
+
+// Each wave is over once this many foods have been eaten
+const WAVE_FOOD: u32 = 5;
+
+// Coins for each food eaten, and for clearing a wave
+const FOOD_COINS: u32 = 1;
+const WAVE_COINS: u32 = 5;
+
+// The run's economy in survival mode: waves of food, with a shop after each
+#[derive(Clone)]
+pub struct Waves {
+    pub wave: u32,
+    pub food_left: u32,
+    pub coins: u32,
+    pub shields: u32,
+    pub multiplier: i32, // points scored this wave are multiplied by this
+    pub shop_open: bool,
+    next_multiplier: i32,
+}
+
+impl Default for Waves {
+    fn default() -> Self {
+        Self {
+            wave: 1,
+            food_left: WAVE_FOOD,
+            coins: 0,
+            shields: 0,
+            multiplier: 1,
+            shop_open: false,
+            next_multiplier: 1,
+        }
+    }
+}
+
+impl Waves {
+    // Count a food towards the wave, opening the shop when it's cleared
+    pub fn ate(&mut self) {
+        self.coins += FOOD_COINS;
+        self.food_left = self.food_left.saturating_sub(1);
+        if self.food_left == 0 {
+            self.coins += WAVE_COINS;
+            self.multiplier = 1;
+            self.shop_open = true;
+        }
+    }
+
+    // Buy an upgrade from the shop
+    // Returns an error if the shop is closed or it can't be afforded
+    pub fn buy(&mut self, upgrade: Upgrade) -> Result<(), ()> {
+        if !self.shop_open || self.coins < upgrade.cost() {
+            return Err(());
+        }
+        self.coins -= upgrade.cost();
+        match upgrade {
+            Upgrade::Shield => self.shields += 1,
+            Upgrade::DoubleScore => self.next_multiplier *= 2,
+        }
+        Ok(())
+    }
+
+    // Leave the shop and start the next wave
+    pub fn close_shop(&mut self) {
+        self.shop_open = false;
+        self.wave += 1;
+        self.food_left = WAVE_FOOD;
+        self.multiplier = self.next_multiplier;
+        self.next_multiplier = 1;
+    }
+}
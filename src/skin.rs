@@ -0,0 +1,112 @@
+// Skins change how the first player's snake looks
+// All but the classic look are unlocked by reaching milestones
+
+use crate::locale::tr;
+use crossterm::style::Color;
+
+// Things a player can achieve over their runs
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Milestone {
+    Score500,
+    BeatBoss,
+    SolvePuzzle,
+    WinDaily,
+}
+
+impl Milestone {
+    pub const ALL: [Milestone; 4] = [
+        Milestone::Score500,
+        Milestone::BeatBoss,
+        Milestone::SolvePuzzle,
+        Milestone::WinDaily,
+    ];
+
+    pub fn description(self) -> String {
+        tr(&format!("milestone.{}", self.key()), &[])
+    }
+
+    // The name the milestone is saved under in the profile
+    pub fn key(self) -> &'static str {
+        match self {
+            Milestone::Score500 => "score-500",
+            Milestone::BeatBoss => "beat-boss",
+            Milestone::SolvePuzzle => "solve-puzzle",
+            Milestone::WinDaily => "win-daily",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Milestone::ALL.iter().copied().find(|m| m.key() == key)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Skin {
+    Classic,
+    Ember,
+    Gold,
+    Circuit,
+    Frost,
+}
+
+impl Skin {
+    // Every skin, in the order the picker lists them
+    pub const ALL: [Skin; 5] = [
+        Skin::Classic,
+        Skin::Ember,
+        Skin::Gold,
+        Skin::Circuit,
+        Skin::Frost,
+    ];
+
+    pub fn name(self) -> String {
+        tr(&format!("skin.{}", self.key()), &[])
+    }
+
+    // The milestone that unlocks the skin, if it isn't always available
+    pub fn unlocked_by(self) -> Option<Milestone> {
+        match self {
+            Skin::Classic => None,
+            Skin::Ember => Some(Milestone::Score500),
+            Skin::Gold => Some(Milestone::BeatBoss),
+            Skin::Circuit => Some(Milestone::SolvePuzzle),
+            Skin::Frost => Some(Milestone::WinDaily),
+        }
+    }
+
+    // Get a two-character ASCII representation of a body segment, or a
+    // one-character one for compact boards, if the skin doesn't just use the
+    // theme's
+    pub fn ascii_rep(self, compact: bool) -> Option<&'static str> {
+        match (self, compact) {
+            (Skin::Classic | Skin::Ember | Skin::Gold | Skin::Frost, _) => None,
+            (Skin::Circuit, false) => Some("[]"),
+            (Skin::Circuit, true) => Some("#"),
+        }
+    }
+
+    // Get the color to draw the snake in, if it isn't drawn plainly
+    pub fn color(self) -> Option<Color> {
+        match self {
+            Skin::Classic => None,
+            Skin::Ember => Some(Color::DarkRed),
+            Skin::Gold => Some(Color::Yellow),
+            Skin::Circuit => Some(Color::Cyan),
+            Skin::Frost => Some(Color::Blue),
+        }
+    }
+
+    pub fn key(self) -> &'static str {
+        match self {
+            Skin::Classic => "classic",
+            Skin::Ember => "ember",
+            Skin::Gold => "gold",
+            Skin::Circuit => "circuit",
+            Skin::Frost => "frost",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Skin::ALL.iter().copied().find(|skin| skin.key() == key)
+    }
+}
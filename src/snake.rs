@@ -0,0 +1,202 @@
+use crate::game::{Direction, InputTransform, SnakeVal};
+use crate::json::Json;
+use crate::level::parse_direction;
+use crate::power_up::{Effect, PowerUp};
+
+pub type SnakeId = u8;
+
+// Length of a freshly spawned snake
+pub const START_LENGTH: i32 = 3;
+
+// Everything about one snake except where its body is, which lives on the
+// board's tiles
+#[derive(Clone)]
+pub struct Snake {
+    pub head_x: i32,
+    pub head_y: i32,
+    pub direction: Direction,
+    pub heading: Direction, // the way the last move went
+    pub alive: bool,
+    pub length: i32,
+    pub start_length: i32, // how long the snake is after respawning
+    pub score: i32,
+    pub growth: i32, // segments still to be grown
+    pub effects: Vec<Effect>,
+    pub overlaps: Vec<(i32, i32, SnakeVal)>, // segments hidden under newer ones by ghosting
+    pub lives: u32,
+    pub invincible: u32, // ticks of respawn protection left
+    pub spawn: (i32, i32),
+    pub spawn_direction: Direction,
+    pub combo: i32, // the multiplier for the next food eaten quickly enough
+    pub last_meal: Option<u32>, // the tick food was last eaten on
+}
+
+impl Snake {
+    pub fn new(spawn: (i32, i32)) -> Self {
+        Self {
+            head_x: spawn.0,
+            head_y: spawn.1,
+            direction: Direction::Up,
+            heading: Direction::Up,
+            alive: true,
+            length: START_LENGTH,
+            start_length: START_LENGTH,
+            score: 0,
+            growth: 0,
+            effects: Vec::new(),
+            overlaps: Vec::new(),
+            lives: 1,
+            invincible: 0,
+            spawn,
+            spawn_direction: Direction::Up,
+            combo: 1,
+            last_meal: None,
+        }
+    }
+
+    // Set the snake's direction
+    // Returns an error if direction is opposite to current direction
+    pub fn set_direction(&mut self, direction: Direction) -> Result<(), ()> {
+        if direction == self.direction.opposite() {
+            Err(())
+        } else {
+            self.direction = direction;
+            Ok(())
+        }
+    }
+
+    // How input for this snake should be scrambled by its active effects
+    pub fn input_transform(&self) -> InputTransform {
+        InputTransform {
+            mirror: self.has_effect(PowerUp::Mirrored),
+            reverse: self.has_effect(PowerUp::Reversed),
+        }
+    }
+
+    pub fn effect(&self, power_up: PowerUp) -> Option<Effect> {
+        self.effects
+            .iter()
+            .find(|effect| effect.power_up == power_up)
+            .copied()
+    }
+
+    pub fn has_effect(&self, power_up: PowerUp) -> bool {
+        self.effect(power_up).is_some()
+    }
+
+    // Start an effect, restarting it if it's already running
+    pub fn start_effect(&mut self, power_up: PowerUp) {
+        self.effects.retain(|effect| effect.power_up != power_up);
+        self.effects.push(Effect::new(power_up));
+    }
+
+    // Count down active effects, returning the ones that ran out
+    pub fn tick_effects(&mut self) -> Vec<PowerUp> {
+        for effect in &mut self.effects {
+            effect.ticks_left = effect.ticks_left.saturating_sub(1);
+        }
+        let ended = self
+            .effects
+            .iter()
+            .filter(|effect| effect.ticks_left == 0)
+            .map(|effect| effect.power_up)
+            .collect();
+        self.effects.retain(|effect| effect.ticks_left > 0);
+        ended
+    }
+
+    // Put the snake back at its spawn point, facing the way it started
+    // Its fresh body is laid out on the board by the game
+    pub fn respawn(&mut self, invincible: u32) {
+        self.overlaps.clear();
+        self.effects.clear();
+        self.growth = 0;
+        self.length = self.start_length;
+        self.head_x = self.spawn.0;
+        self.head_y = self.spawn.1;
+        self.direction = self.spawn_direction;
+        self.heading = self.spawn_direction;
+        self.invincible = invincible;
+        self.combo = 1;
+        self.last_meal = None;
+    }
+
+    // Everything about the snake, for a game state snapshot
+    pub fn to_json(&self) -> Json {
+        let effects: Vec<Json> = self
+            .effects
+            .iter()
+            .map(|effect| {
+                Json::Object(vec![
+                    ("power_up".to_string(), effect.power_up.key().into()),
+                    ("ticks_left".to_string(), effect.ticks_left.into()),
+                ])
+            })
+            .collect();
+        let overlaps: Vec<Json> = self
+            .overlaps
+            .iter()
+            .map(|&(x, y, val)| vec![x, y, val].into())
+            .collect();
+        Json::Object(vec![
+            ("head".to_string(), Json::pos(self.head_x, self.head_y)),
+            ("direction".to_string(), self.direction.key().into()),
+            ("heading".to_string(), self.heading.key().into()),
+            ("alive".to_string(), self.alive.into()),
+            ("length".to_string(), self.length.into()),
+            ("start_length".to_string(), self.start_length.into()),
+            ("score".to_string(), self.score.into()),
+            ("growth".to_string(), self.growth.into()),
+            ("effects".to_string(), effects.into()),
+            ("overlaps".to_string(), overlaps.into()),
+            ("lives".to_string(), self.lives.into()),
+            ("invincible".to_string(), self.invincible.into()),
+            ("spawn".to_string(), Json::pos(self.spawn.0, self.spawn.1)),
+            (
+                "spawn_direction".to_string(),
+                self.spawn_direction.key().into(),
+            ),
+            ("combo".to_string(), self.combo.into()),
+            ("last_meal".to_string(), self.last_meal.into()),
+        ])
+    }
+
+    pub fn from_json(json: &Json) -> Result<Self, String> {
+        let direction = |value: &Json| {
+            let key = value.as_str()?;
+            parse_direction(key).ok_or_else(|| format!("unknown direction {}", key))
+        };
+        let effect = |value: &Json| {
+            let key = value.at("power_up", Json::as_string)?;
+            Ok(Effect {
+                power_up: PowerUp::from_key(&key)
+                    .ok_or_else(|| format!("unknown power-up {}", key))?,
+                ticks_left: value.at("ticks_left", Json::as_u32)?,
+            })
+        };
+        let overlap = |value: &Json| match value.as_array()? {
+            [x, y, val] => Ok((x.as_i32()?, y.as_i32()?, val.as_i32()?)),
+            _ => Err("expected [x, y, segment]".to_string()),
+        };
+        let (head_x, head_y) = json.at("head", Json::as_pos)?;
+        Ok(Self {
+            head_x,
+            head_y,
+            direction: json.at("direction", direction)?,
+            heading: json.at("heading", direction)?,
+            alive: json.at("alive", Json::as_bool)?,
+            length: json.at("length", Json::as_i32)?,
+            start_length: json.at("start_length", Json::as_i32)?,
+            score: json.at("score", Json::as_i32)?,
+            growth: json.at("growth", Json::as_i32)?,
+            effects: json.at("effects", |v| v.as_array()?.iter().map(effect).collect())?,
+            overlaps: json.at("overlaps", |v| v.as_array()?.iter().map(overlap).collect())?,
+            lives: json.at("lives", Json::as_u32)?,
+            invincible: json.at("invincible", Json::as_u32)?,
+            spawn: json.at("spawn", Json::as_pos)?,
+            spawn_direction: json.at("spawn_direction", direction)?,
+            combo: json.at("combo", Json::as_i32)?,
+            last_meal: json.at_option("last_meal", Json::as_u32)?,
+        })
+    }
+}
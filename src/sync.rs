@@ -0,0 +1,292 @@
+// Syncing keeps progress the same on every machine it's played on, through
+// a server the player sets up themselves. It's switched on by a sync file in
+// the data directory, with one setting per line:
+//   endpoint <url>
+//   token <secret>
+//
+// The profile, the high score and the achievements of every mod installed
+// are synced, each kept at <endpoint>/<name> where the name is where it sits
+// in the data directory, like profile or mods/<mod>/achieved. Getting one
+// returns it with a first line of
+//   updated <seconds since 1970>
+// or 404 if it's never been put there, and putting one sends it the same
+// way. Both send the token as a bearer token. Whichever copy was changed
+// last wins, and the other is replaced by it.
+//
+// It only works in builds with the sync feature, and goes through curl, which
+// has to be installed.
+
+#[cfg(feature = "sync")]
+use crate::diagnostic::did_you_mean;
+#[cfg(feature = "sync")]
+use crate::locale::tr;
+#[cfg(feature = "sync")]
+use crate::profile;
+#[cfg(feature = "sync")]
+use std::fs;
+#[cfg(feature = "sync")]
+use std::io::Write;
+#[cfg(feature = "sync")]
+use std::path::{Path, PathBuf};
+#[cfg(feature = "sync")]
+use std::process::{Command, Stdio};
+#[cfg(feature = "sync")]
+use std::time::{Duration, UNIX_EPOCH};
+
+// The file in the data directory that switches syncing on
+#[cfg(feature = "sync")]
+const SETTINGS_FILE: &str = "sync";
+
+// Every setting the sync file can have
+#[cfg(feature = "sync")]
+const SETTINGS: [&str; 2] = ["endpoint", "token"];
+
+// The files synced besides each mod's achievements, by where they are in
+// the data directory
+#[cfg(feature = "sync")]
+const FILES: [&str; 2] = ["profile", "high-score"];
+
+#[cfg(feature = "sync")]
+struct Settings {
+    endpoint: String, // without a / on the end
+    token: String,
+}
+
+#[cfg(feature = "sync")]
+impl Settings {
+    fn path() -> Option<PathBuf> {
+        Some(profile::data_dir()?.join(SETTINGS_FILE))
+    }
+
+    fn load() -> Result<Self, String> {
+        let path = Self::path().ok_or("there's no home directory to sync")?;
+        if !path.exists() {
+            return Err(format!(
+                "syncing isn't set up, {} needs an endpoint and a token",
+                path.display()
+            ));
+        }
+        let text = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    fn parse(text: &str) -> Result<Self, String> {
+        let (mut endpoint, mut token) = (None, None);
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(';') {
+                continue;
+            }
+
+            let (setting, value) = line.split_once(' ').unwrap_or((line, ""));
+            let value = value.trim();
+            match setting {
+                "endpoint" | "token" if value.is_empty() => {
+                    return Err(format!("line {}: {} needs a value", i + 1, setting))
+                }
+                "endpoint" => endpoint = Some(value.trim_end_matches('/').to_string()),
+                "token" => token = Some(value.to_string()),
+                _ => {
+                    return Err(format!(
+                        "line {}: unknown setting {}{}",
+                        i + 1,
+                        setting,
+                        did_you_mean(setting, &SETTINGS)
+                    ))
+                }
+            }
+        }
+        Ok(Self {
+            endpoint: endpoint.ok_or("there's no endpoint to sync with")?,
+            token: token.ok_or("there's no token to sync with")?,
+        })
+    }
+}
+
+// Whether syncing has been switched on
+#[cfg(feature = "sync")]
+pub fn set_up() -> bool {
+    Settings::path().is_some_and(|path| path.exists())
+}
+
+#[cfg(not(feature = "sync"))]
+pub fn set_up() -> bool {
+    false
+}
+
+// Sync every file, returning a line about each one that was sent or fetched
+#[cfg(feature = "sync")]
+pub fn now() -> Result<Vec<String>, String> {
+    let settings = Settings::load()?;
+    let dir = profile::data_dir().ok_or("there's no home directory to sync")?;
+    let mut changes = Vec::new();
+    for name in files(&dir) {
+        if let Some(change) = sync_file(&settings, &dir, &name)? {
+            changes.push(change);
+        }
+    }
+    Ok(changes)
+}
+
+#[cfg(not(feature = "sync"))]
+pub fn now() -> Result<Vec<String>, String> {
+    Err("this build can't sync, it needs the sync feature".to_string())
+}
+
+// The names of the files to sync, with each mod's achievements whether it
+// has any yet or not, so ones reached elsewhere are fetched
+#[cfg(feature = "sync")]
+fn files(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = FILES.iter().map(|name| name.to_string()).collect();
+    let mut mods: Vec<String> = fs::read_dir(dir.join("mods"))
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| path.is_dir())
+        .filter_map(|path| Some(path.file_name()?.to_string_lossy().into_owned()))
+        .collect();
+    mods.sort();
+    names.extend(mods.iter().map(|key| format!("mods/{}/achieved", key)));
+    names
+}
+
+// When a file was last changed, in seconds since 1970, if it's there
+#[cfg(feature = "sync")]
+fn modified(path: &Path) -> Result<Option<u64>, String> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let modified = fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(Some(
+        modified
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs()),
+    ))
+}
+
+// Bring one file and the server's copy of it together, keeping whichever
+// was changed last
+#[cfg(feature = "sync")]
+fn sync_file(settings: &Settings, dir: &Path, name: &str) -> Result<Option<String>, String> {
+    let path = dir.join(name);
+    let local = modified(&path)?;
+
+    let (status, body) = request(settings, name, None)?;
+    let remote = match status {
+        200 => {
+            let (first, text) = body.split_once('\n').unwrap_or((&body, ""));
+            let updated = first
+                .strip_prefix("updated ")
+                .and_then(|seconds| seconds.trim().parse().ok())
+                .ok_or_else(|| format!("{}: the server's copy doesn't say when it's from", name))?;
+            Some((updated, text.to_string()))
+        }
+        404 => None,
+        status => return Err(format!("{}: the server answered {}", name, status)),
+    };
+
+    match (local, remote) {
+        (local, Some((updated, text))) if local.is_none_or(|local| updated > local) => {
+            let fail = |e: std::io::Error| format!("{}: {}", path.display(), e);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).map_err(fail)?;
+            }
+            fs::write(&path, text).map_err(fail)?;
+            // Take on the server's time too, so the copies match next time
+            fs::File::options()
+                .write(true)
+                .open(&path)
+                .and_then(|file| file.set_modified(UNIX_EPOCH + Duration::from_secs(updated)))
+                .map_err(fail)?;
+            Ok(Some(tr("sync.fetched", &[&name])))
+        }
+        (Some(local), remote) if remote.as_ref().is_none_or(|(updated, _)| local > *updated) => {
+            let text =
+                fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            let body = format!("updated {}\n{}", local, text);
+            match request(settings, name, Some(&body))? {
+                (200..=299, _) => Ok(Some(tr("sync.sent", &[&name]))),
+                (status, _) => Err(format!("{}: the server answered {}", name, status)),
+            }
+        }
+        // Neither copy is newer
+        _ => Ok(None),
+    }
+}
+
+// Get a file from the server, or put one there, returning the status
+// it answered with and what came back
+// curl reads what to do from its standard input, so the token isn't on its
+// command line for anyone to see
+#[cfg(feature = "sync")]
+fn request(settings: &Settings, name: &str, upload: Option<&str>) -> Result<(u32, String), String> {
+    let url: Vec<String> = name.split('/').map(escape_url).collect();
+    let mut config = format!(
+        "url = {}\nheader = {}\nwrite-out = \"\\n%{{http_code}}\"\nsilent\nshow-error\n",
+        quote(&format!("{}/{}", settings.endpoint, url.join("/"))),
+        quote(&format!("Authorization: Bearer {}", settings.token))
+    );
+    if let Some(body) = upload {
+        config += &format!(
+            "request = PUT\nheader = \"Content-Type: text/plain\"\ndata-binary = {}\n",
+            quote(body)
+        );
+    }
+
+    let fail = |e: std::io::Error| format!("curl: {}", e);
+    let mut curl = Command::new("curl")
+        .args(["--config", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => "curl isn't installed, syncing needs it".to_string(),
+            _ => fail(e),
+        })?;
+    if let Some(mut stdin) = curl.stdin.take() {
+        stdin.write_all(config.as_bytes()).map_err(fail)?;
+    }
+    let output = curl.wait_with_output().map_err(fail)?;
+    if !output.status.success() {
+        // curl's errors already say they're from curl
+        let error = String::from_utf8_lossy(&output.stderr);
+        return Err(error.trim().to_string());
+    }
+
+    let text = String::from_utf8_lossy(&output.stdout);
+    let (body, status) = text.rsplit_once('\n').unwrap_or(("", &text));
+    let status = status
+        .trim()
+        .parse()
+        .map_err(|_| "curl: the server didn't answer with a status".to_string())?;
+    Ok((status, body.to_string()))
+}
+
+// A string as it's written in a curl config file
+#[cfg(feature = "sync")]
+fn quote(s: &str) -> String {
+    let escaped = s
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+        .replace('\t', "\\t");
+    format!("\"{}\"", escaped)
+}
+
+// Part of a path as it's written in a URL, with everything but letters,
+// digits and -._~ written as %XX
+#[cfg(feature = "sync")]
+fn escape_url(part: &str) -> String {
+    part.bytes()
+        .map(|byte| match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
@@ -0,0 +1,146 @@
+// Themes change the glyphs the board is drawn with
+//
+// A theme file sets glyphs one per line, leaving out any that should keep
+// their usual look:
+//   empty "  "
+//   food "><"
+//   body "██"
+//   head "██"
+//   wall "▒▒"
+// Every glyph has to fill exactly one board cell. Heads are drawn like the
+// rest of the body unless they're given a glyph of their own.
+//
+// Compact boards draw each cell one character wide, with their own set of
+// glyphs set the same way after the word compact:
+//   compact food "o"
+//
+// Lines that are wrong are warned about and leave their glyph as it was.
+
+use crate::diagnostic::{self, did_you_mean};
+use std::fs;
+
+// The names of the glyphs a theme can set
+const GLYPH_NAMES: [&str; 5] = ["empty", "food", "body", "head", "wall"];
+
+// How many characters wide each board cell is drawn
+pub const CELL_WIDTH: usize = 2;
+
+// The glyphs for one width of cell
+#[derive(Clone)]
+pub struct Glyphs {
+    pub empty: String,
+    pub food: String,
+    pub body: String,
+    pub head: Option<String>,
+    pub wall: String,
+}
+
+impl Glyphs {
+    fn new(empty: &str, food: &str, body: &str, wall: &str) -> Self {
+        Self {
+            empty: empty.to_string(),
+            food: food.to_string(),
+            body: body.to_string(),
+            head: None,
+            wall: wall.to_string(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Theme {
+    pub wide: Glyphs,
+    pub compact: Glyphs,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            wide: Glyphs::new("  ", "><", "██", "▒▒"),
+            compact: Glyphs::new(" ", "o", "█", "▒"),
+        }
+    }
+}
+
+impl Theme {
+    // Read and parse a theme file
+    // Returns the theme with a warning for every line that was left out
+    pub fn load(path: &str) -> Result<(Self, Vec<String>), String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        let (theme, problems) = Self::parse(&text);
+        let warnings = problems
+            .iter()
+            .map(|problem| diagnostic::warning(path, problem))
+            .collect();
+        Ok((theme, warnings))
+    }
+
+    // Parse a theme, saying what's wrong with each line that can't be
+    pub fn parse(text: &str) -> (Self, Vec<String>) {
+        let mut theme = Self::default();
+        let mut problems = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(';') {
+                continue;
+            }
+            if let Err(e) = theme.parse_line(line) {
+                problems.push(format!("line {}: {}", i + 1, e));
+            }
+        }
+        (theme, problems)
+    }
+
+    fn parse_line(&mut self, line: &str) -> Result<(), String> {
+        let (compact, line) = match line.strip_prefix("compact ") {
+            Some(rest) => (true, rest.trim_start()),
+            None => (false, line),
+        };
+        let (glyphs, width) = if compact {
+            (&mut self.compact, 1)
+        } else {
+            (&mut self.wide, CELL_WIDTH)
+        };
+
+        let (name, glyph) = line.split_once(' ').unwrap_or((line, ""));
+        let glyph = glyph
+            .trim()
+            .strip_prefix('"')
+            .and_then(|glyph| glyph.strip_suffix('"'))
+            .ok_or_else(|| "glyphs go in double quotes".to_string())?;
+        if glyph.chars().count() != width {
+            let plural = if width == 1 { "" } else { "s" };
+            return Err(format!(
+                "glyphs must be {} character{} wide, not {}",
+                width,
+                plural,
+                glyph.chars().count()
+            ));
+        }
+
+        let glyph = glyph.to_string();
+        match name {
+            "empty" => glyphs.empty = glyph,
+            "food" => glyphs.food = glyph,
+            "body" => glyphs.body = glyph,
+            "head" => glyphs.head = Some(glyph),
+            "wall" => glyphs.wall = glyph,
+            _ => {
+                return Err(format!(
+                    "unknown glyph {}{}",
+                    name,
+                    did_you_mean(name, &GLYPH_NAMES)
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    pub fn glyphs(&self, compact: bool) -> &Glyphs {
+        if compact {
+            &self.compact
+        } else {
+            &self.wide
+        }
+    }
+}
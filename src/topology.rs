@@ -0,0 +1,338 @@
+// A topology says how the tiles of a board join up: where a snake ends up
+// after one step, and whether it runs off the board doing it
+//
+// Boards are bounded by default. The others are picked on the command line
+// by their keys:
+//   bounded        the edges are walls
+//   torus          every edge wraps round to the one opposite
+//   wrap:<edges>   only the listed edges wrap, like wrap:left,right
+//   hex            odd rows sit half a tile to the right, so each tile
+//                  touches six others: two along its row and two in each of
+//                  the rows above and below. Snakes steer along the
+//                  diagonals to reach those rows.
+//
+// Anything that looks at the tiles around another, or how far apart two
+// are, goes through the topology too, so it sees across edges that wrap.
+
+use crate::game::Direction;
+use std::rc::Rc;
+
+pub type Pos = (i32, i32);
+
+// The key of the topology every board starts with
+pub const DEFAULT_KEY: &str = "bounded";
+
+pub trait Topology {
+    // The width and height of the board it joins up
+    fn size(&self) -> Pos;
+
+    // The name it goes by on the command line and in snapshots
+    fn key(&self) -> String;
+
+    // Whether running off an edge brings a snake back on at the opposite one
+    fn wraps(&self, _edge: Direction) -> bool {
+        false
+    }
+
+    // The ways a snake can go from a tile
+    fn directions(&self) -> &'static [Direction] {
+        &Direction::ALL
+    }
+
+    // Which of those ways steering in a direction means, or None if it
+    // doesn't lead anywhere here
+    fn turn(&self, direction: Direction) -> Option<Direction> {
+        Some(direction).filter(|direction| self.directions().contains(direction))
+    }
+
+    // Whether odd rows are drawn half a tile to the right of even ones
+    fn staggered(&self) -> bool {
+        false
+    }
+
+    // Where a position just off the board ends up, brought back on across
+    // edges that wrap, or None past an edge that doesn't
+    fn settle(&self, (x, y): Pos) -> Option<Pos> {
+        let (width, height) = self.size();
+        let across = |n: i32, size: i32, low: Direction, high: Direction| {
+            if n < 0 && !self.wraps(low) || n >= size && !self.wraps(high) {
+                None
+            } else {
+                Some(n.rem_euclid(size))
+            }
+        };
+        Some((
+            across(x, width, Direction::Left, Direction::Right)?,
+            across(y, height, Direction::Up, Direction::Down)?,
+        ))
+    }
+
+    // The tile one step from `pos` going that way, or None off the board
+    fn step(&self, (x, y): Pos, direction: Direction) -> Option<Pos> {
+        self.settle(direction.step(x, y))
+    }
+
+    // The tiles one step from `pos` in every direction
+    fn neighbours(&self, pos: Pos) -> Vec<Pos> {
+        self.directions()
+            .iter()
+            .filter_map(|&direction| self.step(pos, direction))
+            .collect()
+    }
+
+    // The shortest way across the board from one tile to another, going
+    // over an edge that wraps when that's nearer
+    fn offset(&self, from: Pos, to: Pos) -> Pos {
+        let (width, height) = self.size();
+        let shortest = |d: i32, size: i32, low: Direction, high: Direction| {
+            let across = if d > 0 && self.wraps(low) {
+                d - size
+            } else if d < 0 && self.wraps(high) {
+                d + size
+            } else {
+                d
+            };
+            if across.abs() < d.abs() {
+                across
+            } else {
+                d
+            }
+        };
+        (
+            shortest(to.0 - from.0, width, Direction::Left, Direction::Right),
+            shortest(to.1 - from.1, height, Direction::Up, Direction::Down),
+        )
+    }
+}
+
+// Make the topology a key names for a board of that size
+pub fn from_key(key: &str, width: i32, height: i32) -> Option<Rc<dyn Topology>> {
+    let size = (width, height);
+    let topology: Rc<dyn Topology> = match key {
+        "bounded" => Rc::new(Bounded { size }),
+        "torus" => Rc::new(Torus { size }),
+        "hex" => Rc::new(Hex { size }),
+        _ => {
+            let edges = key
+                .strip_prefix("wrap:")?
+                .split(',')
+                .map(edge_from_key)
+                .collect::<Option<Vec<_>>>()?;
+            Rc::new(Edges {
+                size,
+                wrapped: edges,
+            })
+        }
+    };
+    Some(topology)
+}
+
+// The edges of the board are named for the way a snake leaves through them
+fn edge_key(edge: Direction) -> &'static str {
+    match edge {
+        Direction::Up => "top",
+        Direction::Down => "bottom",
+        Direction::Left => "left",
+        Direction::Right => "right",
+        // Diagonals aren't edges, so they never wrap
+        other => other.key(),
+    }
+}
+
+fn edge_from_key(key: &str) -> Option<Direction> {
+    Direction::ALL
+        .iter()
+        .copied()
+        .find(|&edge| edge_key(edge) == key)
+}
+
+pub struct Bounded {
+    size: Pos,
+}
+
+impl Topology for Bounded {
+    fn size(&self) -> Pos {
+        self.size
+    }
+
+    fn key(&self) -> String {
+        "bounded".to_string()
+    }
+}
+
+pub struct Torus {
+    size: Pos,
+}
+
+impl Topology for Torus {
+    fn size(&self) -> Pos {
+        self.size
+    }
+
+    fn key(&self) -> String {
+        "torus".to_string()
+    }
+
+    fn wraps(&self, _edge: Direction) -> bool {
+        true
+    }
+}
+
+// Some edges wrapping and the rest walls
+pub struct Edges {
+    size: Pos,
+    wrapped: Vec<Direction>,
+}
+
+impl Topology for Edges {
+    fn size(&self) -> Pos {
+        self.size
+    }
+
+    fn key(&self) -> String {
+        let edges: Vec<&str> = self.wrapped.iter().map(|&edge| edge_key(edge)).collect();
+        format!("wrap:{}", edges.join(","))
+    }
+
+    fn wraps(&self, edge: Direction) -> bool {
+        self.wrapped.contains(&edge)
+    }
+}
+
+// Rows of hexagons, each odd one shifted half a tile right
+pub struct Hex {
+    size: Pos,
+}
+
+impl Topology for Hex {
+    fn size(&self) -> Pos {
+        self.size
+    }
+
+    fn key(&self) -> String {
+        "hex".to_string()
+    }
+
+    fn directions(&self) -> &'static [Direction] {
+        &Direction::HEX
+    }
+
+    // Steering straight up or down leans left going up and right going
+    // down, so the two still lead opposite ways
+    fn turn(&self, direction: Direction) -> Option<Direction> {
+        Some(match direction {
+            Direction::Up => Direction::UpLeft,
+            Direction::Down => Direction::DownRight,
+            other => other,
+        })
+    }
+
+    fn staggered(&self) -> bool {
+        true
+    }
+
+    // The rows above and below an odd row are further left of it than the
+    // ones around an even row
+    fn step(&self, (x, y): Pos, direction: Direction) -> Option<Pos> {
+        let odd = y.rem_euclid(2) == 1;
+        let pos = match self.turn(direction)? {
+            Direction::UpLeft if !odd => (x - 1, y - 1),
+            Direction::UpRight if odd => (x + 1, y - 1),
+            Direction::DownLeft if !odd => (x - 1, y + 1),
+            Direction::DownRight if odd => (x + 1, y + 1),
+            Direction::UpLeft | Direction::UpRight => (x, y - 1),
+            Direction::DownLeft | Direction::DownRight => (x, y + 1),
+            direction => direction.step(x, y),
+        };
+        self.settle(pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn topology(key: &str) -> Rc<dyn Topology> {
+        from_key(key, 5, 4).unwrap()
+    }
+
+    #[test]
+    fn keys_name_the_topology_back() {
+        for key in ["bounded", "torus", "hex", "wrap:left,right", "wrap:top"] {
+            assert_eq!(topology(key).key(), key);
+        }
+        assert!(from_key("wrap:sideways", 5, 4).is_none());
+    }
+
+    #[test]
+    fn steps_off_the_board_only_wrap_where_the_edge_does() {
+        let bounded = topology("bounded");
+        assert_eq!(bounded.step((4, 0), Direction::Right), None);
+        assert_eq!(bounded.step((3, 0), Direction::Right), Some((4, 0)));
+
+        let torus = topology("torus");
+        assert_eq!(torus.step((4, 0), Direction::Right), Some((0, 0)));
+        assert_eq!(torus.step((0, 0), Direction::Up), Some((0, 3)));
+
+        let sides = topology("wrap:left,right");
+        assert_eq!(sides.step((0, 2), Direction::Left), Some((4, 2)));
+        assert_eq!(sides.step((0, 0), Direction::Up), None);
+    }
+
+    #[test]
+    fn corners_have_neighbours_across_wrapping_edges() {
+        assert_eq!(topology("bounded").neighbours((0, 0)).len(), 2);
+        let mut neighbours = topology("torus").neighbours((0, 0));
+        neighbours.sort();
+        assert_eq!(neighbours, vec![(0, 1), (0, 3), (1, 0), (4, 0)]);
+    }
+
+    #[test]
+    fn offsets_take_the_short_way_round() {
+        assert_eq!(topology("bounded").offset((0, 0), (4, 3)), (4, 3));
+        assert_eq!(topology("torus").offset((0, 0), (4, 3)), (-1, -1));
+        assert_eq!(topology("torus").offset((4, 3), (0, 0)), (1, 1));
+        assert_eq!(topology("wrap:left").offset((0, 0), (4, 3)), (-1, 3));
+        // Half way round is as near one way as the other
+        assert_eq!(topology("torus").offset((0, 0), (0, 2)), (0, 2));
+    }
+
+    #[test]
+    fn hex_tiles_touch_six_others() {
+        let hex = topology("hex");
+        let around = |pos: Pos| {
+            let mut neighbours = hex.neighbours(pos);
+            neighbours.sort();
+            neighbours
+        };
+        // Odd rows lean right of the rows around them, even rows left
+        assert_eq!(
+            around((2, 1)),
+            vec![(1, 1), (2, 0), (2, 2), (3, 0), (3, 1), (3, 2)]
+        );
+        assert_eq!(
+            around((2, 2)),
+            vec![(1, 1), (1, 2), (1, 3), (2, 1), (2, 3), (3, 2)]
+        );
+        assert_eq!(around((0, 0)), vec![(0, 1), (1, 0)]);
+        assert_eq!(hex.step((0, 2), Direction::UpLeft), None);
+        assert_eq!(hex.step((4, 1), Direction::DownRight), None);
+    }
+
+    #[test]
+    fn hex_boards_steer_along_the_diagonals() {
+        let hex = topology("hex");
+        assert!(hex.turn(Direction::Up) == Some(Direction::UpLeft));
+        assert!(hex.turn(Direction::Down) == Some(Direction::DownRight));
+        assert!(hex.turn(Direction::DownLeft) == Some(Direction::DownLeft));
+        assert_eq!(hex.step((2, 2), Direction::Up), Some((1, 1)));
+        // Every step can be taken back the opposite way
+        for &direction in hex.directions() {
+            for pos in [(2, 1), (2, 2)] {
+                let there = hex.step(pos, direction).unwrap();
+                assert_eq!(hex.step(there, direction.opposite()), Some(pos));
+            }
+        }
+        assert!(topology("bounded").turn(Direction::UpLeft).is_none());
+    }
+}
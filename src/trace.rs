@@ -0,0 +1,111 @@
+// A trace writes a line to a file for everything that happens in a game, to
+// look back over a run afterwards
+//
+// It's hooked into the game rather than built in, so the game itself doesn't
+// know it's being watched. Traces are always written in English, whatever
+// language the game is shown in.
+
+use crate::event::{DeathCause, GameEvent};
+use crate::game::{Direction, FoodKind, Game};
+use crate::hooks::Hooks;
+use std::fs::File;
+use std::io::Write;
+
+pub struct Trace {
+    file: File,
+    ticks: u32, // updates seen, across every level played
+}
+
+impl Trace {
+    pub fn create(path: &str) -> Result<Self, String> {
+        let file = File::create(path).map_err(|e| format!("{}: {}", path, e))?;
+        Ok(Self { file, ticks: 0 })
+    }
+
+    // A trace that can't be written to shouldn't stop the game, so it
+    // carries on without the line
+    fn write(&mut self, line: String) {
+        let _ = writeln!(self.file, "{:>6} {}", self.ticks, line);
+    }
+}
+
+impl Hooks for Trace {
+    fn before_tick(&mut self, _game: &mut Game) {
+        self.ticks += 1;
+    }
+
+    fn after_tick(&mut self, game: &mut Game) {
+        for &event in game.happened() {
+            self.write(describe(event));
+        }
+    }
+
+    fn on_spawn_food(&mut self, _game: &mut Game, x: i32, y: i32, kind: FoodKind) {
+        self.write(format!("{} food at {},{}", food_name(kind), x, y));
+    }
+}
+
+fn describe(event: GameEvent) -> String {
+    match event {
+        GameEvent::FoodEaten {
+            snake,
+            kind,
+            points,
+        } => format!(
+            "snake {} ate {} food for {}",
+            snake + 1,
+            food_name(kind),
+            points
+        ),
+        GameEvent::Moved { snake, x, y } => format!("snake {} moved to {},{}", snake + 1, x, y),
+        GameEvent::Turned { snake, from, to } => format!(
+            "snake {} turned from {} to {}",
+            snake + 1,
+            direction_name(from),
+            direction_name(to)
+        ),
+        GameEvent::Died { snake, cause } => {
+            format!("snake {} died: {}", snake + 1, cause_name(cause))
+        }
+        GameEvent::PowerUpStarted { snake, power_up } => {
+            format!("snake {} started {}", snake + 1, power_up.key())
+        }
+        GameEvent::PowerUpEnded { snake, power_up } => {
+            format!("snake {} ran out of {}", snake + 1, power_up.key())
+        }
+        GameEvent::Won(condition) => format!("won, {}", condition.to_text()),
+    }
+}
+
+fn food_name(kind: FoodKind) -> String {
+    match kind {
+        FoodKind::Number(n) => format!("number {}", n),
+        _ => kind.key().to_string(),
+    }
+}
+
+fn direction_name(direction: Direction) -> &'static str {
+    match direction {
+        Direction::Up => "up",
+        Direction::Down => "down",
+        Direction::Left => "left",
+        Direction::Right => "right",
+        Direction::UpLeft => "up and left",
+        Direction::UpRight => "up and right",
+        Direction::DownLeft => "down and left",
+        Direction::DownRight => "down and right",
+    }
+}
+
+fn cause_name(cause: DeathCause) -> String {
+    match cause {
+        DeathCause::Edge => "the edge".to_string(),
+        DeathCause::Wall => "a wall".to_string(),
+        DeathCause::Body(owner) => format!("the body of snake {}", owner + 1),
+        DeathCause::HeadOn => "head-on".to_string(),
+        DeathCause::Obstacle => "an obstacle".to_string(),
+        DeathCause::Poison => "poison".to_string(),
+        DeathCause::Zone => "the zone".to_string(),
+        DeathCause::Beaten => "beaten".to_string(),
+    }
+}
@@ -0,0 +1,194 @@
+// Split-screen versus, where each player races on a board of their own
+//
+// The boards are set up with the same seed, so they start out the same and
+// serve the same food in the same order. The first player to reach the
+// target score wins and the first to die loses. If time runs out first, the
+// higher score wins.
+
+use crate::game::Game;
+use crate::layout;
+use crate::locale::tr;
+use crate::win::{WinCondition, TICKS_PER_SECOND};
+
+// How long a race lasts before the scores decide it
+const RACE_SECONDS: u32 = 180;
+
+// How a race was decided
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Reached(usize),   // the player got to the target score first
+    Outlasted(usize), // the other player died first
+    OnTime(usize),    // the player was ahead when time ran out
+    Draw,
+}
+
+impl Outcome {
+    // What happened, for the game over screen
+    pub fn describe(self, target: i32) -> String {
+        match self {
+            Outcome::Reached(player) => tr("versus.reached", &[&(player + 1), &target]),
+            Outcome::Outlasted(player) => tr("versus.outlasted", &[&(player + 1)]),
+            Outcome::OnTime(player) => tr("versus.on-time", &[&(player + 1)]),
+            Outcome::Draw => tr("draw", &[]),
+        }
+    }
+}
+
+pub struct Versus {
+    boards: Vec<Game>, // one per player, each with a single snake
+    target: i32,
+    ticks_left: u32,
+}
+
+impl Versus {
+    // Race on boards set up the same way, one per player
+    pub fn new(mut boards: Vec<Game>, target: i32) -> Self {
+        for board in &mut boards {
+            board.add_win_condition(WinCondition::Score(target));
+        }
+        Self {
+            boards,
+            target,
+            ticks_left: RACE_SECONDS * TICKS_PER_SECOND,
+        }
+    }
+
+    pub fn target(&self) -> i32 {
+        self.target
+    }
+
+    pub fn boards(&self) -> &[Game] {
+        &self.boards
+    }
+
+    pub fn board_mut(&mut self, player: usize) -> &mut Game {
+        &mut self.boards[player]
+    }
+
+    // Move every board on a tick together
+    pub fn update(&mut self) {
+        for board in &mut self.boards {
+            board.update();
+        }
+        self.ticks_left = self.ticks_left.saturating_sub(1);
+    }
+
+    // How the race went, once it's decided
+    pub fn outcome(&self) -> Option<Outcome> {
+        let reached: Vec<usize> = self.players(|board| board.solved());
+        let standing: Vec<usize> = self.players(|board| !board.finished());
+
+        // Boards can finish on the same tick, and then the score decides
+        match (reached.as_slice(), standing.as_slice()) {
+            ([player], _) => Some(Outcome::Reached(*player)),
+            ([], [player]) => Some(Outcome::Outlasted(*player)),
+            ([], [_, _, ..]) if self.ticks_left > 0 => None,
+            _ => Some(self.leader().map_or(Outcome::Draw, Outcome::OnTime)),
+        }
+    }
+
+    fn players(&self, filter: impl Fn(&Game) -> bool) -> Vec<usize> {
+        (0..self.boards.len())
+            .filter(|&player| filter(&self.boards[player]))
+            .collect()
+    }
+
+    // The player with the highest score, unless it's shared
+    fn leader(&self) -> Option<usize> {
+        let best = self.boards.iter().map(|board| board.score()).max()?;
+        match self.players(|board| board.score() == best).as_slice() {
+            [player] => Some(*player),
+            _ => None,
+        }
+    }
+
+    // Draw the boards side by side in the middle of the terminal, each with
+    // its player's HUD beneath it, then the race clock and any footer lines
+    pub fn render_ascii(&self, footer: &[String]) {
+        let columns: Vec<_> = self
+            .boards
+            .iter()
+            .enumerate()
+            .map(|(player, board)| board.column(&tr("player", &[&(player + 1)])))
+            .collect();
+        let mut lines = vec![
+            String::new(),
+            format!(
+                "  {}",
+                tr(
+                    "versus.clock",
+                    &[&self.target, &self.ticks_left.div_ceil(TICKS_PER_SECOND)]
+                )
+            ),
+        ];
+        lines.extend_from_slice(footer);
+        layout::draw_columns(&columns, &lines);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::test_board;
+
+    // Food straight ahead, then a wall
+    const FOOD: &str = "#\n*\n@\no\n";
+
+    // Nothing ahead, then a wall
+    const NO_FOOD: &str = "#\n.\n@\no\n*\n";
+
+    // Open board all the way up
+    const OPEN: &str = ".\n.\n.\n.\n@\no\n*\n";
+
+    fn race(boards: [&str; 2], target: i32) -> Versus {
+        Versus::new(boards.iter().map(|text| test_board(text)).collect(), target)
+    }
+
+    #[test]
+    fn races_go_on_while_both_are_standing() {
+        let mut versus = race([OPEN, OPEN], 100);
+        versus.update();
+        assert!(versus.outcome().is_none());
+    }
+
+    #[test]
+    fn reaching_the_target_wins() {
+        let mut versus = race([NO_FOOD, FOOD], 1);
+        versus.update();
+        assert!(versus.outcome() == Some(Outcome::Reached(1)));
+    }
+
+    #[test]
+    fn dying_first_loses() {
+        let mut versus = race([NO_FOOD, OPEN], 100);
+        versus.update();
+        versus.update();
+        assert!(versus.outcome() == Some(Outcome::Outlasted(1)));
+    }
+
+    #[test]
+    fn the_score_decides_when_both_finish_together() {
+        let mut versus = race([FOOD, NO_FOOD], 100);
+        versus.update();
+        versus.update();
+        assert!(versus.outcome() == Some(Outcome::OnTime(0)));
+
+        let mut versus = race([NO_FOOD, NO_FOOD], 100);
+        versus.update();
+        versus.update();
+        assert!(versus.outcome() == Some(Outcome::Draw));
+    }
+
+    #[test]
+    fn the_score_decides_when_time_runs_out() {
+        let mut versus = race([OPEN, OPEN], 100);
+        versus.update();
+        versus.ticks_left = 0;
+        assert!(versus.outcome() == Some(Outcome::Draw));
+
+        let mut versus = race(["..\n*.\n@.\no.\n..\n", OPEN], 100);
+        versus.update();
+        versus.ticks_left = 0;
+        assert!(versus.outcome() == Some(Outcome::OnTime(0)));
+    }
+}
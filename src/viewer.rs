@@ -0,0 +1,218 @@
+// The replay viewer plays a replay back on screen, with a progress bar
+// beneath the board and keys for moving about in it:
+//   space  pause or play on        , .  step back or on one update
+//   [ ]    slow down or speed up    n    jump to the next food eaten
+//   g      jump to an update, typed in and confirmed with enter
+//   + -    zoom in or out           q    quit
+//
+// The whole replay is played through once before it's shown, to know how
+// long it is and where the food is eaten. Seeking back plays it again
+// from the seed on a new board.
+
+use crate::event::GameEvent;
+use crate::game::Game;
+use crate::locale::tr;
+use crate::replay::Replay;
+use crate::win::TICKS_PER_SECOND;
+use crate::QUIT_KEYS;
+use crossterm::terminal;
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+// The speeds playback can run at, as multiples of the normal game speed
+const SPEEDS: [f32; 6] = [0.25, 0.5, 1.0, 2.0, 4.0, 8.0];
+
+// Playback starts out at the normal game speed
+const NORMAL_SPEED: usize = 2;
+
+// How many characters across the progress bar is
+const PROGRESS_WIDTH: usize = 40;
+
+// How often to check for keys and a resized terminal while paused
+const IDLE_WAIT: Duration = Duration::from_millis(100);
+
+struct Viewer<'a, F> {
+    board: F, // a new board laid out from the replay's seed
+    replay: &'a Replay,
+    game: Game,
+    end: u32,        // the update the replay is over on
+    meals: Vec<u32>, // the updates the snake ate on
+    speed: usize,    // which of SPEEDS playback runs at
+    paused: bool,
+    typed: Option<String>, // an update being typed in to jump to
+}
+
+impl<'a, F: Fn() -> Game> Viewer<'a, F> {
+    // Play the replay through until it's over, or `limit` updates in
+    fn new(board: F, replay: &'a Replay, limit: u32) -> Self {
+        let mut game = board();
+        let mut meals = Vec::new();
+        while !game.finished() && game.ticks() < limit {
+            replay.step(&mut game);
+            let ate = game
+                .happened()
+                .iter()
+                .any(|event| matches!(event, GameEvent::FoodEaten { snake: 0, .. }));
+            if ate {
+                meals.push(game.ticks());
+            }
+        }
+        Self {
+            game: board(),
+            board,
+            replay,
+            end: game.ticks(),
+            meals,
+            speed: NORMAL_SPEED,
+            paused: false,
+            typed: None,
+        }
+    }
+
+    // Show the game as it is `tick` updates in
+    fn seek(&mut self, tick: u32) {
+        let tick = tick.min(self.end);
+        if tick < self.game.ticks() {
+            let zoom = self.game.zoom();
+            self.game = (self.board)();
+            self.game.set_zoom(zoom);
+        }
+        self.replay.seek(&mut self.game, tick);
+    }
+
+    // How long each update is shown for at the speed playback runs at
+    fn tick_length(&self) -> Duration {
+        let speed = SPEEDS[self.speed] * self.game.speed();
+        Duration::from_secs_f32(1.0 / (TICKS_PER_SECOND as f32 * speed))
+    }
+
+    fn playing(&self) -> bool {
+        !self.paused && self.typed.is_none() && self.game.ticks() < self.end
+    }
+
+    // Act on a key, returning false if it quits the viewer
+    fn key(&mut self, key: char) -> bool {
+        if let Some(typed) = &mut self.typed {
+            match key {
+                '0'..='9' => typed.push(key),
+                '\x7f' | '\x08' => {
+                    typed.pop();
+                }
+                '\r' | '\n' => {
+                    let tick = typed.parse().unwrap_or(self.game.ticks());
+                    self.typed = None;
+                    self.seek(tick);
+                }
+                '\x1b' => self.typed = None,
+                _ if QUIT_KEYS.contains(&key) => return false,
+                _ => (),
+            }
+            return true;
+        }
+
+        let now = self.game.ticks();
+        match key {
+            _ if QUIT_KEYS.contains(&key) => return false,
+            ' ' => self.paused = !self.paused,
+            ',' => {
+                self.paused = true;
+                self.seek(now.saturating_sub(1));
+            }
+            '.' => {
+                self.paused = true;
+                self.seek(now + 1);
+            }
+            '[' => self.speed = self.speed.saturating_sub(1),
+            ']' => self.speed = (self.speed + 1).min(SPEEDS.len() - 1),
+            'n' => {
+                if let Some(&meal) = self.meals.iter().find(|&&meal| meal > now) {
+                    self.seek(meal);
+                }
+            }
+            'g' => self.typed = Some(String::new()),
+            '+' | '=' => self.game.set_zoom(self.game.zoom().zoomed_in()),
+            '-' => self.game.set_zoom(self.game.zoom().zoomed_out()),
+            _ => (),
+        }
+        true
+    }
+
+    // The progress bar and what the viewer is doing, beneath the HUD
+    fn footer(&self) -> Vec<String> {
+        let now = self.game.ticks();
+        let filled = (now as usize * PROGRESS_WIDTH)
+            .checked_div(self.end as usize)
+            .unwrap_or(PROGRESS_WIDTH);
+        let progress = format!(
+            "  [{}{}]  {}",
+            "#".repeat(filled),
+            ".".repeat(PROGRESS_WIDTH - filled),
+            tr("viewer.tick", &[&now, &self.end])
+        );
+
+        let status = if let Some(typed) = &self.typed {
+            tr("viewer.go-to", &[typed])
+        } else {
+            let state = if now >= self.end {
+                tr("viewer.over", &[])
+            } else if self.paused {
+                tr("viewer.paused", &[])
+            } else {
+                tr("viewer.playing", &[])
+            };
+            format!("{}  {}", state, tr("viewer.speed", &[&SPEEDS[self.speed]]))
+        };
+
+        vec![
+            String::new(),
+            progress,
+            format!("  {}", status),
+            format!("  {}", tr("viewer.keys", &[])),
+        ]
+    }
+
+    fn draw(&self) {
+        let title = tr("title.replay", &[&self.game.score(), &self.game.length()]);
+        crate::set_title(&title);
+        self.game.render_ascii(&self.footer());
+    }
+}
+
+// Watch a replay until the viewer quits, on boards made by `board`
+// Replays are only followed for `limit` updates
+pub fn run(board: impl Fn() -> Game, replay: &Replay, keys: &Receiver<char>, limit: u32) {
+    let mut viewer = Viewer::new(board, replay, limit);
+    viewer.draw();
+
+    let mut last_update = Instant::now();
+    let mut size = terminal::size().ok();
+    loop {
+        // Wait for a key until the next update is due
+        let wait = if viewer.playing() {
+            viewer.tick_length().saturating_sub(last_update.elapsed())
+        } else {
+            IDLE_WAIT
+        };
+        match keys.recv_timeout(wait) {
+            Ok(key) => {
+                if !viewer.key(key) {
+                    return;
+                }
+                viewer.draw();
+                continue;
+            }
+            Err(RecvTimeoutError::Timeout) => (),
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+
+        if terminal::size().ok() != size {
+            size = terminal::size().ok();
+            viewer.draw();
+        }
+        if viewer.playing() && last_update.elapsed() >= viewer.tick_length() {
+            last_update = Instant::now();
+            replay.step(&mut viewer.game);
+            viewer.draw();
+        }
+    }
+}
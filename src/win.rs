@@ -0,0 +1,72 @@
+// Win conditions end a game in the first player's favour once they're met
+// Modes and levels can each set their own, and meeting any one of them wins
+
+use crate::locale::tr;
+
+// The game runs this many ticks a second at normal speed
+pub const TICKS_PER_SECOND: u32 = 10;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum WinCondition {
+    ReachLength(i32),
+    Score(i32),
+    Survive(u32), // seconds
+    ClearFood,    // every piece of a level's fixed food
+    EatFood(u32),
+}
+
+impl WinCondition {
+    // Parse the words after `win` in a level file, like `length 20`
+    pub fn parse(words: &[&str]) -> Result<Self, String> {
+        let number = |n: &str| {
+            n.parse::<u32>()
+                .ok()
+                .filter(|&n| n > 0)
+                .ok_or_else(|| format!("{} must be a positive number", words[0]))
+        };
+        match words {
+            ["length", n] => Ok(WinCondition::ReachLength(number(n)? as i32)),
+            ["score", n] => Ok(WinCondition::Score(number(n)? as i32)),
+            ["survive", n] => Ok(WinCondition::Survive(number(n)?)),
+            ["food", n] => Ok(WinCondition::EatFood(number(n)?)),
+            ["clear"] => Ok(WinCondition::ClearFood),
+            _ => {
+                Err("win must be length, score, survive or food and a number, or clear".to_string())
+            }
+        }
+    }
+
+    // The words the condition is written as in a level file
+    pub fn to_text(self) -> String {
+        match self {
+            WinCondition::ReachLength(length) => format!("length {}", length),
+            WinCondition::Score(score) => format!("score {}", score),
+            WinCondition::Survive(seconds) => format!("survive {}", seconds),
+            WinCondition::EatFood(food) => format!("food {}", food),
+            WinCondition::ClearFood => "clear".to_string(),
+        }
+    }
+
+    // What has to be done, for the HUD
+    pub fn goal(self) -> String {
+        match self {
+            WinCondition::ReachLength(length) => tr("goal.length", &[&length]),
+            WinCondition::Score(score) => tr("goal.score", &[&score]),
+            WinCondition::Survive(seconds) => tr("goal.survive", &[&seconds]),
+            WinCondition::EatFood(food) => tr("goal.food", &[&food]),
+            WinCondition::ClearFood => tr("goal.clear", &[]),
+        }
+    }
+
+    // What was done, for the victory screen
+    pub fn describe(self) -> String {
+        match self {
+            WinCondition::ReachLength(length) => tr("won.length", &[&length]),
+            WinCondition::Score(score) => tr("won.score", &[&score]),
+            WinCondition::Survive(1) => tr("won.survive-one", &[]),
+            WinCondition::Survive(seconds) => tr("won.survive", &[&seconds]),
+            WinCondition::EatFood(food) => tr("won.food", &[&food]),
+            WinCondition::ClearFood => tr("won.clear", &[]),
+        }
+    }
+}